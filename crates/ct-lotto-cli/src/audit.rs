@@ -0,0 +1,125 @@
+use std::path::Path;
+
+use anchor_lang::Discriminator;
+use anyhow::{anyhow, bail, Result};
+use base64::Engine;
+use ct_lotto_client::audit::{
+    audit_snapshot, encode_binary_snapshot, parse_binary_snapshot, parse_json_snapshot,
+    SnapshotAccount,
+};
+use ct_lotto_client::state::deserialize_lottery;
+use ct_lotto_client::{Lottery, PROGRAM_ID};
+use solana_account_decoder_client_types::UiAccountEncoding;
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_rpc_client_api::config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+
+/// Writes every program account, plus the vaults of token lotteries, to `out`.
+/// JSON mirrors the `getProgramAccounts` result; anything else is the binary form.
+pub fn write_snapshot(rpc: &RpcClient, out: &Path, json: bool) -> Result<()> {
+    let config = RpcProgramAccountsConfig {
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+
+    let mut accounts: Vec<SnapshotAccount> = rpc
+        .get_program_accounts_with_config(&PROGRAM_ID, config)?
+        .into_iter()
+        .map(|(pubkey, account)| SnapshotAccount {
+            pubkey,
+            owner: account.owner,
+            lamports: account.lamports,
+            data: account.data,
+        })
+        .collect();
+
+    let vaults: Vec<_> = accounts
+        .iter()
+        .filter(|a| a.data.starts_with(Lottery::DISCRIMINATOR))
+        .filter_map(|a| deserialize_lottery(&a.data).ok())
+        .filter(|lottery| lottery.is_token_lottery())
+        .map(|lottery| lottery.payment_vault)
+        .collect();
+
+    for (pubkey, account) in vaults.iter().zip(rpc.get_multiple_accounts(&vaults)?) {
+        if let Some(account) = account {
+            accounts.push(SnapshotAccount {
+                pubkey: *pubkey,
+                owner: account.owner,
+                lamports: account.lamports,
+                data: account.data,
+            });
+        }
+    }
+
+    let bytes = if json {
+        let entries: Vec<_> = accounts
+            .iter()
+            .map(|a| {
+                serde_json::json!({
+                    "pubkey": a.pubkey.to_string(),
+                    "account": {
+                        "lamports": a.lamports,
+                        "owner": a.owner.to_string(),
+                        "data": [base64::engine::general_purpose::STANDARD.encode(&a.data), "base64"],
+                    },
+                })
+            })
+            .collect();
+
+        serde_json::to_vec_pretty(&entries)?
+    } else {
+        encode_binary_snapshot(&accounts)
+    };
+
+    std::fs::write(out, bytes)?;
+    println!("{} accounts written to {}", accounts.len(), out.display());
+
+    Ok(())
+}
+
+/// Prints the reconciliation report of a snapshot; fails if any invariant is broken.
+pub fn audit(snapshot: &Path) -> Result<()> {
+    let bytes = std::fs::read(snapshot)?;
+
+    // JSON snapshots always start with `[` or `{`, binary ones with a little-endian length
+    let accounts = match bytes.iter().find(|b| !b.is_ascii_whitespace()) {
+        Some(b'[') | Some(b'{') => parse_json_snapshot(std::str::from_utf8(&bytes)?),
+        _ => parse_binary_snapshot(&bytes),
+    }
+    .map_err(|e| anyhow!(e))?;
+
+    let report = audit_snapshot(&accounts);
+
+    println!("lottery,seed,escrowed,pot_lamports,liabilities,tickets_sold,bundle_tickets,bundle_count,bundles_found,bundles_closed,refunds_settled,refunded_bundles");
+
+    for l in report.lotteries.iter() {
+        println!(
+            "{},{},{},{},{},{},{},{},{},{},{},{}",
+            l.lottery,
+            l.lottery_seed,
+            l.escrowed.map(|e| e.to_string()).unwrap_or_default(),
+            l.pot_lamports,
+            l.liabilities.map(|l| l.to_string()).unwrap_or_default(),
+            l.tickets_sold,
+            l.bundle_tickets,
+            l.bundle_count,
+            l.bundles_found,
+            l.bundles_closed,
+            l.refunds_settled,
+            l.refunded_bundles
+        );
+    }
+
+    for finding in report.findings.iter() {
+        eprintln!("{:?} {}: {}", finding.kind, finding.account, finding.detail);
+    }
+
+    if !report.is_clean() {
+        bail!("{} invariant violations", report.findings.len());
+    }
+
+    Ok(())
+}
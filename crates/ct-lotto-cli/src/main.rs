@@ -0,0 +1,1211 @@
+//! `ct-lotto`: operator CLI for the ct-lotto-anchor program.
+mod audit;
+mod proof;
+mod rpc;
+mod tx;
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anchor_lang::AnchorDeserialize;
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine;
+use clap::{Args, Parser, Subcommand};
+use ct_lotto_client::instructions::{self, CreateLotteryArgs};
+use ct_lotto_client::intent::intent_message;
+use ct_lotto_client::pda::{lottery_pda, series_pda, series_round_pda};
+use ct_lotto_client::settlement::{expected_payouts, expected_refund, tally_for_draw};
+use ct_lotto_client::ticket::{ticket_code_to_hex, ticket_codes_from_hex};
+use ct_lotto_client::{
+    GameType, LotteryTemplate, PrizeTier, PurchaseIntent, QuickPickSource, TokenPayment,
+};
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::hash::{hash, Hash};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair};
+use solana_sdk::signer::Signer;
+
+use crate::rpc::{
+    fetch_bundles, fetch_configuration, fetch_lottery, fetch_player_account, fetch_series,
+    fetch_ticket_registry, mint_payment, token_payment,
+};
+use crate::tx::TxSender;
+
+#[derive(Parser)]
+#[command(
+    name = "ct-lotto",
+    about = "Operator CLI for the ct-lotto-anchor program"
+)]
+struct Cli {
+    /// RPC endpoint.
+    #[arg(
+        long,
+        global = true,
+        env = "CT_LOTTO_RPC_URL",
+        default_value = "https://api.devnet.solana.com"
+    )]
+    url: String,
+
+    /// Admin keypair (defaults to ~/.config/solana/id.json).
+    #[arg(long, global = true, env = "CT_LOTTO_KEYPAIR")]
+    keypair: Option<PathBuf>,
+
+    /// Print serialized transactions (base64) instead of sending them.
+    #[arg(long, global = true)]
+    dump_tx: bool,
+
+    /// Admin / fee payer pubkey, for --dump-tx without a local keypair.
+    #[arg(long, global = true)]
+    authority: Option<Pubkey>,
+
+    /// Recent blockhash to use, so --dump-tx needs no RPC round-trip for it.
+    #[arg(long, global = true)]
+    blockhash: Option<Hash>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+/// Settings shared by standalone lotteries and series rounds.
+#[derive(Args)]
+struct TemplateArgs {
+    /// Price per ticket in lamports (token base units for token lotteries).
+    #[arg(long)]
+    price: u64,
+    #[arg(long)]
+    start_hex: String,
+    #[arg(long)]
+    end_hex: String,
+    #[arg(long)]
+    fee_percent: u16,
+    #[arg(long)]
+    allow_shared_tickets: bool,
+    /// Prize tier as `match_digits:pool_percentage`, repeatable (strictest first).
+    #[arg(long = "tier")]
+    tiers: Vec<String>,
+    /// Pick the winner by ticket index instead of by code (commit-reveal lotteries only).
+    #[arg(long)]
+    raffle: bool,
+    /// Share of the pool per sequential draw, repeatable.
+    #[arg(long = "draw-percent")]
+    draw_percentages: Vec<u8>,
+    /// Ticket cap of the lottery (0 = the whole code space, or no cap with shared tickets).
+    #[arg(long, default_value_t = 0)]
+    max_tickets: u32,
+    /// Ticket cap per owner (0 = no cap).
+    #[arg(long, default_value_t = 0)]
+    max_tickets_per_wallet: u32,
+    /// Tickets that must be sold for a draw; below it closing sales cancels the lottery.
+    #[arg(long, default_value_t = 0)]
+    min_tickets_to_draw: u32,
+    /// Share of the platform fee paid to referrers, in basis points.
+    #[arg(long, default_value_t = 0)]
+    referral_bps: u16,
+    /// Seconds winners have to claim once the last draw is tallied (0 = no deadline).
+    #[arg(long, default_value_t = 0)]
+    claim_window: i64,
+}
+
+impl TemplateArgs {
+    fn template(self) -> Result<LotteryTemplate> {
+        Ok(LotteryTemplate {
+            lamports_per_ticket: self.price,
+            ticket_code_start_hex: self.start_hex,
+            ticket_code_end_hex: self.end_hex,
+            platform_fee_percentage: self.fee_percent,
+            allow_shared_tickets: self.allow_shared_tickets,
+            prize_tiers: self
+                .tiers
+                .iter()
+                .map(|t| parse_tier(t))
+                .collect::<Result<_>>()?,
+            game_type: if self.raffle {
+                GameType::Raffle
+            } else {
+                GameType::CodeMatch
+            },
+            draw_percentages: self.draw_percentages,
+            max_tickets_total: self.max_tickets,
+            max_tickets_per_wallet: self.max_tickets_per_wallet,
+            min_tickets_to_draw: self.min_tickets_to_draw,
+            referral_bps: self.referral_bps,
+            claim_window: self.claim_window,
+        })
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create the global configuration PDA with the signer as admin.
+    InitConfig,
+
+    /// Propose a new admin (wallet or multisig vault PDA); it takes over once it accepts.
+    ProposeAdmin {
+        #[arg(long)]
+        new_admin: Pubkey,
+    },
+
+    /// Accept the admin role proposed to the signer.
+    AcceptAdmin,
+
+    /// Create a lottery PDA.
+    CreateLottery {
+        #[arg(long)]
+        seed: String,
+        /// Switchboard feed used for the draw.
+        #[arg(long, required_unless_present = "secret_file")]
+        feed: Option<Pubkey>,
+        /// Draw by commit-reveal instead: a fresh secret is written to this file and
+        /// its hash committed; keep it for `reveal-draw`.
+        #[arg(long, conflicts_with = "feed")]
+        secret_file: Option<PathBuf>,
+        #[command(flatten)]
+        template: TemplateArgs,
+        /// SPL mint for token lotteries.
+        #[arg(long)]
+        mint: Option<Pubkey>,
+        /// Token program of `--mint`; read from the mint account when omitted.
+        #[arg(long, requires = "mint")]
+        token_program: Option<Pubkey>,
+    },
+
+    /// Create a recurring SOL lottery series and open its first round.
+    CreateSeries {
+        #[arg(long)]
+        seed: String,
+        /// Switchboard feed every round draws from.
+        #[arg(long)]
+        feed: Pubkey,
+        /// Seconds each round sells tickets for.
+        #[arg(long)]
+        round_duration: i64,
+        #[command(flatten)]
+        template: TemplateArgs,
+    },
+
+    /// Close the current round of a series and open the next one.
+    RollSeries {
+        #[arg(long)]
+        seed: String,
+    },
+
+    /// Carry the unwon prizes of a settled, earlier round into the current round.
+    CarryOver {
+        #[arg(long)]
+        seed: String,
+        #[arg(long)]
+        round: u32,
+    },
+
+    /// Deposit a guaranteed prize into a lottery before its first draw.
+    SeedPrize {
+        #[arg(long)]
+        seed: String,
+        /// Lamports (token base units for token lotteries).
+        #[arg(long)]
+        amount: u64,
+    },
+
+    /// Take the seeded prize of a cancelled lottery back.
+    ReclaimSeed {
+        #[arg(long)]
+        seed: String,
+    },
+
+    /// Stop ticket sales of a lottery.
+    CloseSales {
+        #[arg(long)]
+        seed: String,
+    },
+
+    /// Close a lottery PDA, sweeping any remaining funds to the admin.
+    CloseLottery {
+        #[arg(long)]
+        seed: String,
+    },
+
+    /// List the transaction bundles of a lottery.
+    ListBundles {
+        #[arg(long)]
+        seed: String,
+    },
+
+    /// Compute the expected prize shares of every drawn draw, and refunds.
+    Payouts {
+        #[arg(long)]
+        seed: String,
+    },
+
+    /// Draw the next winning code from the BTC block height read off the feed.
+    Draw {
+        #[arg(long)]
+        seed: String,
+        #[arg(long)]
+        block_height: u64,
+    },
+
+    /// Draw every winning code of a commit-reveal lottery by revealing its secret.
+    RevealDraw {
+        #[arg(long)]
+        seed: String,
+        /// File written by `create-lottery --secret-file`.
+        #[arg(long)]
+        secret_file: PathBuf,
+    },
+
+    /// Cancel a commit-reveal lottery whose draw was not revealed in time, for refunds.
+    CancelExpiredDraw {
+        #[arg(long)]
+        seed: String,
+    },
+
+    /// Tally every bundle against a draw and pay every winning share.
+    Settle {
+        #[arg(long)]
+        seed: String,
+        #[arg(long, default_value_t = 0)]
+        draw_index: u8,
+    },
+
+    /// Refund every bundle of a cancelled lottery.
+    Refund {
+        #[arg(long)]
+        seed: String,
+        #[arg(long)]
+        deduct_fee: bool,
+    },
+
+    /// Withdraw the platform fee of a drawn lottery.
+    WithdrawFees {
+        #[arg(long)]
+        seed: String,
+    },
+
+    /// Sweep prizes left unclaimed after the claim deadline (to the next round of a
+    /// series, or to the treasury).
+    SweepPrizes {
+        #[arg(long)]
+        seed: String,
+    },
+
+    /// Claim the referral earnings of the signer in a drawn lottery.
+    ClaimReferral {
+        #[arg(long)]
+        seed: String,
+    },
+
+    /// Deposit into the signer's player balance, spent by balance and relayed purchases.
+    Deposit {
+        /// Lamports (token base units with `--mint`).
+        #[arg(long)]
+        amount: u64,
+        /// Token of the balance; SOL when omitted.
+        #[arg(long)]
+        mint: Option<Pubkey>,
+    },
+
+    /// Withdraw from the signer's player balance.
+    Withdraw {
+        /// Lamports (token base units with `--mint`).
+        #[arg(long)]
+        amount: u64,
+        /// Token of the balance; SOL when omitted.
+        #[arg(long)]
+        mint: Option<Pubkey>,
+    },
+
+    /// Buy tickets as the signer, paid from their player balance.
+    BuyFromBalance {
+        #[arg(long)]
+        seed: String,
+        /// Ticket code in hex, repeatable.
+        #[arg(long = "ticket", required = true)]
+        tickets: Vec<String>,
+        /// Earns the referral share of the purchase.
+        #[arg(long)]
+        referrer: Option<Pubkey>,
+    },
+
+    /// Buy `count` randomly picked tickets as the signer, paid from their player balance
+    /// (commit-reveal against a recent slot hash).
+    QuickPick {
+        #[arg(long)]
+        seed: String,
+        #[arg(long, default_value_t = 1)]
+        count: u8,
+        /// Earns the referral share of the purchase.
+        #[arg(long)]
+        referrer: Option<Pubkey>,
+    },
+
+    /// Track sold codes of a lottery with unique codes, before any ticket is sold.
+    /// Lotteries and series rounds created by this tool already have one.
+    CreateRegistry {
+        #[arg(long)]
+        seed: String,
+    },
+
+    /// Close the sold-code registry of a closed lottery, refunding its rent.
+    CloseRegistry {
+        #[arg(long)]
+        seed: String,
+    },
+
+    /// Sign a purchase intent as the player and print it (base64) for a relayer.
+    SignIntent {
+        #[arg(long)]
+        seed: String,
+        /// Ticket code in hex, repeatable.
+        #[arg(long = "ticket", required = true)]
+        tickets: Vec<String>,
+        /// Most to pay for all tickets (defaults to the current price).
+        #[arg(long)]
+        max_price: Option<u64>,
+        /// Seconds the intent stays valid.
+        #[arg(long, default_value_t = 600)]
+        expires_in: i64,
+        /// Earns the referral share of the purchase.
+        #[arg(long)]
+        referrer: Option<Pubkey>,
+    },
+
+    /// Submit a signed purchase intent, paying the fees and rent as relayer.
+    RelayIntent {
+        /// Output of `sign-intent`.
+        #[arg(long)]
+        signed: String,
+    },
+
+    /// Save every program account (and token vault) for an offline audit.
+    Snapshot {
+        #[arg(long)]
+        out: PathBuf,
+        /// Write `getProgramAccounts` JSON instead of the binary form.
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Check the reconciliation invariants of a snapshot (JSON or binary).
+    Audit {
+        #[arg(long)]
+        snapshot: PathBuf,
+    },
+
+    /// Export a JSON proof that recomputes a draw from the raw accounts.
+    Prove {
+        #[arg(long)]
+        seed: String,
+        #[arg(long, default_value_t = 0)]
+        draw_index: u8,
+        /// Base64 feed account data captured at the slot the draw read, checked too.
+        #[arg(long)]
+        feed_data: Option<PathBuf>,
+        /// Secret of a commit-reveal lottery, as written by `create-lottery --secret-file`.
+        #[arg(long, requires = "slot_hash")]
+        secret_file: Option<PathBuf>,
+        /// Base64 hash of the draw slot, from the `DrawRevealed` event of the reveal.
+        #[arg(long, requires = "secret_file")]
+        slot_hash: Option<String>,
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+
+    /// Verify a draw proof offline.
+    VerifyProof {
+        #[arg(long)]
+        proof: PathBuf,
+    },
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    // These commands sign nothing
+    match &cli.command {
+        Command::Snapshot { out, json } => {
+            let rpc = RpcClient::new_with_commitment(cli.url, CommitmentConfig::confirmed());
+            return audit::write_snapshot(&rpc, out, *json);
+        }
+        Command::Audit { snapshot } => return audit::audit(snapshot),
+        Command::Prove {
+            seed,
+            draw_index,
+            feed_data,
+            secret_file,
+            slot_hash,
+            out,
+        } => {
+            let rpc = RpcClient::new_with_commitment(cli.url, CommitmentConfig::confirmed());
+            return proof::prove(
+                &rpc,
+                seed,
+                *draw_index,
+                feed_data.as_deref(),
+                secret_file.as_deref().zip(slot_hash.as_deref()),
+                out.as_deref(),
+            );
+        }
+        Command::VerifyProof { proof } => return proof::verify(proof),
+        _ => {}
+    }
+
+    let keypair_path = cli.keypair.clone().or_else(default_keypair_path);
+    let signer = match (&keypair_path, cli.dump_tx && cli.authority.is_some()) {
+        (_, true) => None,
+        (Some(path), false) => Some(
+            read_keypair_file(path)
+                .map_err(|e| anyhow!("cannot read keypair {}: {e}", path.display()))?,
+        ),
+        (None, false) => None,
+    };
+
+    let authority = match (&signer, cli.authority) {
+        (Some(signer), _) => signer.pubkey(),
+        (None, Some(authority)) => authority,
+        (None, None) => bail!("provide --keypair, or --authority with --dump-tx"),
+    };
+
+    let sender = TxSender {
+        rpc: RpcClient::new_with_commitment(cli.url.clone(), CommitmentConfig::confirmed()),
+        signer,
+        authority,
+        dump_tx: cli.dump_tx,
+        blockhash: cli.blockhash,
+    };
+
+    run(cli.command, &sender)
+}
+
+/// Lottery a `--seed` designates: the PDA of a standalone lottery seed, or a lottery
+/// address as is (series rounds, see `create-series` and `roll-series`).
+fn lottery_key(seed: &str) -> Pubkey {
+    seed.parse().unwrap_or_else(|_| lottery_pda(seed).0)
+}
+
+fn default_keypair_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config/solana/id.json"))
+}
+
+/// Writes a fresh commit-reveal secret (base64) to `path` and returns its commitment.
+fn write_draw_secret(path: &Path) -> Result<[u8; 32]> {
+    let secret = Keypair::new().pubkey().to_bytes();
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .with_context(|| format!("cannot create secret file {}", path.display()))?;
+    writeln!(
+        file,
+        "{}",
+        base64::engine::general_purpose::STANDARD.encode(secret)
+    )?;
+
+    Ok(hash(&secret).to_bytes())
+}
+
+fn read_draw_secret(path: &Path) -> Result<[u8; 32]> {
+    let encoded = std::fs::read_to_string(path)
+        .with_context(|| format!("cannot read secret file {}", path.display()))?;
+
+    base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim())
+        .context("secret file must hold base64")?
+        .try_into()
+        .map_err(|_| anyhow!("secret must be 32 bytes"))
+}
+
+fn parse_tier(tier: &str) -> Result<PrizeTier> {
+    let (digits, percentage) = tier
+        .split_once(':')
+        .ok_or_else(|| anyhow!("tier must be `match_digits:pool_percentage`, got {tier}"))?;
+
+    Ok(PrizeTier {
+        match_digits: digits.parse().context("invalid tier match digits")?,
+        pool_percentage: percentage.parse().context("invalid tier percentage")?,
+    })
+}
+
+fn run(command: Command, sender: &TxSender) -> Result<()> {
+    let admin = sender.authority;
+
+    match command {
+        Command::InitConfig => sender.send(
+            "create_configuration_pda",
+            &[instructions::create_configuration_pda(&admin, &admin)],
+        ),
+
+        Command::ProposeAdmin { new_admin } => sender.send(
+            "propose_admin",
+            &[instructions::propose_admin(&admin, &new_admin)],
+        ),
+
+        Command::AcceptAdmin => sender.send("accept_admin", &[instructions::accept_admin(&admin)]),
+
+        Command::CreateLottery {
+            seed,
+            feed,
+            secret_file,
+            template,
+            mint,
+            token_program,
+        } => {
+            let token = match mint {
+                Some(mint) => Some(TokenPayment {
+                    mint,
+                    token_program: match token_program {
+                        Some(program) => program,
+                        None => sender.rpc.get_account(&mint)?.owner,
+                    },
+                }),
+                None => None,
+            };
+
+            let template = template.template()?;
+
+            if template.game_type == GameType::Raffle && feed.is_some() {
+                bail!("raffles are drawn by commit-reveal: pass --secret-file instead of --feed");
+            }
+
+            let unique_codes = !template.allow_shared_tickets;
+            let args = CreateLotteryArgs {
+                lottery_seed: seed.clone(),
+                lamports_per_ticket: template.lamports_per_ticket,
+                start_hex: template.ticket_code_start_hex,
+                end_hex: template.ticket_code_end_hex,
+                fee_percent: template.platform_fee_percentage,
+                allow_shared_tickets: template.allow_shared_tickets,
+                prize_tiers: template.prize_tiers,
+                game_type: template.game_type,
+                draw_percentages: template.draw_percentages,
+                max_tickets_total: template.max_tickets_total,
+                max_tickets_per_wallet: template.max_tickets_per_wallet,
+                min_tickets_to_draw: template.min_tickets_to_draw,
+                referral_bps: template.referral_bps,
+                claim_window: template.claim_window,
+                draw_commitment: secret_file
+                    .map(|path| write_draw_secret(&path))
+                    .transpose()?,
+            };
+
+            let lottery = lottery_pda(&seed).0;
+
+            println!("lottery: {lottery}");
+
+            let mut ixs = vec![instructions::create_lottery_pda(
+                &admin,
+                &admin,
+                feed.as_ref(),
+                token.as_ref(),
+                args,
+            )];
+
+            // Unique codes sell only once their registry exists
+            if unique_codes {
+                ixs.push(instructions::create_ticket_registry(
+                    &admin, &admin, &lottery,
+                ));
+            }
+
+            sender.send("create_lottery_pda", &ixs)
+        }
+
+        Command::CreateSeries {
+            seed,
+            feed,
+            round_duration,
+            template,
+        } => {
+            let series = series_pda(&seed).0;
+            let round = series_round_pda(&series, 1).0;
+            let template = template.template()?;
+
+            if template.game_type == GameType::Raffle {
+                bail!("series rounds draw from the feed and cannot be raffles");
+            }
+
+            let unique_codes = !template.allow_shared_tickets;
+
+            println!("series: {series}");
+            println!("round 1: {round}");
+
+            let mut ixs = vec![instructions::create_lottery_series(
+                &admin,
+                &admin,
+                &seed,
+                &feed,
+                template,
+                round_duration,
+            )];
+
+            if unique_codes {
+                ixs.push(instructions::create_ticket_registry(&admin, &admin, &round));
+            }
+
+            sender.send("create_lottery_series", &ixs)
+        }
+
+        Command::RollSeries { seed } => {
+            let series_key = series_pda(&seed).0;
+            let series = fetch_series(&sender.rpc, &series_key)?;
+            let next_round = series.current_round + 1;
+            let round = series_round_pda(&series_key, next_round).0;
+
+            println!("round {next_round}: {round}");
+
+            let mut ixs = vec![instructions::roll_series(
+                &admin,
+                &seed,
+                series.current_round,
+            )];
+
+            // Each round sells unique codes through its own registry
+            if !series.template.allow_shared_tickets {
+                ixs.push(instructions::create_ticket_registry(&admin, &admin, &round));
+            }
+
+            sender.send("roll_series", &ixs)
+        }
+
+        Command::CarryOver { seed, round } => {
+            let series = fetch_series(&sender.rpc, &series_pda(&seed).0)?;
+
+            sender.send(
+                "carry_over_prizes",
+                &[instructions::carry_over_prizes(
+                    &seed,
+                    round,
+                    series.current_round,
+                )],
+            )
+        }
+
+        Command::SeedPrize { seed, amount } => {
+            let lottery_key = lottery_key(&seed);
+            let lottery = fetch_lottery(&sender.rpc, &lottery_key)?;
+            let token = token_payment(&sender.rpc, &lottery)?;
+
+            sender.send(
+                "seed_prize",
+                &[instructions::seed_prize(
+                    &admin,
+                    &lottery_key,
+                    amount,
+                    token.as_ref(),
+                )],
+            )
+        }
+
+        Command::ReclaimSeed { seed } => {
+            let lottery_key = lottery_key(&seed);
+            let lottery = fetch_lottery(&sender.rpc, &lottery_key)?;
+            let token = token_payment(&sender.rpc, &lottery)?;
+
+            sender.send(
+                "reclaim_seeded_prize",
+                &[instructions::reclaim_seeded_prize(
+                    &admin,
+                    &lottery_key,
+                    token.as_ref(),
+                )],
+            )
+        }
+
+        Command::CloseSales { seed } => {
+            let lottery = lottery_key(&seed);
+
+            sender.send(
+                "close_lottery",
+                &[instructions::close_lottery(&admin, &lottery)],
+            )
+        }
+
+        Command::CloseLottery { seed } => {
+            let lottery_key = lottery_key(&seed);
+            let lottery = fetch_lottery(&sender.rpc, &lottery_key)?;
+            let token = token_payment(&sender.rpc, &lottery)?;
+
+            sender.send(
+                "close_lottery_pda",
+                &[instructions::close_lottery_pda(
+                    &admin,
+                    &lottery_key,
+                    &lottery.rent_payer,
+                    token.as_ref(),
+                )],
+            )
+        }
+
+        Command::ListBundles { seed } => {
+            let lottery = lottery_key(&seed);
+
+            for (key, bundle) in fetch_bundles(&sender.rpc, &lottery)? {
+                let codes: Vec<String> = bundle.tickets[..bundle.ticket_count as usize]
+                    .iter()
+                    .map(ticket_code_to_hex)
+                    .collect();
+
+                println!(
+                    "{key} owner={} tickets={} first_index={} refunded={} draws_tallied={} codes=[{}]",
+                    bundle.owner,
+                    bundle.ticket_count,
+                    bundle.first_ticket_index,
+                    bundle.refunded,
+                    bundle.draws_tallied,
+                    codes.join(",")
+                );
+            }
+
+            Ok(())
+        }
+
+        Command::Payouts { seed } => {
+            let lottery_key = lottery_key(&seed);
+            let lottery = fetch_lottery(&sender.rpc, &lottery_key)?;
+            let bundles = fetch_bundles(&sender.rpc, &lottery_key)?;
+
+            println!(
+                "tickets_sold={} ticket_revenue={} seeded_prize={} pot={} platform_fee={} prize_pool={} cancelled={}",
+                lottery.tickets_sold,
+                lottery.ticket_revenue,
+                lottery.seeded_prize,
+                lottery.pot_lamports,
+                lottery.platform_fee(),
+                lottery.prize_pool,
+                lottery.cancelled
+            );
+
+            for payout in expected_payouts(&lottery, &bundles) {
+                println!(
+                    "draw={} bundle={} owner={} winning_tickets={:?} amount={} paid={}",
+                    payout.draw_index,
+                    payout.bundle,
+                    payout.owner,
+                    payout.tier_winning_tickets,
+                    payout.amount,
+                    payout.paid
+                );
+            }
+
+            if !lottery.has_drawn() {
+                for (key, bundle) in bundles.iter().filter(|(_, b)| !b.refunded) {
+                    println!(
+                        "refund bundle={} owner={} amount={} (fee kept: {})",
+                        key,
+                        bundle.owner,
+                        expected_refund(&lottery, bundle, false),
+                        expected_refund(&lottery, bundle, true)
+                    );
+                }
+            }
+
+            Ok(())
+        }
+
+        Command::Draw { seed, block_height } => {
+            let lottery_key = lottery_key(&seed);
+            let lottery = fetch_lottery(&sender.rpc, &lottery_key)?;
+
+            sender.send(
+                "draw_winner",
+                &[instructions::draw_winner(
+                    &admin,
+                    &lottery_key,
+                    &lottery.switchboard_feed_btc_block_decimal,
+                    block_height.to_be_bytes(),
+                )],
+            )
+        }
+
+        Command::RevealDraw { seed, secret_file } => {
+            let lottery_key = lottery_key(&seed);
+            let lottery = fetch_lottery(&sender.rpc, &lottery_key)?;
+            let secret = read_draw_secret(&secret_file)?;
+
+            if hash(&secret).to_bytes() != lottery.draw_commitment {
+                bail!(
+                    "{} does not hold the secret committed by {lottery_key}",
+                    secret_file.display()
+                );
+            }
+
+            sender.send(
+                "reveal_draw",
+                &[instructions::reveal_draw(&admin, &lottery_key, secret)],
+            )
+        }
+
+        Command::CancelExpiredDraw { seed } => sender.send(
+            "cancel_expired_draw",
+            &[instructions::cancel_expired_draw(&lottery_key(&seed))],
+        ),
+
+        Command::Settle { seed, draw_index } => {
+            let lottery_key = lottery_key(&seed);
+            let lottery = fetch_lottery(&sender.rpc, &lottery_key)?;
+            let bundles = fetch_bundles(&sender.rpc, &lottery_key)?;
+            let token = token_payment(&sender.rpc, &lottery)?;
+            let index = draw_index as usize;
+
+            let draw = lottery
+                .draws
+                .get(index)
+                .ok_or_else(|| anyhow!("lottery has no draw {draw_index}"))?;
+
+            if !draw.drawn {
+                bail!("draw {draw_index} has not taken place yet");
+            }
+
+            let mut batch = Vec::new();
+
+            // Tallies come first: shares are only payable once every bundle is counted
+            for (key, bundle) in bundles.iter() {
+                if bundle.draws_tallied as usize == index {
+                    batch.push((
+                        format!("tally {key}"),
+                        instructions::tally_transaction_bundle(&admin, &lottery_key, key),
+                    ));
+                }
+            }
+
+            for (key, bundle) in bundles.iter() {
+                let wins = tally_for_draw(&lottery, bundle, index)
+                    .iter()
+                    .any(|t| *t > 0);
+                let paid = bundle.draws_rewarded & (1 << index) != 0;
+
+                if wins && !paid {
+                    batch.push((
+                        format!("reward {key}"),
+                        instructions::reward_transaction_bundle(
+                            &admin,
+                            &lottery_key,
+                            key,
+                            &bundle.owner,
+                            draw_index,
+                            // Balance buyers keep their winnings in the balance
+                            bundle.paid_from_balance,
+                            token.as_ref(),
+                        ),
+                    ));
+                }
+            }
+
+            sender.send_each(batch)
+        }
+
+        Command::Refund { seed, deduct_fee } => {
+            let lottery_key = lottery_key(&seed);
+            let lottery = fetch_lottery(&sender.rpc, &lottery_key)?;
+            let bundles = fetch_bundles(&sender.rpc, &lottery_key)?;
+            let token = token_payment(&sender.rpc, &lottery)?;
+
+            let batch = bundles
+                .iter()
+                .filter(|(_, bundle)| !bundle.refunded)
+                .map(|(key, bundle)| {
+                    (
+                        format!("refund {key}"),
+                        instructions::refund_transaction_bundle(
+                            &admin,
+                            &lottery_key,
+                            key,
+                            &bundle.owner,
+                            deduct_fee,
+                            bundle.paid_from_balance,
+                            token.as_ref(),
+                        ),
+                    )
+                })
+                .collect();
+
+            sender.send_each(batch)
+        }
+
+        Command::WithdrawFees { seed } => {
+            let lottery_key = lottery_key(&seed);
+            let lottery = fetch_lottery(&sender.rpc, &lottery_key)?;
+            let token = token_payment(&sender.rpc, &lottery)?;
+
+            sender.send(
+                "withdraw_platform_fees",
+                &[instructions::withdraw_platform_fees(
+                    &admin,
+                    &lottery_key,
+                    token.as_ref(),
+                )],
+            )
+        }
+
+        Command::SweepPrizes { seed } => {
+            let lottery_key = lottery_key(&seed);
+            let lottery = fetch_lottery(&sender.rpc, &lottery_key)?;
+            let token = token_payment(&sender.rpc, &lottery)?;
+
+            // Series rounds roll their unclaimed prizes into the running round
+            let rollover = if lottery.is_series_round() {
+                Some(fetch_series(&sender.rpc, &lottery.series)?.current_lottery)
+            } else {
+                None
+            };
+
+            // Anyone may crank the sweep, the treasury is always the configured admin
+            let treasury = fetch_configuration(&sender.rpc)?.admin;
+
+            sender.send(
+                "sweep_unclaimed_prizes",
+                &[instructions::sweep_unclaimed_prizes(
+                    &treasury,
+                    &lottery_key,
+                    rollover.as_ref(),
+                    token.as_ref(),
+                )],
+            )
+        }
+
+        Command::ClaimReferral { seed } => {
+            let lottery_key = lottery_key(&seed);
+            let lottery = fetch_lottery(&sender.rpc, &lottery_key)?;
+            let token = token_payment(&sender.rpc, &lottery)?;
+
+            sender.send(
+                "claim_referral_rewards",
+                &[instructions::claim_referral_rewards(
+                    &admin,
+                    &lottery_key,
+                    token.as_ref(),
+                )],
+            )
+        }
+
+        Command::Deposit { amount, mint } => {
+            let token = match mint {
+                Some(mint) => Some(mint_payment(&sender.rpc, &mint)?),
+                None => None,
+            };
+
+            sender.send(
+                "deposit_balance",
+                &[instructions::deposit_balance(
+                    &admin,
+                    amount,
+                    token.as_ref(),
+                )],
+            )
+        }
+
+        Command::Withdraw { amount, mint } => {
+            let token = match mint {
+                Some(mint) => Some(mint_payment(&sender.rpc, &mint)?),
+                None => None,
+            };
+
+            sender.send(
+                "withdraw_balance",
+                &[instructions::withdraw_balance(
+                    &admin,
+                    amount,
+                    token.as_ref(),
+                )],
+            )
+        }
+
+        Command::BuyFromBalance {
+            seed,
+            tickets,
+            referrer,
+        } => {
+            let lottery_key = lottery_key(&seed);
+            let lottery = fetch_lottery(&sender.rpc, &lottery_key)?;
+            let token = token_payment(&sender.rpc, &lottery)?;
+            let tickets =
+                ticket_codes_from_hex(&tickets).map_err(|e| anyhow!("invalid ticket code: {e}"))?;
+
+            // Any value unique to this purchase seeds the bundle
+            let tx_sig_hash = Keypair::new().pubkey().to_bytes();
+
+            sender.send(
+                "purchase_from_balance",
+                &[instructions::purchase_from_balance(
+                    &admin,
+                    &lottery_key,
+                    &lottery.lottery_seed,
+                    tx_sig_hash,
+                    tickets,
+                    referrer.as_ref(),
+                    lottery
+                        .has_ticket_registry()
+                        .then_some(&lottery.ticket_registry),
+                    token.as_ref(),
+                )],
+            )
+        }
+
+        Command::QuickPick {
+            seed,
+            count,
+            referrer,
+        } => {
+            if sender.dump_tx {
+                bail!("quick-pick reveals in a later slot and cannot be dumped");
+            }
+
+            let lottery_key = lottery_key(&seed);
+            let lottery = fetch_lottery(&sender.rpc, &lottery_key)?;
+            let token = token_payment(&sender.rpc, &lottery)?;
+
+            let tx_sig_hash = Keypair::new().pubkey().to_bytes();
+            let secret = Keypair::new().pubkey().to_bytes();
+            let source = QuickPickSource::CommitReveal {
+                commitment: hash(&secret).to_bytes(),
+            };
+
+            sender.send(
+                "request_quick_pick",
+                &[instructions::request_quick_pick(
+                    &admin,
+                    &lottery_key,
+                    tx_sig_hash,
+                    count,
+                    source,
+                    referrer.as_ref(),
+                    token.as_ref(),
+                )],
+            )?;
+
+            // The reveal mixes in the hash of the request slot, known once it is past
+            let request_slot = sender.rpc.get_slot()?;
+            while sender.rpc.get_slot()? <= request_slot {
+                std::thread::sleep(std::time::Duration::from_millis(400));
+            }
+
+            sender.send(
+                "fulfill_quick_pick",
+                &[instructions::fulfill_quick_pick(
+                    &admin,
+                    &lottery_key,
+                    &lottery.lottery_seed,
+                    tx_sig_hash,
+                    source,
+                    Some(secret),
+                    referrer.as_ref(),
+                    lottery
+                        .has_ticket_registry()
+                        .then_some(&lottery.ticket_registry),
+                    token.as_ref(),
+                )],
+            )
+        }
+
+        Command::CreateRegistry { seed } => {
+            let lottery = lottery_key(&seed);
+
+            sender.send(
+                "create_ticket_registry",
+                &[instructions::create_ticket_registry(
+                    &admin, &admin, &lottery,
+                )],
+            )
+        }
+
+        Command::CloseRegistry { seed } => {
+            let lottery_key = lottery_key(&seed);
+            let lottery = fetch_lottery(&sender.rpc, &lottery_key)?;
+
+            if !lottery.has_ticket_registry() {
+                bail!("lottery {lottery_key} has no ticket registry");
+            }
+
+            let registry = fetch_ticket_registry(&sender.rpc, &lottery.ticket_registry)?;
+
+            sender.send(
+                "close_ticket_registry",
+                &[instructions::close_ticket_registry(
+                    &admin,
+                    &lottery_key,
+                    &registry.rent_payer,
+                )],
+            )
+        }
+
+        Command::SignIntent {
+            seed,
+            tickets,
+            max_price,
+            expires_in,
+            referrer,
+        } => {
+            let lottery_key = lottery_key(&seed);
+            let lottery = fetch_lottery(&sender.rpc, &lottery_key)?;
+            let mint = lottery.is_token_lottery().then_some(lottery.payment_mint);
+            let player_account = fetch_player_account(&sender.rpc, &admin, mint.as_ref())?;
+            let tickets =
+                ticket_codes_from_hex(&tickets).map_err(|e| anyhow!("invalid ticket code: {e}"))?;
+
+            let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+
+            let intent = PurchaseIntent {
+                lottery: lottery_key,
+                owner: admin,
+                max_price: max_price.unwrap_or(lottery.lamports_per_ticket * tickets.len() as u64),
+                tickets,
+                expiry: now + expires_in,
+                nonce: player_account.nonce,
+                referrer,
+            };
+
+            let signer = sender
+                .signer
+                .as_ref()
+                .ok_or_else(|| anyhow!("signing an intent requires the player keypair"))?;
+
+            let message = intent_message(&intent);
+            let mut signed = signer.sign_message(&message).as_ref().to_vec();
+            signed.extend_from_slice(&message);
+
+            println!(
+                "intent: {}",
+                base64::engine::general_purpose::STANDARD.encode(signed)
+            );
+
+            Ok(())
+        }
+
+        Command::RelayIntent { signed } => {
+            let signed = base64::engine::general_purpose::STANDARD
+                .decode(signed.trim())
+                .context("intent is not valid base64")?;
+
+            if signed.len() < 64 {
+                bail!("intent is too short");
+            }
+
+            let (signature, message) = signed.split_at(64);
+            let intent = PurchaseIntent::try_from_slice(message).context("invalid intent")?;
+            let lottery = fetch_lottery(&sender.rpc, &intent.lottery)?;
+            let token = token_payment(&sender.rpc, &lottery)?;
+
+            sender.send(
+                "purchase_with_intent",
+                &instructions::purchase_with_intent(
+                    &admin,
+                    &lottery.lottery_seed,
+                    intent,
+                    signature.try_into()?,
+                    lottery
+                        .has_ticket_registry()
+                        .then_some(&lottery.ticket_registry),
+                    token.as_ref(),
+                ),
+            )
+        }
+
+        Command::Snapshot { .. }
+        | Command::Audit { .. }
+        | Command::Prove { .. }
+        | Command::VerifyProof { .. } => unreachable!(),
+    }
+}
@@ -0,0 +1,92 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use ct_lotto_client::proof::{build_draw_proof, verify_draw_proof, DrawProof};
+use ct_lotto_client::state::deserialize_lottery;
+use solana_rpc_client::rpc_client::RpcClient;
+
+use crate::rpc::fetch_bundle_data;
+use crate::{lottery_key, read_draw_secret};
+
+/// Builds the proof of a draw from the feed value and slot the draw recorded.
+/// `feed_data` (base64 feed account data captured at that slot) is embedded and
+/// checked too; the live feed has usually moved on, so it is never used.
+/// Commit-reveal draws need `reveal`: the secret file and the base64 slot hash
+/// the reveal recorded.
+pub fn prove(
+    rpc: &RpcClient,
+    seed: &str,
+    draw_index: u8,
+    feed_data: Option<&Path>,
+    reveal: Option<(&Path, &str)>,
+    out: Option<&Path>,
+) -> Result<()> {
+    let lottery_key = lottery_key(seed);
+    let lottery_data = rpc
+        .get_account_data(&lottery_key)
+        .with_context(|| format!("lottery {lottery_key} not found"))?;
+    let lottery = deserialize_lottery(&lottery_data).map_err(|e| anyhow!("{e}"))?;
+    let feed_key = lottery.switchboard_feed_btc_block_decimal;
+
+    let feed_data = match feed_data {
+        Some(path) => Some(
+            base64::engine::general_purpose::STANDARD
+                .decode(std::fs::read_to_string(path)?.trim())
+                .context("feed data must be base64")?,
+        ),
+        None => None,
+    };
+
+    let reveal = match reveal {
+        Some((secret_file, slot_hash)) => {
+            let slot_hash: [u8; 32] = base64::engine::general_purpose::STANDARD
+                .decode(slot_hash)
+                .context("slot hash must be base64")?
+                .try_into()
+                .map_err(|_| anyhow!("slot hash must be 32 bytes"))?;
+
+            Some((read_draw_secret(secret_file)?, slot_hash))
+        }
+        None => None,
+    };
+
+    let bundles = fetch_bundle_data(rpc, &lottery_key)?;
+
+    let proof = build_draw_proof(
+        &lottery_key,
+        &lottery_data,
+        feed_data.as_deref().map(|data| (&feed_key, data)),
+        reveal
+            .as_ref()
+            .map(|(secret, slot_hash)| (secret, slot_hash)),
+        draw_index,
+        &bundles,
+    )
+    .map_err(|e| anyhow!(e))?;
+
+    let json = serde_json::to_string_pretty(&proof)?;
+
+    match out {
+        Some(path) => std::fs::write(path, json)?,
+        None => println!("{json}"),
+    }
+
+    Ok(())
+}
+
+pub fn verify(path: &Path) -> Result<()> {
+    let proof: DrawProof = serde_json::from_slice(&std::fs::read(path)?)?;
+
+    verify_draw_proof(&proof).map_err(|e| anyhow!("proof rejected: {e}"))?;
+
+    println!(
+        "proof valid: lottery {} draw {} winning code {} ({} winning bundles)",
+        proof.lottery_seed,
+        proof.draw_index,
+        proof.winning_code,
+        proof.winners.len()
+    );
+
+    Ok(())
+}
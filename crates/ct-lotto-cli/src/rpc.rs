@@ -0,0 +1,129 @@
+use anchor_lang::Discriminator;
+use anyhow::{anyhow, Context, Result};
+use ct_lotto_client::pda::{configuration_pda, player_account_pda};
+use ct_lotto_client::state::{
+    deserialize_configuration, deserialize_lottery, deserialize_lottery_series,
+    deserialize_player_account, deserialize_ticket_registry, deserialize_transaction_bundle,
+};
+use ct_lotto_client::{
+    Configuration, Lottery, LotterySeries, PlayerAccount, TicketRegistry, TokenPayment,
+    TransactionBundle, PROGRAM_ID,
+};
+use solana_account_decoder_client_types::UiAccountEncoding;
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_rpc_client_api::config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_rpc_client_api::filter::{Memcmp, RpcFilterType};
+use solana_sdk::pubkey::Pubkey;
+
+pub fn fetch_configuration(rpc: &RpcClient) -> Result<Configuration> {
+    let account = rpc
+        .get_account(&configuration_pda().0)
+        .context("configuration not found")?;
+
+    deserialize_configuration(&account.data)
+        .map_err(|e| anyhow!("invalid configuration account: {e}"))
+}
+
+pub fn fetch_lottery(rpc: &RpcClient, lottery: &Pubkey) -> Result<Lottery> {
+    let account = rpc
+        .get_account(lottery)
+        .with_context(|| format!("lottery {lottery} not found"))?;
+
+    deserialize_lottery(&account.data).map_err(|e| anyhow!("invalid lottery account: {e}"))
+}
+
+pub fn fetch_series(rpc: &RpcClient, series: &Pubkey) -> Result<LotterySeries> {
+    let account = rpc
+        .get_account(series)
+        .with_context(|| format!("series {series} not found"))?;
+
+    deserialize_lottery_series(&account.data).map_err(|e| anyhow!("invalid series account: {e}"))
+}
+
+/// Balance account of a player, in SOL or in the token of `mint`.
+pub fn fetch_player_account(
+    rpc: &RpcClient,
+    owner: &Pubkey,
+    mint: Option<&Pubkey>,
+) -> Result<PlayerAccount> {
+    let player_account = player_account_pda(owner, &mint.copied().unwrap_or_default()).0;
+    let account = rpc
+        .get_account(&player_account)
+        .with_context(|| format!("no deposited balance for {owner}"))?;
+
+    deserialize_player_account(&account.data).map_err(|e| anyhow!("invalid player account: {e}"))
+}
+
+pub fn fetch_ticket_registry(rpc: &RpcClient, registry: &Pubkey) -> Result<TicketRegistry> {
+    let account = rpc
+        .get_account(registry)
+        .with_context(|| format!("ticket registry {registry} not found"))?;
+
+    deserialize_ticket_registry(&account.data)
+        .map_err(|e| anyhow!("invalid ticket registry account: {e}"))
+}
+
+/// Raw account data of every bundle of a lottery.
+pub fn fetch_bundle_data(rpc: &RpcClient, lottery: &Pubkey) -> Result<Vec<(Pubkey, Vec<u8>)>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![
+            RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                0,
+                TransactionBundle::DISCRIMINATOR.to_vec(),
+            )),
+            // lottery_pda is the first field after the discriminator
+            RpcFilterType::Memcmp(Memcmp::new_raw_bytes(8, lottery.to_bytes().to_vec())),
+        ]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+
+    Ok(rpc
+        .get_program_accounts_with_config(&PROGRAM_ID, config)?
+        .into_iter()
+        .map(|(key, account)| (key, account.data))
+        .collect())
+}
+
+/// Every bundle of a lottery, in sale order.
+pub fn fetch_bundles(
+    rpc: &RpcClient,
+    lottery: &Pubkey,
+) -> Result<Vec<(Pubkey, TransactionBundle)>> {
+    let mut bundles = fetch_bundle_data(rpc, lottery)?
+        .into_iter()
+        .map(|(key, data)| {
+            deserialize_transaction_bundle(&data)
+                .map(|bundle| (key, bundle))
+                .map_err(|e| anyhow!("invalid bundle {key}: {e}"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    bundles.sort_by_key(|(_, bundle)| bundle.first_ticket_index);
+
+    Ok(bundles)
+}
+
+/// Mint and token program of a token lottery, `None` for SOL lotteries.
+pub fn token_payment(rpc: &RpcClient, lottery: &Lottery) -> Result<Option<TokenPayment>> {
+    if !lottery.is_token_lottery() {
+        return Ok(None);
+    }
+
+    mint_payment(rpc, &lottery.payment_mint).map(Some)
+}
+
+/// Mint with the token program owning it.
+pub fn mint_payment(rpc: &RpcClient, mint: &Pubkey) -> Result<TokenPayment> {
+    let account = rpc
+        .get_account(mint)
+        .with_context(|| format!("payment mint {mint} not found"))?;
+
+    Ok(TokenPayment {
+        mint: *mint,
+        token_program: account.owner,
+    })
+}
@@ -0,0 +1,68 @@
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use solana_sdk::transaction::Transaction;
+
+/// Who signs (or will sign) the transactions and how they leave the CLI.
+pub struct TxSender {
+    pub rpc: RpcClient,
+    pub signer: Option<Keypair>,
+    pub authority: Pubkey,
+    pub dump_tx: bool,
+    pub blockhash: Option<Hash>,
+}
+
+impl TxSender {
+    fn recent_blockhash(&self) -> Result<Hash> {
+        match self.blockhash {
+            Some(hash) => Ok(hash),
+            None => Ok(self.rpc.get_latest_blockhash()?),
+        }
+    }
+
+    /// Sends one transaction, or prints it base64-encoded with `--dump-tx`
+    /// (partially signed when a keypair is available) for a multisig or cold signer.
+    pub fn send(&self, label: &str, instructions: &[Instruction]) -> Result<()> {
+        let blockhash = self.recent_blockhash()?;
+        let mut tx = Transaction::new_with_payer(instructions, Some(&self.authority));
+
+        if self.dump_tx {
+            match &self.signer {
+                Some(signer) => tx.partial_sign(&[signer], blockhash),
+                None => tx.message.recent_blockhash = blockhash,
+            }
+
+            let bytes = bincode::serialize(&tx)?;
+            println!(
+                "{label}: {}",
+                base64::engine::general_purpose::STANDARD.encode(bytes)
+            );
+
+            return Ok(());
+        }
+
+        let signer = self
+            .signer
+            .as_ref()
+            .ok_or_else(|| anyhow!("a keypair is required unless --dump-tx is set"))?;
+
+        tx.sign(&[signer], blockhash);
+
+        let signature = self.rpc.send_and_confirm_transaction(&tx)?;
+        println!("{label}: {signature}");
+
+        Ok(())
+    }
+
+    pub fn send_each(&self, batch: Vec<(String, Instruction)>) -> Result<()> {
+        for (label, instruction) in batch {
+            self.send(&label, &[instruction])?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,610 @@
+//! Offline reconciliation of a snapshot of program accounts.
+//!
+//! A snapshot is the `getProgramAccounts` output of the program (JSON, either the
+//! bare result array or the full RPC response), optionally extended with the token
+//! vaults of token lotteries, which are owned by the token program. The binary
+//! form is the borsh encoding of `Vec<SnapshotAccount>`.
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+
+use anchor_lang::prelude::{borsh, Pubkey, Rent};
+use anchor_lang::{AccountDeserialize, AnchorDeserialize, AnchorSerialize, Discriminator};
+use anchor_spl::token_interface::TokenAccount;
+use base64::Engine;
+use ct_lotto_anchor::lotto_util::bundle_refund;
+use serde_json::Value;
+
+use crate::settlement::expected_payouts;
+use crate::state::{deserialize_lottery, deserialize_transaction_bundle};
+use crate::{Lottery, TransactionBundle};
+
+#[derive(Clone, Debug, PartialEq, Eq, AnchorSerialize, AnchorDeserialize)]
+pub struct SnapshotAccount {
+    pub pubkey: Pubkey,
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FindingKind {
+    /// Escrowed funds do not cover what the lottery still owes.
+    Underfunded,
+    /// The fee and referral figures of a lottery contradict each other.
+    LedgerMismatch,
+    /// `tickets_sold` differs from the tickets held by the lottery's bundles
+    /// (or is below them, once bundles have been closed).
+    TicketCountMismatch,
+    /// `bundle_count` differs from the bundles found plus the bundles closed.
+    BundleCountMismatch,
+    /// `refunds_settled` differs from the bundles marked refunded, beyond
+    /// what the closed bundles can account for.
+    RefundCountMismatch,
+    /// A bundle points to a lottery that is not in the snapshot.
+    OrphanedBundle,
+    /// The vault of a token lottery is not in the snapshot.
+    VaultMissing,
+    /// An account carries a program discriminator but cannot be decoded.
+    Undecodable,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Finding {
+    pub account: Pubkey,
+    pub kind: FindingKind,
+    pub detail: String,
+}
+
+/// Reconciliation figures of one lottery.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LotteryAudit {
+    pub lottery: Pubkey,
+    pub lottery_seed: String,
+    /// Lamports above rent exemption, or vault token balance for token lotteries.
+    pub escrowed: Option<u64>,
+    /// Funds the program's own ledger says it holds.
+    pub pot_lamports: u64,
+    /// `None` when the ledger is inconsistent (see `FindingKind::LedgerMismatch`).
+    pub liabilities: Option<u64>,
+    pub tickets_sold: u32,
+    pub bundle_tickets: u64,
+    pub bundle_count: u32,
+    pub bundles_found: u32,
+    pub bundles_closed: u32,
+    pub refunds_settled: u32,
+    pub refunded_bundles: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AuditReport {
+    pub lotteries: Vec<LotteryAudit>,
+    pub findings: Vec<Finding>,
+}
+
+impl AuditReport {
+    pub fn is_clean(&self) -> bool {
+        self.findings.is_empty()
+    }
+}
+
+/* -------------------------------------------------
+   SNAPSHOT PARSING
+--------------------------------------------------*/
+pub fn parse_json_snapshot(json: &str) -> Result<Vec<SnapshotAccount>, String> {
+    let value: Value = serde_json::from_str(json).map_err(|e| format!("invalid JSON: {e}"))?;
+
+    // Accept the bare result, `{ result: [...] }` and `{ result: { context, value: [...] } }`
+    let entries = match &value {
+        Value::Array(_) => &value,
+        _ => match &value["result"] {
+            Value::Array(_) => &value["result"],
+            result => &result["value"],
+        },
+    };
+
+    entries
+        .as_array()
+        .ok_or("snapshot is not a getProgramAccounts result")?
+        .iter()
+        .map(parse_json_account)
+        .collect()
+}
+
+fn parse_json_account(entry: &Value) -> Result<SnapshotAccount, String> {
+    let pubkey = json_pubkey(&entry["pubkey"])?;
+    let account = &entry["account"];
+
+    let data = match &account["data"] {
+        Value::Array(parts) if parts.get(1).and_then(Value::as_str) == Some("base64") => {
+            let encoded = parts[0].as_str().unwrap_or_default();
+
+            base64::engine::general_purpose::STANDARD
+                .decode(encoded)
+                .map_err(|e| format!("{pubkey}: invalid base64 data: {e}"))?
+        }
+        _ => return Err(format!("{pubkey}: account data must be base64 encoded")),
+    };
+
+    Ok(SnapshotAccount {
+        pubkey,
+        owner: json_pubkey(&account["owner"])?,
+        lamports: account["lamports"]
+            .as_u64()
+            .ok_or_else(|| format!("{pubkey}: missing lamports"))?,
+        data,
+    })
+}
+
+fn json_pubkey(value: &Value) -> Result<Pubkey, String> {
+    let text = value.as_str().ok_or("missing pubkey")?;
+
+    Pubkey::from_str(text).map_err(|e| format!("invalid pubkey {text}: {e}"))
+}
+
+pub fn parse_binary_snapshot(bytes: &[u8]) -> Result<Vec<SnapshotAccount>, String> {
+    Vec::<SnapshotAccount>::try_from_slice(bytes).map_err(|e| format!("invalid snapshot: {e}"))
+}
+
+pub fn encode_binary_snapshot(accounts: &[SnapshotAccount]) -> Vec<u8> {
+    // Serializing into a Vec cannot fail
+    accounts.to_vec().try_to_vec().unwrap_or_default()
+}
+
+/* -------------------------------------------------
+   INVARIANTS
+--------------------------------------------------*/
+pub fn audit_snapshot(accounts: &[SnapshotAccount]) -> AuditReport {
+    let mut report = AuditReport::default();
+    let mut lotteries: BTreeMap<Pubkey, (&SnapshotAccount, Lottery)> = BTreeMap::new();
+    let mut bundles: BTreeMap<Pubkey, Vec<(Pubkey, TransactionBundle)>> = BTreeMap::new();
+    let by_key: HashMap<Pubkey, &SnapshotAccount> =
+        accounts.iter().map(|a| (a.pubkey, a)).collect();
+
+    for account in accounts.iter().filter(|a| a.owner == crate::PROGRAM_ID) {
+        if account.data.starts_with(Lottery::DISCRIMINATOR) {
+            match deserialize_lottery(&account.data) {
+                Ok(lottery) => {
+                    lotteries.insert(account.pubkey, (account, lottery));
+                }
+                Err(e) => report.findings.push(undecodable(account, e)),
+            }
+        } else if account.data.starts_with(TransactionBundle::DISCRIMINATOR) {
+            match deserialize_transaction_bundle(&account.data) {
+                Ok(bundle) => bundles
+                    .entry(bundle.lottery_pda)
+                    .or_default()
+                    .push((account.pubkey, bundle)),
+                Err(e) => report.findings.push(undecodable(account, e)),
+            }
+        }
+    }
+
+    for (lottery_key, lottery_bundles) in bundles.iter() {
+        if !lotteries.contains_key(lottery_key) {
+            for (bundle_key, _) in lottery_bundles {
+                report.findings.push(Finding {
+                    account: *bundle_key,
+                    kind: FindingKind::OrphanedBundle,
+                    detail: format!("lottery {lottery_key} is closed or missing"),
+                });
+            }
+        }
+    }
+
+    for (lottery_key, (account, lottery)) in lotteries.iter() {
+        let mut lottery_bundles = bundles.remove(lottery_key).unwrap_or_default();
+        lottery_bundles.sort_by_key(|(_, bundle)| bundle.first_ticket_index);
+
+        let audit = audit_lottery(account, lottery, &lottery_bundles, &by_key, &mut report);
+        report.lotteries.push(audit);
+    }
+
+    report
+}
+
+fn undecodable(account: &SnapshotAccount, error: anchor_lang::error::Error) -> Finding {
+    Finding {
+        account: account.pubkey,
+        kind: FindingKind::Undecodable,
+        detail: error.to_string(),
+    }
+}
+
+fn audit_lottery(
+    account: &SnapshotAccount,
+    lottery: &Lottery,
+    bundles: &[(Pubkey, TransactionBundle)],
+    by_key: &HashMap<Pubkey, &SnapshotAccount>,
+    report: &mut AuditReport,
+) -> LotteryAudit {
+    let lottery_key = account.pubkey;
+    let mut finding = |kind: FindingKind, detail: String| {
+        report.findings.push(Finding {
+            account: lottery_key,
+            kind,
+            detail,
+        })
+    };
+
+    let bundle_tickets: u64 = bundles.iter().map(|(_, b)| b.ticket_count as u64).sum();
+    let refunded_bundles = bundles.iter().filter(|(_, b)| b.refunded).count() as u32;
+    let bundles_found = bundles.len() as u32;
+    let bundles_closed = lottery.bundles_closed;
+
+    // Closed bundles are gone with their tickets, which are then only bounded
+    let tickets_match = if bundles_closed == 0 {
+        bundle_tickets == lottery.tickets_sold as u64
+    } else {
+        bundle_tickets <= lottery.tickets_sold as u64
+    };
+
+    if !tickets_match {
+        finding(
+            FindingKind::TicketCountMismatch,
+            format!(
+                "tickets_sold {} but bundles hold {} ({} bundles closed)",
+                lottery.tickets_sold, bundle_tickets, bundles_closed
+            ),
+        );
+    }
+
+    if bundles_found as u64 + bundles_closed as u64 != lottery.bundle_count as u64 {
+        finding(
+            FindingKind::BundleCountMismatch,
+            format!(
+                "bundle_count {} but {} bundles found and {} closed",
+                lottery.bundle_count, bundles_found, bundles_closed
+            ),
+        );
+    }
+
+    // Each closed bundle may have been a refunded one
+    let refunds_match = refunded_bundles <= lottery.refunds_settled
+        && lottery.refunds_settled - refunded_bundles <= bundles_closed;
+
+    if !refunds_match {
+        finding(
+            FindingKind::RefundCountMismatch,
+            format!(
+                "refunds_settled {} but {} bundles refunded ({} bundles closed)",
+                lottery.refunds_settled, refunded_bundles, bundles_closed
+            ),
+        );
+    }
+
+    let escrowed = if lottery.is_token_lottery() {
+        by_key
+            .get(&lottery.payment_vault)
+            .and_then(|vault| TokenAccount::try_deserialize(&mut &vault.data[..]).ok())
+            .map(|vault| vault.amount)
+    } else {
+        let rent = Rent::default().minimum_balance(account.data.len());
+        Some(account.lamports.saturating_sub(rent))
+    };
+
+    let liabilities = match outstanding_liabilities(lottery, bundles) {
+        Ok(liabilities) => Some(liabilities),
+        Err(detail) => {
+            finding(FindingKind::LedgerMismatch, detail);
+            None
+        }
+    };
+
+    match (escrowed, liabilities) {
+        (Some(escrowed), Some(liabilities)) if escrowed < liabilities => finding(
+            FindingKind::Underfunded,
+            format!("escrowed {escrowed} but owes {liabilities}"),
+        ),
+        (Some(escrowed), _) if escrowed < lottery.pot_lamports => finding(
+            FindingKind::Underfunded,
+            format!(
+                "escrowed {escrowed} but the pot ledger holds {}",
+                lottery.pot_lamports
+            ),
+        ),
+        (Some(_), _) => {}
+        (None, _) => finding(
+            FindingKind::VaultMissing,
+            format!("vault {} not in snapshot", lottery.payment_vault),
+        ),
+    }
+
+    LotteryAudit {
+        lottery: lottery_key,
+        lottery_seed: lottery.lottery_seed.clone(),
+        escrowed,
+        pot_lamports: lottery.pot_lamports,
+        liabilities,
+        tickets_sold: lottery.tickets_sold,
+        bundle_tickets,
+        bundle_count: lottery.bundle_count,
+        bundles_found,
+        bundles_closed,
+        refunds_settled: lottery.refunds_settled,
+        refunded_bundles,
+    }
+}
+
+/// Funds the lottery still has to pay out.
+///
+/// Before the first draw every ticket payment is owed, either to a future prize pool
+/// or back to the players; once refunds start, the unrefunded bundles are owed their
+/// refund (without fee deduction, the larger of the two). The seeded prize is owed
+/// to the pool or back to the operator. After a draw, the unpaid prize shares, the
+/// pools of draws not yet drawn, the unwithdrawn fee and the unclaimed referral
+/// earnings are owed; unpaid prize shares stop being owed once swept.
+///
+/// Fails with a description when the referral earnings exceed the platform fee
+/// they come out of, or the referral claims exceed the earnings.
+pub fn outstanding_liabilities(
+    lottery: &Lottery,
+    bundles: &[(Pubkey, TransactionBundle)],
+) -> Result<u64, String> {
+    if !lottery.has_drawn() {
+        if lottery.refunds_settled == 0 {
+            return Ok(lottery.ticket_revenue + lottery.seeded_prize);
+        }
+
+        let unrefunded: u64 = bundles
+            .iter()
+            .filter(|(_, b)| !b.refunded)
+            .map(|(_, b)| bundle_refund(lottery, b, false))
+            .sum();
+
+        return Ok(unrefunded + lottery.seeded_prize);
+    }
+
+    let unpaid_prizes: u64 = if lottery.prizes_swept {
+        0
+    } else {
+        expected_payouts(lottery, bundles)
+            .iter()
+            .filter(|p| !p.paid)
+            .map(|p| p.amount)
+            .sum()
+    };
+
+    let undrawn_pools: u64 = lottery
+        .draws
+        .iter()
+        .filter(|d| !d.drawn)
+        .map(|d| lottery.prize_pool * (d.pool_percentage as u64) / 100)
+        .sum();
+
+    let unpaid_fee = if lottery.platform_fee_withdrawn {
+        0
+    } else {
+        lottery
+            .platform_fee()
+            .checked_sub(lottery.referral_fees_accrued)
+            .ok_or_else(|| {
+                format!(
+                    "referral_fees_accrued {} exceeds the platform fee {}",
+                    lottery.referral_fees_accrued,
+                    lottery.platform_fee()
+                )
+            })?
+    };
+
+    let unclaimed_referrals = lottery
+        .referral_fees_accrued
+        .checked_sub(lottery.referral_fees_claimed)
+        .ok_or_else(|| {
+            format!(
+                "referral_fees_claimed {} exceeds referral_fees_accrued {}",
+                lottery.referral_fees_claimed, lottery.referral_fees_accrued
+            )
+        })?;
+
+    Ok(unpaid_prizes + undrawn_pools + unpaid_fee + unclaimed_referrals)
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::AccountSerialize;
+
+    use super::*;
+    use crate::fixtures::{bundle, lottery, PRICE};
+
+    fn program_account<T: AccountSerialize>(
+        pubkey: Pubkey,
+        value: &T,
+        escrowed: u64,
+    ) -> SnapshotAccount {
+        let mut data = Vec::new();
+        value.try_serialize(&mut data).unwrap();
+
+        SnapshotAccount {
+            pubkey,
+            owner: crate::PROGRAM_ID,
+            lamports: Rent::default().minimum_balance(data.len()) + escrowed,
+            data,
+        }
+    }
+
+    /// Snapshot of `lottery`, escrowing `escrowed`, and its `bundles`.
+    fn snapshot(
+        key: Pubkey,
+        lottery: &Lottery,
+        escrowed: u64,
+        bundles: &[TransactionBundle],
+    ) -> Vec<SnapshotAccount> {
+        let mut accounts = vec![program_account(key, lottery, escrowed)];
+
+        accounts.extend(
+            bundles
+                .iter()
+                .map(|b| program_account(Pubkey::new_unique(), b, 0)),
+        );
+
+        accounts
+    }
+
+    fn finding_kinds(accounts: &[SnapshotAccount]) -> Vec<FindingKind> {
+        audit_snapshot(accounts)
+            .findings
+            .into_iter()
+            .map(|f| f.kind)
+            .collect()
+    }
+
+    #[test]
+    fn consistent_lottery_is_clean() {
+        let key = Pubkey::new_unique();
+        let bundles = [bundle(key, 0, 3), bundle(key, 3, 1)];
+
+        let report = audit_snapshot(&snapshot(key, &lottery(), 4 * PRICE, &bundles));
+
+        assert!(report.is_clean(), "{:?}", report.findings);
+        assert_eq!(report.lotteries[0].liabilities, Some(4 * PRICE));
+    }
+
+    #[test]
+    fn closed_settled_bundles_are_accounted_for() {
+        let key = Pubkey::new_unique();
+
+        // Cancelled lottery: both bundles refunded, one of them since closed
+        let mut lottery = lottery();
+        lottery.open = false;
+        lottery.cancelled = true;
+        lottery.refunds_settled = 2;
+        lottery.bundles_closed = 1;
+        lottery.pot_lamports = 0;
+
+        let mut remaining = bundle(key, 3, 1);
+        remaining.refunded = true;
+
+        let report = audit_snapshot(&snapshot(key, &lottery, 0, &[remaining]));
+
+        assert!(report.is_clean(), "{:?}", report.findings);
+        assert_eq!(report.lotteries[0].bundles_closed, 1);
+    }
+
+    #[test]
+    fn underfunded_escrow_is_reported() {
+        let key = Pubkey::new_unique();
+        let bundles = [bundle(key, 0, 3), bundle(key, 3, 1)];
+
+        assert_eq!(
+            finding_kinds(&snapshot(key, &lottery(), 3 * PRICE, &bundles)),
+            vec![FindingKind::Underfunded]
+        );
+    }
+
+    #[test]
+    fn referral_ledger_above_the_fee_is_reported() {
+        let key = Pubkey::new_unique();
+        let bundles = [bundle(key, 0, 3), bundle(key, 3, 1)];
+
+        let mut lottery = lottery();
+        lottery.open = false;
+        lottery.draws[0].drawn = true;
+        lottery.draws[0].winning_code = 15u64.to_be_bytes();
+        lottery.referral_fees_accrued = lottery.platform_fee() + 1;
+
+        let report = audit_snapshot(&snapshot(key, &lottery, 4 * PRICE, &bundles));
+
+        assert_eq!(report.findings[0].kind, FindingKind::LedgerMismatch);
+        assert_eq!(report.lotteries[0].liabilities, None);
+
+        lottery.referral_fees_accrued = 0;
+        lottery.referral_fees_claimed = 1;
+
+        assert_eq!(
+            finding_kinds(&snapshot(key, &lottery, 4 * PRICE, &bundles)),
+            vec![FindingKind::LedgerMismatch]
+        );
+    }
+
+    #[test]
+    fn ticket_count_mismatch_is_reported() {
+        let key = Pubkey::new_unique();
+        let bundles = [bundle(key, 0, 3), bundle(key, 3, 1)];
+
+        let mut lottery = lottery();
+        lottery.tickets_sold = 5;
+
+        assert_eq!(
+            finding_kinds(&snapshot(key, &lottery, 4 * PRICE, &bundles)),
+            vec![FindingKind::TicketCountMismatch]
+        );
+
+        // Closed bundles only explain missing tickets, never extra ones
+        lottery.tickets_sold = 3;
+        lottery.bundle_count = 3;
+        lottery.bundles_closed = 1;
+
+        assert_eq!(
+            finding_kinds(&snapshot(key, &lottery, 4 * PRICE, &bundles)),
+            vec![FindingKind::TicketCountMismatch]
+        );
+    }
+
+    #[test]
+    fn bundle_count_mismatch_is_reported() {
+        let key = Pubkey::new_unique();
+        let bundles = [bundle(key, 0, 3), bundle(key, 3, 1)];
+
+        let mut lottery = lottery();
+        lottery.bundles_closed = 1;
+
+        assert_eq!(
+            finding_kinds(&snapshot(key, &lottery, 4 * PRICE, &bundles)),
+            vec![FindingKind::BundleCountMismatch]
+        );
+    }
+
+    #[test]
+    fn refund_count_mismatch_is_reported() {
+        let key = Pubkey::new_unique();
+        let bundles = [bundle(key, 0, 3), bundle(key, 3, 1)];
+
+        let mut lottery = lottery();
+        lottery.open = false;
+        lottery.cancelled = true;
+        lottery.refunds_settled = 1;
+
+        assert_eq!(
+            finding_kinds(&snapshot(key, &lottery, 4 * PRICE, &bundles)),
+            vec![FindingKind::RefundCountMismatch]
+        );
+    }
+
+    #[test]
+    fn bundle_of_a_missing_lottery_is_orphaned() {
+        let key = Pubkey::new_unique();
+        let bundles = [bundle(key, 0, 3), bundle(key, 3, 1)];
+
+        let mut accounts = snapshot(key, &lottery(), 4 * PRICE, &bundles);
+        accounts.push(program_account(
+            Pubkey::new_unique(),
+            &bundle(Pubkey::new_unique(), 0, 1),
+            0,
+        ));
+
+        assert_eq!(finding_kinds(&accounts), vec![FindingKind::OrphanedBundle]);
+    }
+
+    #[test]
+    fn token_lottery_without_its_vault_is_reported() {
+        let key = Pubkey::new_unique();
+        let bundles = [bundle(key, 0, 3), bundle(key, 3, 1)];
+
+        let mut lottery = lottery();
+        lottery.payment_mint = Pubkey::new_unique();
+        lottery.payment_vault = Pubkey::new_unique();
+
+        assert_eq!(
+            finding_kinds(&snapshot(key, &lottery, 0, &bundles)),
+            vec![FindingKind::VaultMissing]
+        );
+    }
+
+    #[test]
+    fn truncated_lottery_is_undecodable() {
+        let mut account = program_account(Pubkey::new_unique(), &lottery(), 0);
+        account.data.truncate(Lottery::DISCRIMINATOR.len() + 4);
+
+        assert_eq!(finding_kinds(&[account]), vec![FindingKind::Undecodable]);
+    }
+}
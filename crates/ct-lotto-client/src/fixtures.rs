@@ -0,0 +1,77 @@
+//! Account values shared by the unit tests.
+use anchor_lang::prelude::Pubkey;
+use ct_lotto_anchor::lotto_util::{build_draws, validate_prize_tiers};
+
+use crate::{GameType, Lottery, TransactionBundle};
+
+pub const PRICE: u64 = 1_000;
+
+/// Open SOL lottery over codes "0".."f" with 4 tickets sold in 2 bundles, fully escrowed.
+pub fn lottery() -> Lottery {
+    Lottery {
+        switchboard_feed_btc_block_decimal: Pubkey::default(),
+        tickets_sold: 4,
+        lamports_per_ticket: PRICE,
+        ticket_code_start_hex: "0".to_string(),
+        ticket_code_end_hex: "f".to_string(),
+        refunds_settled: 0,
+        open: true,
+        platform_fee_percentage: 2,
+        allow_shared_tickets: false,
+        bundle_count: 2,
+        prize_pool: 0,
+        prize_tiers: validate_prize_tiers(vec![]).unwrap(),
+        game_type: GameType::CodeMatch,
+        draws: build_draws(vec![]).unwrap(),
+        lottery_seed: "fixture".to_string(),
+        bump: 255,
+        payment_mint: Pubkey::default(),
+        payment_vault: Pubkey::default(),
+        ticket_revenue: 4 * PRICE,
+        platform_fee_withdrawn: false,
+        max_tickets_total: 16,
+        max_tickets_per_wallet: 0,
+        min_tickets_to_draw: 1,
+        cancelled: false,
+        seeded_prize: 0,
+        pot_lamports: 4 * PRICE,
+        referral_bps: 0,
+        referral_fees_accrued: 0,
+        referral_fees_claimed: 0,
+        series: Pubkey::default(),
+        round: 0,
+        sales_close_ts: 0,
+        claim_window: 0,
+        claim_deadline: 0,
+        prizes_swept: false,
+        rent_payer: Pubkey::default(),
+        ticket_registry: Pubkey::default(),
+        draw_commitment: [1; 32],
+        draw_slot: 0,
+        bundles_closed: 0,
+        prizes_carried_over: false,
+    }
+}
+
+/// Bundle of `lottery` holding the codes `first_ticket_index..+ticket_count`.
+pub fn bundle(lottery: Pubkey, first_ticket_index: u32, ticket_count: u16) -> TransactionBundle {
+    let mut tickets = [[0; 8]; 100];
+
+    for (i, ticket) in tickets.iter_mut().take(ticket_count as usize).enumerate() {
+        *ticket = ((first_ticket_index as u64) + (i as u64)).to_be_bytes();
+    }
+
+    TransactionBundle {
+        lottery_pda: lottery,
+        owner: Pubkey::new_unique(),
+        tickets,
+        refunded: false,
+        ticket_count,
+        first_ticket_index,
+        draws_tallied: 0,
+        tier_winning_tickets: Default::default(),
+        draws_rewarded: 0,
+        rent_payer: Pubkey::default(),
+        paid_from_balance: false,
+    }
+}
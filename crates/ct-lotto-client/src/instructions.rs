@@ -0,0 +1,944 @@
+//! Instruction builders. Each function mirrors one instruction of the program,
+//! deriving every PDA and associated token account it needs.
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::{system_program, sysvar};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token;
+use anchor_spl::token_2022::spl_token_2022;
+use ct_lotto_anchor::{accounts, instruction};
+use solana_system_interface::instruction as system_instruction;
+
+use crate::intent::{ed25519_verify_instruction, intent_hash, intent_message};
+use crate::pda::{
+    bundle_pda, configuration_pda, event_authority_pda, lottery_pda, player_account_pda,
+    player_tickets_pda, quick_pick_pda, referral_pda, series_pda, series_round_pda,
+    ticket_registry_pda, token_account_address, vault_address,
+};
+use crate::{LotteryTemplate, PurchaseIntent, QuickPickSource, TokenPayment, PROGRAM_ID};
+
+/// Arguments of `create_lottery_pda`, in instruction order.
+pub use ct_lotto_anchor::instruction::CreateLotteryPda as CreateLotteryArgs;
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+/// Optional token accounts of a payout instruction: (mint, vault, recipient ATA, token program).
+type PayoutTokenAccounts = (
+    Option<Pubkey>,
+    Option<Pubkey>,
+    Option<Pubkey>,
+    Option<Pubkey>,
+);
+
+fn payout_token_accounts(
+    lottery: &Pubkey,
+    recipient: &Pubkey,
+    token: Option<&TokenPayment>,
+) -> PayoutTokenAccounts {
+    match token {
+        Some(token) => (
+            Some(token.mint),
+            Some(vault_address(lottery, token)),
+            Some(token_account_address(recipient, token)),
+            Some(token.token_program),
+        ),
+        None => (None, None, None, None),
+    }
+}
+
+/// Player account a payout is credited to, with the token accounts of the payout:
+/// into the owner's player balance when `to_balance`, their wallet otherwise.
+fn owner_payout_accounts(
+    lottery: &Pubkey,
+    owner: &Pubkey,
+    to_balance: bool,
+    token: Option<&TokenPayment>,
+) -> (Option<Pubkey>, PayoutTokenAccounts) {
+    if !to_balance {
+        return (None, payout_token_accounts(lottery, owner, token));
+    }
+
+    let player_account = balance_account(owner, token);
+
+    (
+        Some(player_account),
+        payout_token_accounts(lottery, &player_account, token),
+    )
+}
+
+/// Player account holding `owner`'s balance in the lottery currency.
+fn balance_account(owner: &Pubkey, token: Option<&TokenPayment>) -> Pubkey {
+    let mint = token.map(|t| t.mint).unwrap_or_default();
+
+    player_account_pda(owner, &mint).0
+}
+
+/// Optional token accounts of a player balance transfer: (mint, balance vault, token program).
+fn balance_token_accounts(
+    player_account: &Pubkey,
+    token: Option<&TokenPayment>,
+) -> (Option<Pubkey>, Option<Pubkey>, Option<Pubkey>) {
+    match token {
+        Some(token) => (
+            Some(token.mint),
+            Some(token_account_address(player_account, token)),
+            Some(token.token_program),
+        ),
+        None => (None, None, None),
+    }
+}
+
+/// `payer` funds the rent and gets it back when the configuration is closed.
+pub fn create_configuration_pda(admin: &Pubkey, payer: &Pubkey) -> Instruction {
+    build(
+        accounts::CreateConfigurationPDA {
+            configuration: configuration_pda().0,
+            admin: *admin,
+            payer: *payer,
+            system_program: system_program::ID,
+            event_authority: event_authority_pda().0,
+            program: PROGRAM_ID,
+        },
+        instruction::CreateConfigurationPda {},
+    )
+}
+
+/// Admin handover, step one: `new_admin` may be a wallet or a multisig vault PDA.
+pub fn propose_admin(admin: &Pubkey, new_admin: &Pubkey) -> Instruction {
+    build(
+        accounts::ProposeAdmin {
+            configuration: configuration_pda().0,
+            admin: *admin,
+            event_authority: event_authority_pda().0,
+            program: PROGRAM_ID,
+        },
+        instruction::ProposeAdmin {
+            new_admin: *new_admin,
+        },
+    )
+}
+
+/// Admin handover, step two, signed by the proposed admin.
+pub fn accept_admin(pending_admin: &Pubkey) -> Instruction {
+    build(
+        accounts::AcceptAdmin {
+            configuration: configuration_pda().0,
+            pending_admin: *pending_admin,
+            event_authority: event_authority_pda().0,
+            program: PROGRAM_ID,
+        },
+        instruction::AcceptAdmin {},
+    )
+}
+
+/// `payer` funds the rent; it can differ from `admin`, e.g. when the admin is a multisig PDA.
+/// `switchboard_feed` is `None` for commit-reveal draws (`args.draw_commitment` set).
+pub fn create_lottery_pda(
+    admin: &Pubkey,
+    payer: &Pubkey,
+    switchboard_feed: Option<&Pubkey>,
+    token: Option<&TokenPayment>,
+    args: CreateLotteryArgs,
+) -> Instruction {
+    let lottery = lottery_pda(&args.lottery_seed).0;
+
+    build(
+        accounts::CreateLotteryPDA {
+            configuration: configuration_pda().0,
+            lottery,
+            admin: *admin,
+            payer: *payer,
+            switchboard_feed_btc_block_decimal: switchboard_feed.copied(),
+            payment_mint: token.map(|t| t.mint),
+            vault: token.map(|t| vault_address(&lottery, t)),
+            token_program: token.map(|t| t.token_program),
+            associated_token_program: token.map(|_| associated_token::ID),
+            system_program: system_program::ID,
+            event_authority: event_authority_pda().0,
+            program: PROGRAM_ID,
+        },
+        args,
+    )
+}
+
+/// Creates a series and opens its first round.
+pub fn create_lottery_series(
+    admin: &Pubkey,
+    payer: &Pubkey,
+    series_seed: &str,
+    switchboard_feed: &Pubkey,
+    template: LotteryTemplate,
+    round_duration: i64,
+) -> Instruction {
+    let series = series_pda(series_seed).0;
+
+    build(
+        accounts::CreateLotterySeries {
+            configuration: configuration_pda().0,
+            series,
+            lottery: series_round_pda(&series, 1).0,
+            admin: *admin,
+            payer: *payer,
+            switchboard_feed_btc_block_decimal: *switchboard_feed,
+            system_program: system_program::ID,
+            event_authority: event_authority_pda().0,
+            program: PROGRAM_ID,
+        },
+        instruction::CreateLotterySeries {
+            series_seed: series_seed.to_string(),
+            template,
+            round_duration,
+        },
+    )
+}
+
+/// Rolls a series from `current_round` to the next round; `payer` funds its rent.
+pub fn roll_series(payer: &Pubkey, series_seed: &str, current_round: u32) -> Instruction {
+    let series = series_pda(series_seed).0;
+
+    build(
+        accounts::RollSeries {
+            series,
+            current_lottery: series_round_pda(&series, current_round).0,
+            next_lottery: series_round_pda(&series, current_round + 1).0,
+            payer: *payer,
+            system_program: system_program::ID,
+            event_authority: event_authority_pda().0,
+            program: PROGRAM_ID,
+        },
+        instruction::RollSeries {},
+    )
+}
+
+/// Carries what settled `round` left behind into the current round of the series.
+pub fn carry_over_prizes(series_seed: &str, round: u32, current_round: u32) -> Instruction {
+    let series = series_pda(series_seed).0;
+
+    build(
+        accounts::CarryOverPrizes {
+            series,
+            round: series_round_pda(&series, round).0,
+            current_lottery: series_round_pda(&series, current_round).0,
+            event_authority: event_authority_pda().0,
+            program: PROGRAM_ID,
+        },
+        instruction::CarryOverPrizes {},
+    )
+}
+
+/// SOL payment for a bundle (ticket count × price). `create_transaction_bundle` requires it
+/// as the instruction directly before it, in the same transaction.
+pub fn ticket_payment(owner: &Pubkey, lottery: &Pubkey, lamports: u64) -> Instruction {
+    system_instruction::transfer(owner, lottery, lamports)
+}
+
+/// Token payment into the lottery vault, the token counterpart of [`ticket_payment`].
+pub fn token_ticket_payment(
+    owner: &Pubkey,
+    lottery: &Pubkey,
+    token: &TokenPayment,
+    amount: u64,
+    decimals: u8,
+) -> anchor_lang::Result<Instruction> {
+    Ok(spl_token_2022::instruction::transfer_checked(
+        &token.token_program,
+        &token_account_address(owner, token),
+        &token.mint,
+        &vault_address(lottery, token),
+        owner,
+        &[],
+        amount,
+        decimals,
+    )?)
+}
+
+/// `lottery_seed` is the lottery's own `lottery_seed` field, which seeds its bundles
+/// (for series rounds it differs from the seeds of the lottery PDA).
+/// `ticket_registry` is `Lottery::ticket_registry` when the lottery has one.
+/// Without a `funder`, [`ticket_payment`] (or [`token_ticket_payment`]) must come right
+/// before this instruction; a `funder` pays in-program instead, as needed under CPI.
+#[allow(clippy::too_many_arguments)]
+pub fn create_transaction_bundle(
+    admin: &Pubkey,
+    payer: &Pubkey,
+    lottery: &Pubkey,
+    lottery_seed: &str,
+    tx_sig_hash: [u8; 32],
+    owner: &Pubkey,
+    purchased_numbers: Vec<[u8; 8]>,
+    referrer: Option<&Pubkey>,
+    ticket_registry: Option<&Pubkey>,
+    funder: Option<&Pubkey>,
+    token: Option<&TokenPayment>,
+) -> Instruction {
+    let lottery = *lottery;
+    let funded_token = funder.zip(token);
+
+    build(
+        accounts::CreateTransactionBundle {
+            configuration: configuration_pda().0,
+            lottery,
+            bundle: bundle_pda(lottery_seed, &tx_sig_hash).0,
+            ticket_registry: ticket_registry.copied(),
+            player_tickets: player_tickets_pda(&lottery, owner).0,
+            referral: referrer.map(|r| referral_pda(&lottery, r).0),
+            admin: *admin,
+            payer: *payer,
+            payment_mint: token.map(|t| t.mint),
+            funder: funder.copied(),
+            vault: funded_token.map(|(_, t)| vault_address(&lottery, t)),
+            funder_token_account: funded_token.map(|(f, t)| token_account_address(f, t)),
+            token_program: funded_token.map(|(_, t)| t.token_program),
+            instructions: sysvar::instructions::ID,
+            system_program: system_program::ID,
+            event_authority: event_authority_pda().0,
+            program: PROGRAM_ID,
+        },
+        instruction::CreateTransactionBundle {
+            _lottery_seed: lottery_seed.to_string(),
+            tx_sig_hash,
+            owner: *owner,
+            purchased_numbers,
+            referrer: referrer.copied(),
+        },
+    )
+}
+
+/// Ed25519 signature check of a signed intent followed by the relayed purchase,
+/// to be sent in this order in one transaction paid by `relayer`.
+/// `lottery_seed` is the lottery's own `lottery_seed` field, as for `create_transaction_bundle`.
+pub fn purchase_with_intent(
+    relayer: &Pubkey,
+    lottery_seed: &str,
+    intent: PurchaseIntent,
+    signature: &[u8; 64],
+    ticket_registry: Option<&Pubkey>,
+    token: Option<&TokenPayment>,
+) -> [Instruction; 2] {
+    let message = intent_message(&intent);
+    let tx_sig_hash = intent_hash(&intent);
+    let player_account = balance_account(&intent.owner, token);
+    let (payment_mint, balance_vault, token_program) =
+        balance_token_accounts(&player_account, token);
+
+    [
+        ed25519_verify_instruction(&intent.owner, signature, &message),
+        build(
+            accounts::PurchaseWithIntent {
+                lottery: intent.lottery,
+                bundle: bundle_pda(lottery_seed, &tx_sig_hash).0,
+                ticket_registry: ticket_registry.copied(),
+                player_tickets: player_tickets_pda(&intent.lottery, &intent.owner).0,
+                referral: intent.referrer.map(|r| referral_pda(&intent.lottery, &r).0),
+                player_account,
+                payer: *relayer,
+                payment_mint,
+                balance_vault,
+                vault: token.map(|t| vault_address(&intent.lottery, t)),
+                token_program,
+                instructions: sysvar::instructions::ID,
+                system_program: system_program::ID,
+                event_authority: event_authority_pda().0,
+                program: PROGRAM_ID,
+            },
+            instruction::PurchaseWithIntent {
+                _lottery_seed: lottery_seed.to_string(),
+                tx_sig_hash,
+                intent,
+            },
+        ),
+    ]
+}
+
+/// Ticket purchase paid from the owner's player balance, with no payment transfer.
+#[allow(clippy::too_many_arguments)]
+pub fn purchase_from_balance(
+    owner: &Pubkey,
+    lottery: &Pubkey,
+    lottery_seed: &str,
+    tx_sig_hash: [u8; 32],
+    purchased_numbers: Vec<[u8; 8]>,
+    referrer: Option<&Pubkey>,
+    ticket_registry: Option<&Pubkey>,
+    token: Option<&TokenPayment>,
+) -> Instruction {
+    let player_account = balance_account(owner, token);
+    let (payment_mint, balance_vault, token_program) =
+        balance_token_accounts(&player_account, token);
+
+    build(
+        accounts::PurchaseFromBalance {
+            lottery: *lottery,
+            bundle: bundle_pda(lottery_seed, &tx_sig_hash).0,
+            ticket_registry: ticket_registry.copied(),
+            player_tickets: player_tickets_pda(lottery, owner).0,
+            referral: referrer.map(|r| referral_pda(lottery, r).0),
+            player_account,
+            owner: *owner,
+            payment_mint,
+            balance_vault,
+            vault: token.map(|t| vault_address(lottery, t)),
+            token_program,
+            system_program: system_program::ID,
+            event_authority: event_authority_pda().0,
+            program: PROGRAM_ID,
+        },
+        instruction::PurchaseFromBalance {
+            _lottery_seed: lottery_seed.to_string(),
+            tx_sig_hash,
+            purchased_numbers,
+            referrer: referrer.copied(),
+        },
+    )
+}
+
+pub fn create_ticket_registry(admin: &Pubkey, payer: &Pubkey, lottery: &Pubkey) -> Instruction {
+    build(
+        accounts::CreateTicketRegistry {
+            configuration: configuration_pda().0,
+            lottery: *lottery,
+            ticket_registry: ticket_registry_pda(lottery).0,
+            admin: *admin,
+            payer: *payer,
+            system_program: system_program::ID,
+            event_authority: event_authority_pda().0,
+            program: PROGRAM_ID,
+        },
+        instruction::CreateTicketRegistry {},
+    )
+}
+
+/// `rent_payer` is the registry's `rent_payer`, refunded its rent.
+pub fn close_ticket_registry(admin: &Pubkey, lottery: &Pubkey, rent_payer: &Pubkey) -> Instruction {
+    build(
+        accounts::CloseTicketRegistry {
+            configuration: configuration_pda().0,
+            lottery: *lottery,
+            ticket_registry: ticket_registry_pda(lottery).0,
+            rent_payer: *rent_payer,
+            admin: *admin,
+            event_authority: event_authority_pda().0,
+            program: PROGRAM_ID,
+        },
+        instruction::CloseTicketRegistry {},
+    )
+}
+
+/// Quick pick of `ticket_count` codes, fulfilled later by `fulfill_quick_pick`.
+/// Switchboard sources must follow the randomness commit in the same transaction.
+#[allow(clippy::too_many_arguments)]
+pub fn request_quick_pick(
+    owner: &Pubkey,
+    lottery: &Pubkey,
+    tx_sig_hash: [u8; 32],
+    ticket_count: u8,
+    source: QuickPickSource,
+    referrer: Option<&Pubkey>,
+    token: Option<&TokenPayment>,
+) -> Instruction {
+    let randomness_account = match source {
+        QuickPickSource::Switchboard { randomness_account } => Some(randomness_account),
+        QuickPickSource::CommitReveal { .. } => None,
+    };
+
+    build(
+        accounts::RequestQuickPick {
+            lottery: *lottery,
+            quick_pick: quick_pick_pda(lottery, &tx_sig_hash).0,
+            player_account: balance_account(owner, token),
+            randomness_account,
+            owner: *owner,
+            system_program: system_program::ID,
+            event_authority: event_authority_pda().0,
+            program: PROGRAM_ID,
+        },
+        instruction::RequestQuickPick {
+            tx_sig_hash,
+            ticket_count,
+            source,
+            referrer: referrer.copied(),
+        },
+    )
+}
+
+/// Fulfils a quick pick: `secret` for commit-reveal sources, while Switchboard
+/// sources must follow the randomness reveal in the same transaction.
+/// `lottery_seed` and `ticket_registry` are as for `create_transaction_bundle`,
+/// `referrer` is the one given to `request_quick_pick`.
+#[allow(clippy::too_many_arguments)]
+pub fn fulfill_quick_pick(
+    owner: &Pubkey,
+    lottery: &Pubkey,
+    lottery_seed: &str,
+    tx_sig_hash: [u8; 32],
+    source: QuickPickSource,
+    secret: Option<[u8; 32]>,
+    referrer: Option<&Pubkey>,
+    ticket_registry: Option<&Pubkey>,
+    token: Option<&TokenPayment>,
+) -> Instruction {
+    let player_account = balance_account(owner, token);
+    let (payment_mint, balance_vault, token_program) =
+        balance_token_accounts(&player_account, token);
+    let (randomness_account, slot_hashes) = match source {
+        QuickPickSource::Switchboard { randomness_account } => (Some(randomness_account), None),
+        QuickPickSource::CommitReveal { .. } => (None, Some(sysvar::slot_hashes::ID)),
+    };
+
+    build(
+        accounts::FulfillQuickPick {
+            lottery: *lottery,
+            quick_pick: quick_pick_pda(lottery, &tx_sig_hash).0,
+            bundle: bundle_pda(lottery_seed, &tx_sig_hash).0,
+            ticket_registry: ticket_registry.copied(),
+            player_tickets: player_tickets_pda(lottery, owner).0,
+            referral: referrer.map(|r| referral_pda(lottery, r).0),
+            player_account,
+            randomness_account,
+            slot_hashes,
+            owner: *owner,
+            payment_mint,
+            balance_vault,
+            vault: token.map(|t| vault_address(lottery, t)),
+            token_program,
+            system_program: system_program::ID,
+            event_authority: event_authority_pda().0,
+            program: PROGRAM_ID,
+        },
+        instruction::FulfillQuickPick {
+            _lottery_seed: lottery_seed.to_string(),
+            secret,
+        },
+    )
+}
+
+pub fn cancel_quick_pick(owner: &Pubkey, lottery: &Pubkey, tx_sig_hash: [u8; 32]) -> Instruction {
+    build(
+        accounts::CancelQuickPick {
+            quick_pick: quick_pick_pda(lottery, &tx_sig_hash).0,
+            owner: *owner,
+            event_authority: event_authority_pda().0,
+            program: PROGRAM_ID,
+        },
+        instruction::CancelQuickPick {},
+    )
+}
+
+/// Deposit into the owner's SOL balance, or token balance of `token.mint`.
+pub fn deposit_balance(owner: &Pubkey, amount: u64, token: Option<&TokenPayment>) -> Instruction {
+    let player_account = balance_account(owner, token);
+    let (payment_mint, balance_vault, token_program) =
+        balance_token_accounts(&player_account, token);
+
+    build(
+        accounts::DepositBalance {
+            player_account,
+            owner: *owner,
+            payment_mint,
+            balance_vault,
+            owner_token_account: token.map(|t| token_account_address(owner, t)),
+            token_program,
+            associated_token_program: token.map(|_| associated_token::ID),
+            system_program: system_program::ID,
+            event_authority: event_authority_pda().0,
+            program: PROGRAM_ID,
+        },
+        instruction::DepositBalance { amount },
+    )
+}
+
+pub fn withdraw_balance(owner: &Pubkey, amount: u64, token: Option<&TokenPayment>) -> Instruction {
+    let player_account = balance_account(owner, token);
+    let (payment_mint, balance_vault, token_program) =
+        balance_token_accounts(&player_account, token);
+
+    build(
+        accounts::WithdrawBalance {
+            player_account,
+            owner: *owner,
+            payment_mint,
+            balance_vault,
+            owner_token_account: token.map(|t| token_account_address(owner, t)),
+            token_program,
+            event_authority: event_authority_pda().0,
+            program: PROGRAM_ID,
+        },
+        instruction::WithdrawBalance { amount },
+    )
+}
+
+pub fn draw_winner(
+    admin: &Pubkey,
+    lottery: &Pubkey,
+    switchboard_feed: &Pubkey,
+    winning_bytes: [u8; 8],
+) -> Instruction {
+    build(
+        accounts::DrawWinner {
+            configuration: configuration_pda().0,
+            lottery: *lottery,
+            switchboard_feed_btc_block_decimal: *switchboard_feed,
+            admin: *admin,
+            event_authority: event_authority_pda().0,
+            program: PROGRAM_ID,
+        },
+        instruction::DrawWinner { winning_bytes },
+    )
+}
+
+/// Cancels a commit-reveal lottery whose draw slot hash expired unrevealed
+/// (permissionless), so its bundles can be refunded.
+pub fn cancel_expired_draw(lottery: &Pubkey) -> Instruction {
+    build(
+        accounts::CancelExpiredDraw {
+            lottery: *lottery,
+            slot_hashes: sysvar::slot_hashes::ID,
+            event_authority: event_authority_pda().0,
+            program: PROGRAM_ID,
+        },
+        instruction::CancelExpiredDraw {},
+    )
+}
+
+/// Draws every draw of a commit-reveal lottery; `secret` hashes to its `draw_commitment`.
+pub fn reveal_draw(admin: &Pubkey, lottery: &Pubkey, secret: [u8; 32]) -> Instruction {
+    build(
+        accounts::RevealDraw {
+            configuration: configuration_pda().0,
+            lottery: *lottery,
+            slot_hashes: sysvar::slot_hashes::ID,
+            admin: *admin,
+            event_authority: event_authority_pda().0,
+            program: PROGRAM_ID,
+        },
+        instruction::RevealDraw { secret },
+    )
+}
+
+pub fn tally_transaction_bundle(admin: &Pubkey, lottery: &Pubkey, bundle: &Pubkey) -> Instruction {
+    build(
+        accounts::TallyTransactionBundle {
+            configuration: configuration_pda().0,
+            lottery: *lottery,
+            bundle: *bundle,
+            admin: *admin,
+            event_authority: event_authority_pda().0,
+            program: PROGRAM_ID,
+        },
+        instruction::TallyTransactionBundle {},
+    )
+}
+
+/// `to_balance` credits the prize to the owner's player balance instead of their wallet.
+pub fn reward_transaction_bundle(
+    admin: &Pubkey,
+    lottery: &Pubkey,
+    bundle: &Pubkey,
+    owner: &Pubkey,
+    draw_index: u8,
+    to_balance: bool,
+    token: Option<&TokenPayment>,
+) -> Instruction {
+    let (player_account, (payment_mint, vault, owner_token_account, token_program)) =
+        owner_payout_accounts(lottery, owner, to_balance, token);
+
+    build(
+        accounts::RewardTransactionBundle {
+            configuration: configuration_pda().0,
+            lottery: *lottery,
+            bundle: *bundle,
+            owner: *owner,
+            player_account,
+            admin: *admin,
+            payment_mint,
+            vault,
+            owner_token_account,
+            token_program,
+            system_program: system_program::ID,
+            event_authority: event_authority_pda().0,
+            program: PROGRAM_ID,
+        },
+        instruction::RewardTransactionBundle { draw_index },
+    )
+}
+
+/// Prize claim signed by the winner themselves; `to_balance` credits it to their
+/// player balance instead of their wallet.
+pub fn claim_prize(
+    owner: &Pubkey,
+    lottery: &Pubkey,
+    bundle: &Pubkey,
+    draw_index: u8,
+    to_balance: bool,
+    token: Option<&TokenPayment>,
+) -> Instruction {
+    let (player_account, (payment_mint, vault, owner_token_account, token_program)) =
+        owner_payout_accounts(lottery, owner, to_balance, token);
+
+    build(
+        accounts::ClaimPrize {
+            lottery: *lottery,
+            bundle: *bundle,
+            owner: *owner,
+            player_account,
+            payment_mint,
+            vault,
+            owner_token_account,
+            token_program,
+            system_program: system_program::ID,
+            event_authority: event_authority_pda().0,
+            program: PROGRAM_ID,
+        },
+        instruction::ClaimPrize { draw_index },
+    )
+}
+
+/// Sweeps unpaid prizes after the claim deadline: into `rollover` (a later round of
+/// the same series) for series rounds, to the treasury (the configured admin) otherwise.
+pub fn sweep_unclaimed_prizes(
+    treasury: &Pubkey,
+    lottery: &Pubkey,
+    rollover: Option<&Pubkey>,
+    token: Option<&TokenPayment>,
+) -> Instruction {
+    let (payment_mint, vault, treasury_token_account, token_program) =
+        payout_token_accounts(lottery, treasury, token);
+
+    build(
+        accounts::SweepUnclaimedPrizes {
+            configuration: configuration_pda().0,
+            lottery: *lottery,
+            rollover: rollover.copied(),
+            treasury: *treasury,
+            payment_mint,
+            vault,
+            treasury_token_account,
+            token_program,
+            system_program: system_program::ID,
+            event_authority: event_authority_pda().0,
+            program: PROGRAM_ID,
+        },
+        instruction::SweepUnclaimedPrizes {},
+    )
+}
+
+/// `to_balance` credits the refund to the owner's player balance; required for
+/// bundles bought from it (`TransactionBundle::paid_from_balance`).
+pub fn refund_transaction_bundle(
+    admin: &Pubkey,
+    lottery: &Pubkey,
+    bundle: &Pubkey,
+    owner: &Pubkey,
+    deduct_fee: bool,
+    to_balance: bool,
+    token: Option<&TokenPayment>,
+) -> Instruction {
+    let (player_account, (payment_mint, vault, owner_token_account, token_program)) =
+        owner_payout_accounts(lottery, owner, to_balance, token);
+
+    build(
+        accounts::RefundTransactionBundle {
+            configuration: configuration_pda().0,
+            lottery: *lottery,
+            bundle: *bundle,
+            owner: *owner,
+            player_account,
+            admin: *admin,
+            payment_mint,
+            vault,
+            owner_token_account,
+            token_program,
+            system_program: system_program::ID,
+            event_authority: event_authority_pda().0,
+            program: PROGRAM_ID,
+        },
+        instruction::RefundTransactionBundle { deduct_fee },
+    )
+}
+
+pub fn withdraw_platform_fees(
+    admin: &Pubkey,
+    lottery: &Pubkey,
+    token: Option<&TokenPayment>,
+) -> Instruction {
+    let (payment_mint, vault, admin_token_account, token_program) =
+        payout_token_accounts(lottery, admin, token);
+
+    build(
+        accounts::WithdrawPlatformFees {
+            configuration: configuration_pda().0,
+            lottery: *lottery,
+            admin: *admin,
+            payment_mint,
+            vault,
+            admin_token_account,
+            token_program,
+            system_program: system_program::ID,
+            event_authority: event_authority_pda().0,
+            program: PROGRAM_ID,
+        },
+        instruction::WithdrawPlatformFees {},
+    )
+}
+
+pub fn claim_referral_rewards(
+    referrer: &Pubkey,
+    lottery: &Pubkey,
+    token: Option<&TokenPayment>,
+) -> Instruction {
+    let (payment_mint, vault, referrer_token_account, token_program) =
+        payout_token_accounts(lottery, referrer, token);
+
+    build(
+        accounts::ClaimReferralRewards {
+            lottery: *lottery,
+            referral: referral_pda(lottery, referrer).0,
+            referrer: *referrer,
+            payment_mint,
+            vault,
+            referrer_token_account,
+            token_program,
+            system_program: system_program::ID,
+            event_authority: event_authority_pda().0,
+            program: PROGRAM_ID,
+        },
+        instruction::ClaimReferralRewards {},
+    )
+}
+
+pub fn seed_prize(
+    admin: &Pubkey,
+    lottery: &Pubkey,
+    amount: u64,
+    token: Option<&TokenPayment>,
+) -> Instruction {
+    let (payment_mint, vault, admin_token_account, token_program) =
+        payout_token_accounts(lottery, admin, token);
+
+    build(
+        accounts::SeedPrize {
+            configuration: configuration_pda().0,
+            lottery: *lottery,
+            admin: *admin,
+            payment_mint,
+            vault,
+            admin_token_account,
+            token_program,
+            system_program: system_program::ID,
+            event_authority: event_authority_pda().0,
+            program: PROGRAM_ID,
+        },
+        instruction::SeedPrize { amount },
+    )
+}
+
+pub fn reclaim_seeded_prize(
+    admin: &Pubkey,
+    lottery: &Pubkey,
+    token: Option<&TokenPayment>,
+) -> Instruction {
+    let (payment_mint, vault, admin_token_account, token_program) =
+        payout_token_accounts(lottery, admin, token);
+
+    build(
+        accounts::ReclaimSeededPrize {
+            configuration: configuration_pda().0,
+            lottery: *lottery,
+            admin: *admin,
+            payment_mint,
+            vault,
+            admin_token_account,
+            token_program,
+            system_program: system_program::ID,
+            event_authority: event_authority_pda().0,
+            program: PROGRAM_ID,
+        },
+        instruction::ReclaimSeededPrize {},
+    )
+}
+
+pub fn close_lottery(admin: &Pubkey, lottery: &Pubkey) -> Instruction {
+    build(
+        accounts::CloseLottery {
+            configuration: configuration_pda().0,
+            lottery: *lottery,
+            admin: *admin,
+            system_program: system_program::ID,
+            event_authority: event_authority_pda().0,
+            program: PROGRAM_ID,
+        },
+        instruction::CloseLottery {},
+    )
+}
+
+/// `rent_payer` is the bundle's recorded `rent_payer`, refunded its rent.
+/// Only settled bundles (refunded, or tallied and paid) can be closed.
+pub fn close_transaction_bundle(
+    admin: &Pubkey,
+    lottery: &Pubkey,
+    bundle: &Pubkey,
+    rent_payer: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::CloseTransactionBundle {
+            configuration: configuration_pda().0,
+            lottery: *lottery,
+            bundle: *bundle,
+            admin: *admin,
+            rent_payer: *rent_payer,
+            system_program: system_program::ID,
+            event_authority: event_authority_pda().0,
+            program: PROGRAM_ID,
+        },
+        instruction::CloseTransactionBundle {},
+    )
+}
+
+/// Remaining funds go to `admin`, the rent to the lottery's recorded `rent_payer`.
+pub fn close_lottery_pda(
+    admin: &Pubkey,
+    lottery: &Pubkey,
+    rent_payer: &Pubkey,
+    token: Option<&TokenPayment>,
+) -> Instruction {
+    let (payment_mint, vault, admin_token_account, token_program) =
+        payout_token_accounts(lottery, admin, token);
+
+    build(
+        accounts::CloseLotteryPDA {
+            configuration: configuration_pda().0,
+            lottery: *lottery,
+            admin: *admin,
+            rent_payer: *rent_payer,
+            payment_mint,
+            vault,
+            admin_token_account,
+            token_program,
+            system_program: system_program::ID,
+            event_authority: event_authority_pda().0,
+            program: PROGRAM_ID,
+        },
+        instruction::CloseLotteryPda {},
+    )
+}
+
+/// `rent_payer` is the configuration's recorded `rent_payer`, refunded its rent.
+pub fn close_configuration_pda(admin: &Pubkey, rent_payer: &Pubkey) -> Instruction {
+    build(
+        accounts::CloseConfigurationPDA {
+            configuration: configuration_pda().0,
+            admin: *admin,
+            rent_payer: *rent_payer,
+            system_program: system_program::ID,
+            event_authority: event_authority_pda().0,
+            program: PROGRAM_ID,
+        },
+        instruction::CloseConfigurationPda {},
+    )
+}
@@ -0,0 +1,67 @@
+//! Signed purchase intents. The player signs [`intent_message`] with their
+//! wallet key; a relayer submits the signature through the Ed25519 program
+//! right before `purchase_with_intent` (see `instructions::purchase_with_intent`).
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::AnchorSerialize;
+use solana_sdk_ids::ed25519_program;
+use solana_sha256_hasher::hash;
+
+use crate::PurchaseIntent;
+
+const PUBKEY_SIZE: usize = 32;
+const SIGNATURE_SIZE: usize = 64;
+const OFFSETS_START: usize = 2;
+const OFFSETS_SIZE: usize = 14;
+/// Instruction index meaning "this instruction's own data".
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// Bytes the player signs: the borsh encoding of the intent.
+pub fn intent_message(intent: &PurchaseIntent) -> Vec<u8> {
+    intent
+        .try_to_vec()
+        .expect("purchase intent serialization cannot fail")
+}
+
+/// Hash seeding the bundle of a relayed purchase, so each intent maps to one bundle.
+pub fn intent_hash(intent: &PurchaseIntent) -> [u8; 32] {
+    hash(&intent_message(intent)).to_bytes()
+}
+
+/// Ed25519 program instruction verifying `signature` by `signer` over `message`,
+/// with key, signature and message stored in its own data.
+pub fn ed25519_verify_instruction(
+    signer: &Pubkey,
+    signature: &[u8; SIGNATURE_SIZE],
+    message: &[u8],
+) -> Instruction {
+    let public_key_offset = OFFSETS_START + OFFSETS_SIZE;
+    let signature_offset = public_key_offset + PUBKEY_SIZE;
+    let message_offset = signature_offset + SIGNATURE_SIZE;
+
+    let offsets = [
+        signature_offset as u16,
+        CURRENT_INSTRUCTION,
+        public_key_offset as u16,
+        CURRENT_INSTRUCTION,
+        message_offset as u16,
+        message.len() as u16,
+        CURRENT_INSTRUCTION,
+    ];
+
+    // [signature count, padding, offsets, key, signature, message]
+    let mut data = Vec::with_capacity(message_offset + message.len());
+    data.extend_from_slice(&[1, 0]);
+    for offset in offsets {
+        data.extend_from_slice(&offset.to_le_bytes());
+    }
+    data.extend_from_slice(signer.as_ref());
+    data.extend_from_slice(signature);
+    data.extend_from_slice(message);
+
+    Instruction {
+        program_id: ed25519_program::ID,
+        accounts: vec![],
+        data,
+    }
+}
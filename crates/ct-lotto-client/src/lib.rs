@@ -0,0 +1,37 @@
+//! Rust client for the `ct_lotto_anchor` program.
+//!
+//! - [`audit`]: offline reconciliation of a snapshot of program accounts
+//! - [`pda`]: addresses of the configuration, series, lottery, bundle, registry and vault accounts
+//! - [`instructions`]: typed builders for every program instruction
+//! - [`intent`]: signed purchase intents for relayed (gasless) purchases
+//! - [`state`]: account deserializers
+//! - [`proof`]: self-contained, offline-verifiable draw proofs
+//! - [`settlement`]: off-chain preview of tallies, payouts and refunds
+//! - [`ticket`]: canonical ticket code encoding, shared with the on-chain `lotto_util`
+pub mod audit;
+#[cfg(test)]
+mod fixtures;
+pub mod instructions;
+pub mod intent;
+pub mod pda;
+pub mod proof;
+pub mod settlement;
+pub mod state;
+pub mod ticket;
+
+pub use anchor_lang::prelude::Pubkey;
+pub use anchor_lang::solana_program::instruction::Instruction;
+pub use ct_lotto_anchor::account_struct::{
+    Configuration, Draw, GameType, Lottery, LotterySeries, LotteryTemplate, PlayerAccount,
+    PlayerTickets, PrizeTier, PurchaseIntent, QuickPickRequest, QuickPickSource, ReferralRewards,
+    TicketRegistry, TransactionBundle,
+};
+pub use ct_lotto_anchor::ID as PROGRAM_ID;
+
+/// Mint and token program of a lottery priced in an SPL token.
+/// Vault and owner token accounts are derived from it as associated token accounts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TokenPayment {
+    pub mint: Pubkey,
+    pub token_program: Pubkey,
+}
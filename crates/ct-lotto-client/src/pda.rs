@@ -0,0 +1,91 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+
+use crate::{Lottery, TokenPayment, PROGRAM_ID};
+
+/// `["configuration"]`
+pub fn configuration_pda() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"configuration"], &PROGRAM_ID)
+}
+
+/// `["lottery", lottery_seed]`
+pub fn lottery_pda(lottery_seed: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"lottery", lottery_seed.as_bytes()], &PROGRAM_ID)
+}
+
+/// `["series", series_seed]`
+pub fn series_pda(series_seed: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"series", series_seed.as_bytes()], &PROGRAM_ID)
+}
+
+/// `["lottery", series, round]`, lottery of one round of a series.
+pub fn series_round_pda(series: &Pubkey, round: u32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"lottery", series.as_ref(), &round.to_le_bytes()],
+        &PROGRAM_ID,
+    )
+}
+
+/// Address a lottery account was derived at, standalone or series round.
+pub fn lottery_address(lottery: &Lottery) -> Pubkey {
+    if lottery.is_series_round() {
+        series_round_pda(&lottery.series, lottery.round).0
+    } else {
+        lottery_pda(&lottery.lottery_seed).0
+    }
+}
+
+/// `["bundle", lottery_seed, tx_sig_hash]`
+pub fn bundle_pda(lottery_seed: &str, tx_sig_hash: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"bundle", lottery_seed.as_bytes(), tx_sig_hash],
+        &PROGRAM_ID,
+    )
+}
+
+/// `["player", lottery, owner]`, per-owner ticket count of a lottery.
+pub fn player_tickets_pda(lottery: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"player", lottery.as_ref(), owner.as_ref()], &PROGRAM_ID)
+}
+
+/// `["referrer", lottery, referrer]`, referral earnings of a referrer in a lottery.
+pub fn referral_pda(lottery: &Pubkey, referrer: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"referrer", lottery.as_ref(), referrer.as_ref()],
+        &PROGRAM_ID,
+    )
+}
+
+/// `["player_account", owner, payment_mint]`, deposited balance of a player
+/// (the default pubkey as mint for SOL).
+pub fn player_account_pda(owner: &Pubkey, payment_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"player_account", owner.as_ref(), payment_mint.as_ref()],
+        &PROGRAM_ID,
+    )
+}
+
+/// `["registry", lottery]`, sold codes of a lottery with unique codes.
+pub fn ticket_registry_pda(lottery: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"registry", lottery.as_ref()], &PROGRAM_ID)
+}
+
+/// `["quick_pick", lottery, tx_sig_hash]`, pending quick pick of a player.
+pub fn quick_pick_pda(lottery: &Pubkey, tx_sig_hash: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"quick_pick", lottery.as_ref(), tx_sig_hash], &PROGRAM_ID)
+}
+
+/// `["__event_authority"]`, signer of the program's self-CPI events.
+pub fn event_authority_pda() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"__event_authority"], &PROGRAM_ID)
+}
+
+/// Lottery-owned token vault of a token lottery.
+pub fn vault_address(lottery: &Pubkey, token: &TokenPayment) -> Pubkey {
+    get_associated_token_address_with_program_id(lottery, &token.mint, &token.token_program)
+}
+
+/// Associated token account of `owner` for the lottery mint.
+pub fn token_account_address(owner: &Pubkey, token: &TokenPayment) -> Pubkey {
+    get_associated_token_address_with_program_id(owner, &token.mint, &token.token_program)
+}
@@ -0,0 +1,590 @@
+//! Self-contained draw proofs.
+//!
+//! A proof embeds the raw lottery and bundle account data, so a third party can
+//! recompute the draw offline with the program's own logic: the winning code or
+//! raffle index as `draw_winner` derives it from the feed value and slot the draw
+//! recorded, or as `reveal_draw` derives it from the revealed secret and the hash
+//! of the draw slot, and the winning tickets and shares as the tally and reward
+//! instructions count them. The feed moves on after the draw, so its account data
+//! is only embedded (and checked) when captured at the recorded slot.
+use std::str::FromStr;
+
+use anchor_lang::prelude::Pubkey;
+use base64::Engine;
+use ct_lotto_anchor::lotto_util::{
+    commit_reveal_code_range, commit_reveal_winning_code, feed_value_from_data, mix_slot_hash,
+    raffle_winning_index, score_ticket, ticket_code_to_hex,
+};
+use serde::{Deserialize, Serialize};
+use solana_sha256_hasher::hash;
+
+use crate::pda::lottery_address;
+use crate::settlement::expected_payouts;
+use crate::state::{bundle_tickets, deserialize_lottery, deserialize_transaction_bundle};
+use crate::{GameType, Lottery, PrizeTier, TransactionBundle, PROGRAM_ID};
+
+pub const DRAW_PROOF_VERSION: u8 = 3;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofAccount {
+    pub pubkey: String,
+    /// Base64 account data.
+    pub data: String,
+}
+
+/// What `reveal_draw` mixed into a commit-reveal draw, as its `DrawRevealed` event
+/// recorded it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofReveal {
+    /// Base64 secret the lottery committed to.
+    pub secret: String,
+    pub draw_slot: u64,
+    /// Base64 hash of `draw_slot`.
+    pub slot_hash: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofWinner {
+    pub bundle: String,
+    pub owner: String,
+    pub tier_winning_tickets: Vec<u16>,
+    /// Winning ticket codes, in hex.
+    pub winning_tickets: Vec<String>,
+    pub amount: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DrawProof {
+    pub version: u8,
+    pub program_id: String,
+    pub lottery_seed: String,
+    pub lottery: ProofAccount,
+    /// Feed account captured at `feed_slot`, when available.
+    pub feed: Option<ProofAccount>,
+    /// Commit-reveal draws only.
+    pub reveal: Option<ProofReveal>,
+    /// Feed value the draw recorded, for feed draws.
+    pub block_height: Option<u64>,
+    /// Slot of the feed result the draw recorded, for feed draws.
+    pub feed_slot: Option<u64>,
+    pub draw_index: u8,
+    pub game_type: String,
+    pub winning_code: String,
+    pub winning_ticket_index: Option<u32>,
+    pub tickets_sold: u32,
+    pub prize_pool: u64,
+    pub bundles: Vec<ProofAccount>,
+    pub winners: Vec<ProofWinner>,
+}
+
+/// Recomputes a draw from raw account data and packages it as a proof.
+/// `feed` is the feed account captured at the slot the draw read it in, if any.
+/// `reveal` is the secret and draw slot hash of a commit-reveal draw, required
+/// for those. Fails if the inputs do not reproduce what the program recorded.
+pub fn build_draw_proof(
+    lottery_key: &Pubkey,
+    lottery_data: &[u8],
+    feed: Option<(&Pubkey, &[u8])>,
+    reveal: Option<(&[u8; 32], &[u8; 32])>,
+    draw_index: u8,
+    bundle_data: &[(Pubkey, Vec<u8>)],
+) -> Result<DrawProof, String> {
+    let lottery = deserialize_lottery(lottery_data).map_err(|e| format!("lottery: {e}"))?;
+
+    if lottery_address(&lottery) != *lottery_key {
+        return Err(format!(
+            "{lottery_key} is not the lottery PDA of seed {}",
+            lottery.lottery_seed
+        ));
+    }
+
+    let draw = lottery
+        .draws
+        .get(draw_index as usize)
+        .ok_or_else(|| format!("lottery has no draw {draw_index}"))?;
+
+    if !draw.drawn {
+        return Err(format!("draw {draw_index} has not taken place"));
+    }
+
+    // draw_winner records the feed value it checked, reveal_draw the code it derived
+    let winning_code = draw.winning_code;
+
+    if lottery.is_commit_reveal() {
+        let (secret, slot_hash) = reveal.ok_or_else(|| {
+            format!("{lottery_key} is drawn by commit-reveal: its secret and slot hash are needed")
+        })?;
+
+        if feed.is_some() {
+            return Err(format!(
+                "{lottery_key} is drawn by commit-reveal, not from a feed"
+            ));
+        }
+
+        verify_reveal(&lottery, secret, slot_hash, draw_index)?;
+    } else if reveal.is_some() {
+        return Err(format!(
+            "{lottery_key} is drawn from a feed, not by commit-reveal"
+        ));
+    }
+
+    if let Some((feed_key, feed_data)) = feed {
+        if lottery.switchboard_feed_btc_block_decimal != *feed_key {
+            return Err(format!(
+                "lottery draws from feed {}",
+                lottery.switchboard_feed_btc_block_decimal
+            ));
+        }
+
+        // Exactly what read_feed_value hands to draw_winner
+        let (value, slot) = feed_value_from_data(feed_data).map_err(|e| format!("feed: {e}"))?;
+
+        if slot != draw.feed_slot {
+            return Err(format!(
+                "feed data is of slot {slot} but draw {draw_index} read slot {}",
+                draw.feed_slot
+            ));
+        }
+
+        if value != winning_code {
+            return Err(format!(
+                "feed reads {} but draw {draw_index} recorded {}",
+                u64::from_be_bytes(value),
+                u64::from_be_bytes(winning_code)
+            ));
+        }
+    }
+
+    let winning_ticket_index = match lottery.game_type {
+        GameType::CodeMatch => None,
+        GameType::Raffle => {
+            let index = raffle_winning_index(&winning_code, lottery.tickets_sold);
+
+            if index != draw.winning_ticket_index {
+                return Err(format!(
+                    "draw {draw_index} recorded ticket index {} instead of {index}",
+                    draw.winning_ticket_index
+                ));
+            }
+
+            Some(index)
+        }
+    };
+
+    let mut bundles: Vec<(Pubkey, TransactionBundle)> = bundle_data
+        .iter()
+        .map(|(key, data)| {
+            deserialize_transaction_bundle(data)
+                .map(|bundle| (*key, bundle))
+                .map_err(|e| format!("bundle {key}: {e}"))
+        })
+        .collect::<Result<_, _>>()?;
+
+    bundles.retain(|(_, bundle)| bundle.lottery_pda == *lottery_key);
+    bundles.sort_by_key(|(_, bundle)| bundle.first_ticket_index);
+
+    // Winners are only provable against the full set of tickets sold
+    let bundle_tickets_total: u64 = bundles.iter().map(|(_, b)| b.ticket_count as u64).sum();
+
+    if bundle_tickets_total != lottery.tickets_sold as u64 {
+        return Err(format!(
+            "bundles hold {bundle_tickets_total} of {} tickets sold",
+            lottery.tickets_sold
+        ));
+    }
+
+    let winners = expected_payouts(&lottery, &bundles)
+        .into_iter()
+        .filter(|payout| payout.draw_index == draw_index)
+        .map(|payout| {
+            let (_, bundle) = bundles
+                .iter()
+                .find(|(key, _)| *key == payout.bundle)
+                .ok_or_else(|| format!("payout of unknown bundle {}", payout.bundle))?;
+
+            Ok(ProofWinner {
+                bundle: payout.bundle.to_string(),
+                owner: payout.owner.to_string(),
+                tier_winning_tickets: payout.tier_winning_tickets.to_vec(),
+                winning_tickets: winning_tickets(
+                    bundle,
+                    &winning_code,
+                    winning_ticket_index,
+                    &lottery.prize_tiers,
+                ),
+                amount: payout.amount,
+            })
+        })
+        .collect::<Result<_, String>>()?;
+
+    Ok(DrawProof {
+        version: DRAW_PROOF_VERSION,
+        program_id: PROGRAM_ID.to_string(),
+        lottery_seed: lottery.lottery_seed.clone(),
+        lottery: proof_account(lottery_key, lottery_data),
+        feed: feed.map(|(key, data)| proof_account(key, data)),
+        reveal: reveal.map(|(secret, slot_hash)| ProofReveal {
+            secret: encode(secret),
+            draw_slot: lottery.draw_slot,
+            slot_hash: encode(slot_hash),
+        }),
+        block_height: (!lottery.is_commit_reveal()).then(|| u64::from_be_bytes(winning_code)),
+        feed_slot: (!lottery.is_commit_reveal()).then_some(draw.feed_slot),
+        draw_index,
+        game_type: match lottery.game_type {
+            GameType::CodeMatch => "code_match",
+            GameType::Raffle => "raffle",
+        }
+        .to_string(),
+        winning_code: ticket_code_to_hex(&winning_code),
+        winning_ticket_index,
+        tickets_sold: lottery.tickets_sold,
+        prize_pool: lottery.prize_pool,
+        bundles: bundles
+            .iter()
+            .filter_map(|(key, _)| bundle_data.iter().find(|(k, _)| k == key))
+            .map(|(key, data)| proof_account(key, data))
+            .collect(),
+        winners,
+    })
+}
+
+/// Re-derives the codes `reveal_draw` drew up to `draw_index` from the secret and
+/// slot hash. SlotHashes drops the draw slot soon after, so the slot hash is
+/// taken as `DrawRevealed` recorded it; the secret is checked against the commitment.
+fn verify_reveal(
+    lottery: &Lottery,
+    secret: &[u8; 32],
+    slot_hash: &[u8; 32],
+    draw_index: u8,
+) -> Result<(), String> {
+    if hash(secret).to_bytes() != lottery.draw_commitment {
+        return Err("secret does not match the lottery commitment".to_string());
+    }
+
+    let random_value = mix_slot_hash(secret, slot_hash);
+    let code_range = commit_reveal_code_range(lottery).map_err(|e| e.to_string())?;
+
+    for (index, draw) in lottery.draws[..=draw_index as usize].iter().enumerate() {
+        let drawn_codes: Vec<[u8; 8]> = lottery.draws[..index]
+            .iter()
+            .map(|d| d.winning_code)
+            .collect();
+        let code = commit_reveal_winning_code(&random_value, index as u8, code_range, &drawn_codes);
+
+        if code != draw.winning_code {
+            return Err(format!(
+                "draw {index} recorded code {} instead of {}",
+                ticket_code_to_hex(&draw.winning_code),
+                ticket_code_to_hex(&code)
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn winning_tickets(
+    bundle: &TransactionBundle,
+    winning_code: &[u8; 8],
+    winning_ticket_index: Option<u32>,
+    tiers: &[PrizeTier],
+) -> Vec<String> {
+    bundle_tickets(bundle)
+        .iter()
+        .enumerate()
+        .filter(|(i, ticket)| match winning_ticket_index {
+            Some(index) => bundle.first_ticket_index + *i as u32 == index,
+            None => score_ticket(ticket, winning_code, tiers).is_some(),
+        })
+        .map(|(_, ticket)| ticket_code_to_hex(ticket))
+        .collect()
+}
+
+fn encode(data: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(data)
+}
+
+fn decode_32(value: &str) -> Result<[u8; 32], String> {
+    base64::engine::general_purpose::STANDARD
+        .decode(value)
+        .map_err(|e| format!("invalid base64 {value}: {e}"))?
+        .try_into()
+        .map_err(|_| format!("{value} is not 32 bytes"))
+}
+
+fn proof_account(key: &Pubkey, data: &[u8]) -> ProofAccount {
+    ProofAccount {
+        pubkey: key.to_string(),
+        data: encode(data),
+    }
+}
+
+fn decode_account(account: &ProofAccount) -> Result<(Pubkey, Vec<u8>), String> {
+    let key = Pubkey::from_str(&account.pubkey)
+        .map_err(|e| format!("invalid pubkey {}: {e}", account.pubkey))?;
+    let data = base64::engine::general_purpose::STANDARD
+        .decode(&account.data)
+        .map_err(|e| format!("{key}: invalid base64 data: {e}"))?;
+
+    Ok((key, data))
+}
+
+/// Re-derives every claim of a proof from the account data it embeds.
+pub fn verify_draw_proof(proof: &DrawProof) -> Result<(), String> {
+    if proof.version != DRAW_PROOF_VERSION {
+        return Err(format!("unsupported proof version {}", proof.version));
+    }
+
+    if proof.program_id != PROGRAM_ID.to_string() {
+        return Err(format!("proof is for program {}", proof.program_id));
+    }
+
+    let (lottery_key, lottery_data) = decode_account(&proof.lottery)?;
+    let feed = proof.feed.as_ref().map(decode_account).transpose()?;
+    let reveal = proof
+        .reveal
+        .as_ref()
+        .map(|reveal| Ok::<_, String>((decode_32(&reveal.secret)?, decode_32(&reveal.slot_hash)?)))
+        .transpose()?;
+    let bundles = proof
+        .bundles
+        .iter()
+        .map(decode_account)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let recomputed = build_draw_proof(
+        &lottery_key,
+        &lottery_data,
+        feed.as_ref().map(|(key, data)| (key, data.as_slice())),
+        reveal
+            .as_ref()
+            .map(|(secret, slot_hash)| (secret, slot_hash)),
+        proof.draw_index,
+        &bundles,
+    )?;
+
+    if recomputed != *proof {
+        return Err("proof claims differ from the recomputed draw".to_string());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::AccountSerialize;
+    use switchboard_on_demand::PullFeedAccountData;
+
+    use ct_lotto_anchor::lotto_util::{build_draws, record_draw};
+
+    use super::*;
+    use crate::fixtures::{bundle, lottery};
+    use crate::pda::lottery_pda;
+    use crate::Lottery;
+
+    const BLOCK_HEIGHT: u64 = 0x0f;
+    const FEED_SLOT: u64 = 1_234;
+    const SECRET: [u8; 32] = [7; 32];
+    const SLOT_HASH: [u8; 32] = [9; 32];
+
+    fn serialize<T: AccountSerialize>(value: &T) -> Vec<u8> {
+        let mut data = Vec::new();
+        value.try_serialize(&mut data).unwrap();
+        data
+    }
+
+    /// Feed account data whose current result is `value`, produced in `slot`.
+    fn feed_data(value: u64, slot: u64) -> Vec<u8> {
+        let mut feed: PullFeedAccountData = bytemuck::Zeroable::zeroed();
+        feed.result.value = (value as i128) * 10i128.pow(18);
+        feed.result.slot = slot;
+        feed.last_update_timestamp = 1;
+
+        let mut data =
+            <PullFeedAccountData as switchboard_on_demand::Discriminator>::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(bytemuck::bytes_of(&feed));
+        data
+    }
+
+    /// Feed-drawn lottery whose only draw picked code "f", sold in the second bundle.
+    fn drawn_lottery(feed: Pubkey) -> (Pubkey, Lottery, Vec<(Pubkey, Vec<u8>)>) {
+        let mut lottery = lottery();
+        lottery.switchboard_feed_btc_block_decimal = feed;
+        lottery.draw_commitment = [0; 32];
+        lottery.open = false;
+        lottery.prize_pool = lottery.pot_lamports - lottery.platform_fee();
+        lottery.draws[0].drawn = true;
+        lottery.draws[0].winning_code = BLOCK_HEIGHT.to_be_bytes();
+        lottery.draws[0].feed_slot = FEED_SLOT;
+
+        let key = lottery_pda(&lottery.lottery_seed).0;
+        let bundles = vec![
+            (Pubkey::new_unique(), serialize(&bundle(key, 0, 3))),
+            (Pubkey::new_unique(), serialize(&bundle(key, 13, 3))),
+        ];
+        lottery.tickets_sold = 6;
+
+        (key, lottery, bundles)
+    }
+
+    #[test]
+    fn proof_round_trips_without_the_moved_feed() {
+        let (key, lottery, bundles) = drawn_lottery(Pubkey::new_unique());
+
+        let proof = build_draw_proof(&key, &serialize(&lottery), None, None, 0, &bundles).unwrap();
+
+        assert_eq!(proof.block_height, Some(BLOCK_HEIGHT));
+        assert_eq!(proof.feed_slot, Some(FEED_SLOT));
+        assert_eq!(proof.winners.len(), 1);
+        assert_eq!(proof.winners[0].winning_tickets, vec!["f".to_string()]);
+        assert_eq!(proof.winners[0].amount, lottery.prize_pool);
+
+        let json = serde_json::to_string(&proof).unwrap();
+        verify_draw_proof(&serde_json::from_str(&json).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn proof_checks_a_feed_captured_at_the_draw_slot() {
+        let feed = Pubkey::new_unique();
+        let (key, lottery, bundles) = drawn_lottery(feed);
+        let lottery_data = serialize(&lottery);
+
+        let captured = feed_data(BLOCK_HEIGHT, FEED_SLOT);
+        let proof = build_draw_proof(
+            &key,
+            &lottery_data,
+            Some((&feed, &captured)),
+            None,
+            0,
+            &bundles,
+        )
+        .unwrap();
+
+        verify_draw_proof(&proof).unwrap();
+
+        // The live feed has moved on to a later block
+        let moved = feed_data(BLOCK_HEIGHT + 1, FEED_SLOT + 10);
+        assert!(build_draw_proof(
+            &key,
+            &lottery_data,
+            Some((&feed, &moved)),
+            None,
+            0,
+            &bundles
+        )
+        .is_err());
+
+        let forged = feed_data(BLOCK_HEIGHT + 1, FEED_SLOT);
+        assert!(build_draw_proof(
+            &key,
+            &lottery_data,
+            Some((&feed, &forged)),
+            None,
+            0,
+            &bundles
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn tampered_proof_is_rejected() {
+        let (key, lottery, bundles) = drawn_lottery(Pubkey::new_unique());
+
+        let mut proof =
+            build_draw_proof(&key, &serialize(&lottery), None, None, 0, &bundles).unwrap();
+        proof.winners[0].amount += 1;
+
+        assert!(verify_draw_proof(&proof).is_err());
+    }
+
+    /// Raffle of 6 tickets in 2 bundles, revealed over two draws as `reveal_draw` does.
+    fn revealed_raffle() -> (Pubkey, Lottery, Vec<(Pubkey, Vec<u8>)>) {
+        let mut lottery = lottery();
+        lottery.game_type = GameType::Raffle;
+        lottery.prize_tiers = vec![];
+        lottery.draws = build_draws(vec![50, 50]).unwrap();
+        lottery.draw_commitment = hash(&SECRET).to_bytes();
+        lottery.draw_slot = 42;
+        lottery.open = false;
+        lottery.tickets_sold = 6;
+
+        let random_value = mix_slot_hash(&SECRET, &SLOT_HASH);
+        let code_range = commit_reveal_code_range(&lottery).unwrap();
+
+        for index in 0..2 {
+            let drawn_codes: Vec<[u8; 8]> = lottery.draws[..index]
+                .iter()
+                .map(|d| d.winning_code)
+                .collect();
+            let code =
+                commit_reveal_winning_code(&random_value, index as u8, code_range, &drawn_codes);
+            record_draw(&mut lottery, index, code, 0).unwrap();
+        }
+
+        let key = lottery_pda(&lottery.lottery_seed).0;
+        let bundles = vec![
+            (Pubkey::new_unique(), serialize(&bundle(key, 0, 3))),
+            (Pubkey::new_unique(), serialize(&bundle(key, 3, 3))),
+        ];
+
+        (key, lottery, bundles)
+    }
+
+    #[test]
+    fn proof_rederives_a_revealed_raffle() {
+        let (key, lottery, bundles) = revealed_raffle();
+        let lottery_data = serialize(&lottery);
+
+        for draw_index in 0..2 {
+            let proof = build_draw_proof(
+                &key,
+                &lottery_data,
+                None,
+                Some((&SECRET, &SLOT_HASH)),
+                draw_index,
+                &bundles,
+            )
+            .unwrap();
+
+            assert_eq!(proof.block_height, None);
+            assert_eq!(
+                proof.winning_ticket_index,
+                Some(lottery.draws[draw_index as usize].winning_ticket_index)
+            );
+            assert_eq!(proof.winners.len(), 1);
+
+            let json = serde_json::to_string(&proof).unwrap();
+            verify_draw_proof(&serde_json::from_str(&json).unwrap()).unwrap();
+        }
+
+        // Two draws over six tickets pick two distinct tickets
+        assert_ne!(
+            lottery.draws[0].winning_ticket_index,
+            lottery.draws[1].winning_ticket_index
+        );
+    }
+
+    #[test]
+    fn reveal_proof_needs_the_committed_secret_and_slot_hash() {
+        let (key, lottery, bundles) = revealed_raffle();
+        let lottery_data = serialize(&lottery);
+        let build = |reveal| build_draw_proof(&key, &lottery_data, None, reveal, 0, &bundles);
+
+        assert!(build(None).is_err());
+        assert!(build(Some((&[8; 32], &SLOT_HASH))).is_err());
+        assert!(build(Some((&SECRET, &[8; 32]))).is_err());
+
+        // A feed-drawn lottery has nothing to reveal
+        let (key, lottery, bundles) = drawn_lottery(Pubkey::new_unique());
+        assert!(build_draw_proof(
+            &key,
+            &serialize(&lottery),
+            None,
+            Some((&SECRET, &SLOT_HASH)),
+            0,
+            &bundles
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,95 @@
+//! Off-chain replay of the tally and payout logic, used to preview what
+//! settlement will pay before sending any transaction.
+use anchor_lang::prelude::Pubkey;
+use ct_lotto_anchor::account_struct::MAX_PRIZE_TIERS;
+use ct_lotto_anchor::lotto_util::{
+    bundle_payout, bundle_refund, tally_bundle, tally_raffle_bundle,
+};
+
+use crate::{GameType, Lottery, TransactionBundle};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExpectedPayout {
+    pub bundle: Pubkey,
+    pub owner: Pubkey,
+    pub draw_index: u8,
+    pub tier_winning_tickets: [u16; MAX_PRIZE_TIERS],
+    pub amount: u64,
+    /// Whether the program has already paid this share.
+    pub paid: bool,
+}
+
+/// Winning tickets of a bundle for one draw, exactly as `tally_transaction_bundle` counts them.
+pub fn tally_for_draw(
+    lottery: &Lottery,
+    bundle: &TransactionBundle,
+    draw_index: usize,
+) -> [u16; MAX_PRIZE_TIERS] {
+    let draw = &lottery.draws[draw_index];
+
+    match lottery.game_type {
+        GameType::CodeMatch => tally_bundle(bundle, &draw.winning_code, &lottery.prize_tiers),
+        GameType::Raffle => tally_raffle_bundle(bundle, draw.winning_ticket_index),
+    }
+}
+
+/// Expected winning shares of every drawn draw, tallying bundles locally
+/// so the result is available before the on-chain tally completes.
+pub fn expected_payouts(
+    lottery: &Lottery,
+    bundles: &[(Pubkey, TransactionBundle)],
+) -> Vec<ExpectedPayout> {
+    let mut simulated = lottery.clone();
+    let mut simulated_bundles: Vec<(Pubkey, TransactionBundle)> = bundles.to_vec();
+    let mut payouts = Vec::new();
+
+    for draw_index in 0..lottery.draws.len() {
+        if !lottery.draws[draw_index].drawn {
+            continue;
+        }
+
+        // Settled bundles may be closed, but a complete tally still counts them
+        let tally_complete = lottery.draws[draw_index].bundles_tallied == lottery.bundle_count;
+
+        if !tally_complete {
+            simulated.draws[draw_index].tier_winning_tickets = [0; MAX_PRIZE_TIERS];
+        }
+
+        for (_, bundle) in simulated_bundles.iter_mut() {
+            let matches = tally_for_draw(lottery, bundle, draw_index);
+
+            if !tally_complete {
+                for (i, count) in matches.iter().enumerate() {
+                    simulated.draws[draw_index].tier_winning_tickets[i] += *count as u32;
+                }
+            }
+
+            bundle.tier_winning_tickets[draw_index] = matches;
+        }
+
+        for (key, bundle) in simulated_bundles.iter() {
+            let matches = bundle.tier_winning_tickets[draw_index];
+
+            if matches.iter().all(|m| *m == 0) {
+                continue;
+            }
+
+            payouts.push(ExpectedPayout {
+                bundle: *key,
+                owner: bundle.owner,
+                draw_index: draw_index as u8,
+                tier_winning_tickets: matches,
+                amount: bundle_payout(&simulated, bundle, draw_index),
+                paid: bundle.draws_rewarded & (1 << draw_index) != 0,
+            });
+        }
+    }
+
+    payouts
+}
+
+/// Amount `refund_transaction_bundle` pays for a bundle.
+/// Cancelled lotteries never deduct the fee.
+pub fn expected_refund(lottery: &Lottery, bundle: &TransactionBundle, deduct_fee: bool) -> u64 {
+    bundle_refund(lottery, bundle, deduct_fee && !lottery.cancelled)
+}
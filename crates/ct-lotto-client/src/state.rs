@@ -0,0 +1,52 @@
+use anchor_lang::{AccountDeserialize, Result};
+
+use crate::{
+    Configuration, Lottery, LotterySeries, PlayerAccount, PlayerTickets, QuickPickRequest,
+    ReferralRewards, TicketRegistry, TransactionBundle,
+};
+
+/// Deserializes any program account from raw account data (discriminator included).
+pub fn deserialize_account<T: AccountDeserialize>(data: &[u8]) -> Result<T> {
+    T::try_deserialize(&mut &data[..])
+}
+
+pub fn deserialize_configuration(data: &[u8]) -> Result<Configuration> {
+    deserialize_account(data)
+}
+
+pub fn deserialize_lottery(data: &[u8]) -> Result<Lottery> {
+    deserialize_account(data)
+}
+
+pub fn deserialize_lottery_series(data: &[u8]) -> Result<LotterySeries> {
+    deserialize_account(data)
+}
+
+pub fn deserialize_player_account(data: &[u8]) -> Result<PlayerAccount> {
+    deserialize_account(data)
+}
+
+pub fn deserialize_player_tickets(data: &[u8]) -> Result<PlayerTickets> {
+    deserialize_account(data)
+}
+
+pub fn deserialize_quick_pick_request(data: &[u8]) -> Result<QuickPickRequest> {
+    deserialize_account(data)
+}
+
+pub fn deserialize_ticket_registry(data: &[u8]) -> Result<TicketRegistry> {
+    deserialize_account(data)
+}
+
+pub fn deserialize_referral_rewards(data: &[u8]) -> Result<ReferralRewards> {
+    deserialize_account(data)
+}
+
+pub fn deserialize_transaction_bundle(data: &[u8]) -> Result<TransactionBundle> {
+    deserialize_account(data)
+}
+
+/// Ticket codes actually held by a bundle (the unused slots are dropped).
+pub fn bundle_tickets(bundle: &TransactionBundle) -> &[[u8; 8]] {
+    &bundle.tickets[..bundle.ticket_count as usize]
+}
@@ -0,0 +1,14 @@
+//! Ticket codes are hex strings stored on-chain as the big-endian bytes of
+//! their value. These are the program's own `lotto_util` functions, so clients
+//! encode tickets exactly the way the program compares them.
+pub use ct_lotto_anchor::lotto_util::{
+    matching_suffix_digits, score_ticket, ticket_code_from_hex, ticket_code_to_hex,
+};
+
+/// Encodes a list of hex ticket codes for `create_transaction_bundle`.
+pub fn ticket_codes_from_hex<S: AsRef<str>>(codes: &[S]) -> anchor_lang::Result<Vec<[u8; 8]>> {
+    codes
+        .iter()
+        .map(|code| ticket_code_from_hex(code.as_ref()))
+        .collect()
+}
@@ -2,108 +2,991 @@ use crate::account_size::*;
 use crate::account_struct::*;
 use crate::lotto_enum::LottoError;
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions as sysvar_instructions;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use solana_sdk_ids::sysvar::slot_hashes;
 
 /* -------------------------------------------------
    CREATE CONFIGURATION PDA
 --------------------------------------------------*/
+#[event_cpi]
 #[derive(Accounts)]
 pub struct CreateConfigurationPDA<'info> {
     #[account(
         init,
-        payer = admin,
+        payer = payer,
         seeds = [b"configuration"],
         bump,
         space = get_configuration_size()
     )]
     pub configuration: Account<'info, Configuration>,
 
+    /// Admin authority, may be a multisig PDA signing through CPI.
+    pub admin: Signer<'info>,
+
+    /// Pays the rent of the configuration.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/* -------------------------------------------------
+   PROPOSE / ACCEPT ADMIN
+   Two-step handover, so the new admin (wallet or multisig
+   PDA) proves it can sign before it takes over.
+--------------------------------------------------*/
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ProposeAdmin<'info> {
+    #[account(mut)]
+    pub configuration: Account<'info, Configuration>,
+
+    pub admin: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    #[account(mut)]
+    pub configuration: Account<'info, Configuration>,
+
+    pub pending_admin: Signer<'info>,
+}
+
+/* -------------------------------------------------
+   CREATE LOTTERY PDA
+--------------------------------------------------*/
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(lottery_seed: String)]
+pub struct CreateLotteryPDA<'info> {
+    pub configuration: Account<'info, Configuration>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [
+            b"lottery",
+            lottery_seed.as_bytes()
+        ],
+        bump,
+        space = get_lottery_size()
+    )]
+    pub lottery: Account<'info, Lottery>,
+
+    /// Admin authority, may be a multisig PDA signing through CPI.
+    pub admin: Signer<'info>,
+
+    /// Pays the rent of the created accounts.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Switchboard feed of the draws, omitted for commit-reveal draws.
+    pub switchboard_feed_btc_block_decimal: Option<AccountInfo<'info>>,
+
+    /// SPL mint tickets are priced in, omitted for SOL lotteries.
+    pub payment_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// Lottery-owned token vault, created alongside token lotteries.
+    #[account(
+        init,
+        payer = payer,
+        associated_token::mint = payment_mint,
+        associated_token::authority = lottery,
+        associated_token::token_program = token_program
+    )]
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/* -------------------------------------------------
+   CREATE LOTTERY SERIES
+   PDA = ["series", series_seed]
+   Round PDA = ["lottery", series, round (u32 LE)]
+--------------------------------------------------*/
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(series_seed: String)]
+pub struct CreateLotterySeries<'info> {
+    pub configuration: Account<'info, Configuration>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [
+            b"series",
+            series_seed.as_bytes()
+        ],
+        bump,
+        space = get_lottery_series_size()
+    )]
+    pub series: Account<'info, LotterySeries>,
+
+    /// First round of the series.
+    #[account(
+        init,
+        payer = payer,
+        seeds = [
+            b"lottery",
+            series.key().as_ref(),
+            &1u32.to_le_bytes()
+        ],
+        bump,
+        space = get_lottery_size()
+    )]
+    pub lottery: Account<'info, Lottery>,
+
+    /// Admin authority, may be a multisig PDA signing through CPI.
+    pub admin: Signer<'info>,
+
+    /// Pays the rent of the created accounts.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK
+    pub switchboard_feed_btc_block_decimal: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/* -------------------------------------------------
+   ROLL LOTTERY SERIES (permissionless)
+   Next round PDA = ["lottery", series, current_round + 1]
+--------------------------------------------------*/
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RollSeries<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"series",
+            series.series_seed.as_bytes()
+        ],
+        bump = series.bump
+    )]
+    pub series: Account<'info, LotterySeries>,
+
+    #[account(
+        mut,
+        address = series.current_lottery @ LottoError::KeyMismatch
+    )]
+    pub current_lottery: Account<'info, Lottery>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [
+            b"lottery",
+            series.key().as_ref(),
+            &(series.current_round + 1).to_le_bytes()
+        ],
+        bump,
+        space = get_lottery_size()
+    )]
+    pub next_lottery: Account<'info, Lottery>,
+
+    /// Anyone may roll the series, paying the rent of the next round.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/* -------------------------------------------------
+   CARRY OVER PRIZES (permissionless)
+   Moves what a settled round left behind into the
+   current round of its series
+--------------------------------------------------*/
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CarryOverPrizes<'info> {
+    #[account(
+        seeds = [
+            b"series",
+            series.series_seed.as_bytes()
+        ],
+        bump = series.bump
+    )]
+    pub series: Account<'info, LotterySeries>,
+
+    /// Earlier round of the series, whose draws are settled.
+    #[account(
+        mut,
+        constraint = round.series == series.key()
+            && round.key() != series.current_lottery @ LottoError::KeyMismatch
+    )]
+    pub round: Account<'info, Lottery>,
+
+    #[account(
+        mut,
+        address = series.current_lottery @ LottoError::KeyMismatch
+    )]
+    pub current_lottery: Account<'info, Lottery>,
+}
+
+/* -------------------------------------------------
+   CREATE TRANSACTION BUNDLE
+   PDA = ["bundle", lottery_seed, tx_sig_hash]
+   Player PDA = ["player", lottery, owner]
+   Referral PDA = ["referrer", lottery, referrer]
+--------------------------------------------------*/
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(
+    lottery_seed: String,
+    tx_sig_hash: [u8; 32],
+    owner: Pubkey,
+    purchased_numbers: Vec<[u8; 8]>,
+    referrer: Option<Pubkey>
+)]
+pub struct CreateTransactionBundle<'info> {
+    pub configuration: Account<'info, Configuration>,
+
+    #[account(mut)]
+    pub lottery: Account<'info, Lottery>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [
+            b"bundle",
+            lottery_seed.as_bytes(),
+            &tx_sig_hash
+        ],
+        bump,
+        space = get_transaction_bundle_size()
+    )]
+    pub bundle: Account<'info, TransactionBundle>,
+
+    /// Lotteries with unique codes only: sold codes, kept unique across bundles.
+    #[account(mut, address = lottery.ticket_registry @ LottoError::KeyMismatch)]
+    pub ticket_registry: Option<AccountLoader<'info, TicketRegistry>>,
+
+    /// Running ticket count of the owner, for the per-wallet cap.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [
+            b"player",
+            lottery.key().as_ref(),
+            owner.as_ref()
+        ],
+        bump,
+        space = get_player_tickets_size()
+    )]
+    pub player_tickets: Account<'info, PlayerTickets>,
+
+    /// Referred purchases only: earnings of the referrer.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [
+            b"referrer",
+            lottery.key().as_ref(),
+            referrer.unwrap_or_default().as_ref()
+        ],
+        bump,
+        space = get_referral_rewards_size()
+    )]
+    pub referral: Option<Account<'info, ReferralRewards>>,
+
+    /// Admin authority, may be a multisig PDA signing through CPI.
+    pub admin: Signer<'info>,
+
+    /// Pays the rent of the created accounts.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Token lotteries only: used to account for Token-2022 transfer fees.
+    pub payment_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// Pays the tickets in-program on the owner's behalf, instead of a transfer
+    /// instruction right before this one. Required under CPI (e.g. a multisig vault).
+    #[account(mut)]
+    pub funder: Option<Signer<'info>>,
+
+    /// Funded token purchases only: lottery-owned vault.
+    #[account(mut)]
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Funded token purchases only: funder's token account.
+    #[account(mut)]
+    pub funder_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    /// CHECK: Instructions sysvar, used to find the payment transfer
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/* -------------------------------------------------
+   PURCHASE WITH SIGNED INTENT (relayed)
+--------------------------------------------------*/
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(lottery_seed: String, tx_sig_hash: [u8; 32], intent: PurchaseIntent)]
+pub struct PurchaseWithIntent<'info> {
+    #[account(mut, address = intent.lottery @ LottoError::IntentMismatch)]
+    pub lottery: Account<'info, Lottery>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [
+            b"bundle",
+            lottery_seed.as_bytes(),
+            &tx_sig_hash
+        ],
+        bump,
+        space = get_transaction_bundle_size()
+    )]
+    pub bundle: Account<'info, TransactionBundle>,
+
+    /// Lotteries with unique codes only: sold codes, kept unique across bundles.
+    #[account(mut, address = lottery.ticket_registry @ LottoError::KeyMismatch)]
+    pub ticket_registry: Option<AccountLoader<'info, TicketRegistry>>,
+
+    /// Running ticket count of the owner, for the per-wallet cap.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [
+            b"player",
+            lottery.key().as_ref(),
+            intent.owner.as_ref()
+        ],
+        bump,
+        space = get_player_tickets_size()
+    )]
+    pub player_tickets: Account<'info, PlayerTickets>,
+
+    /// Referred purchases only: earnings of the referrer.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [
+            b"referrer",
+            lottery.key().as_ref(),
+            intent.referrer.unwrap_or_default().as_ref()
+        ],
+        bump,
+        space = get_referral_rewards_size()
+    )]
+    pub referral: Option<Account<'info, ReferralRewards>>,
+
+    /// Escrow the tickets are paid from.
+    #[account(
+        mut,
+        seeds = [
+            b"player_account",
+            intent.owner.as_ref(),
+            lottery.payment_mint.as_ref()
+        ],
+        bump = player_account.bump
+    )]
+    pub player_account: Account<'info, PlayerAccount>,
+
+    /// Relayer submitting the intent, pays fees and the rent of the created accounts.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Token lotteries only: mint the lottery is paid in.
+    pub payment_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// Token lotteries only: token account holding the player's balance.
+    #[account(mut)]
+    pub balance_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token lotteries only: lottery-owned vault.
+    #[account(mut)]
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    /// CHECK: Instructions sysvar, used to find the Ed25519 signature check
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/* -------------------------------------------------
+   PURCHASE FROM PLAYER BALANCE
+--------------------------------------------------*/
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(
+    lottery_seed: String,
+    tx_sig_hash: [u8; 32],
+    purchased_numbers: Vec<[u8; 8]>,
+    referrer: Option<Pubkey>
+)]
+pub struct PurchaseFromBalance<'info> {
+    #[account(mut)]
+    pub lottery: Account<'info, Lottery>,
+
+    #[account(
+        init,
+        payer = owner,
+        seeds = [
+            b"bundle",
+            lottery_seed.as_bytes(),
+            &tx_sig_hash
+        ],
+        bump,
+        space = get_transaction_bundle_size()
+    )]
+    pub bundle: Account<'info, TransactionBundle>,
+
+    /// Lotteries with unique codes only: sold codes, kept unique across bundles.
+    #[account(mut, address = lottery.ticket_registry @ LottoError::KeyMismatch)]
+    pub ticket_registry: Option<AccountLoader<'info, TicketRegistry>>,
+
+    /// Running ticket count of the owner, for the per-wallet cap.
+    #[account(
+        init_if_needed,
+        payer = owner,
+        seeds = [
+            b"player",
+            lottery.key().as_ref(),
+            owner.key().as_ref()
+        ],
+        bump,
+        space = get_player_tickets_size()
+    )]
+    pub player_tickets: Account<'info, PlayerTickets>,
+
+    /// Referred purchases only: earnings of the referrer.
+    #[account(
+        init_if_needed,
+        payer = owner,
+        seeds = [
+            b"referrer",
+            lottery.key().as_ref(),
+            referrer.unwrap_or_default().as_ref()
+        ],
+        bump,
+        space = get_referral_rewards_size()
+    )]
+    pub referral: Option<Account<'info, ReferralRewards>>,
+
+    /// Balance the tickets are paid from.
+    #[account(
+        mut,
+        seeds = [
+            b"player_account",
+            owner.key().as_ref(),
+            lottery.payment_mint.as_ref()
+        ],
+        bump = player_account.bump
+    )]
+    pub player_account: Account<'info, PlayerAccount>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// Token lotteries only: mint the lottery is paid in.
+    pub payment_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// Token lotteries only: token account holding the player's balance.
+    #[account(mut)]
+    pub balance_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token lotteries only: lottery-owned vault.
+    #[account(mut)]
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/* -------------------------------------------------
+   DEPOSIT / WITHDRAW PLAYER BALANCE
+--------------------------------------------------*/
+#[event_cpi]
+#[derive(Accounts)]
+pub struct DepositBalance<'info> {
+    #[account(
+        init_if_needed,
+        payer = owner,
+        seeds = [
+            b"player_account",
+            owner.key().as_ref(),
+            payment_mint.as_ref().map(|mint| mint.key()).unwrap_or_default().as_ref()
+        ],
+        bump,
+        space = get_player_account_size()
+    )]
+    pub player_account: Account<'info, PlayerAccount>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// Token balances only: mint of the balance, omitted for SOL.
+    pub payment_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// Token balances only: token account of the player account PDA holding the balance.
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = payment_mint,
+        associated_token::authority = player_account,
+        associated_token::token_program = token_program
+    )]
+    pub balance_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token balances only: owner's token account funding the deposit.
+    #[account(mut)]
+    pub owner_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct WithdrawBalance<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"player_account",
+            owner.key().as_ref(),
+            player_account.payment_mint.as_ref()
+        ],
+        bump = player_account.bump
+    )]
+    pub player_account: Account<'info, PlayerAccount>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// Token balances only: mint of the balance.
+    pub payment_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// Token balances only: token account holding the balance.
+    #[account(mut)]
+    pub balance_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token balances only: owner's token account receiving the withdrawal.
+    #[account(mut)]
+    pub owner_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+/* -------------------------------------------------
+   TICKET REGISTRY (admin)
+--------------------------------------------------*/
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CreateTicketRegistry<'info> {
+    pub configuration: Account<'info, Configuration>,
+
+    #[account(mut)]
+    pub lottery: Account<'info, Lottery>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [
+            b"registry",
+            lottery.key().as_ref()
+        ],
+        bump,
+        space = get_ticket_registry_size()
+    )]
+    pub ticket_registry: AccountLoader<'info, TicketRegistry>,
+
+    /// Admin authority, may be a multisig PDA signing through CPI.
+    pub admin: Signer<'info>,
+
+    /// Pays the rent of the registry.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CloseTicketRegistry<'info> {
+    pub configuration: Account<'info, Configuration>,
+
+    #[account(mut)]
+    pub lottery: Account<'info, Lottery>,
+
+    #[account(
+        mut,
+        address = lottery.ticket_registry @ LottoError::KeyMismatch,
+        close = rent_payer
+    )]
+    pub ticket_registry: AccountLoader<'info, TicketRegistry>,
+
+    /// CHECK: receives the rent of the registry
+    #[account(mut, address = ticket_registry.load()?.rent_payer @ LottoError::KeyMismatch)]
+    pub rent_payer: AccountInfo<'info>,
+
+    pub admin: Signer<'info>,
+}
+
+/* -------------------------------------------------
+   QUICK PICK (request, fulfil, cancel)
+--------------------------------------------------*/
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(tx_sig_hash: [u8; 32])]
+pub struct RequestQuickPick<'info> {
+    pub lottery: Account<'info, Lottery>,
+
+    #[account(
+        init,
+        payer = owner,
+        seeds = [
+            b"quick_pick",
+            lottery.key().as_ref(),
+            &tx_sig_hash
+        ],
+        bump,
+        space = get_quick_pick_request_size()
+    )]
+    pub quick_pick: Account<'info, QuickPickRequest>,
+
+    /// Balance the tickets will be paid from.
+    #[account(
+        seeds = [
+            b"player_account",
+            owner.key().as_ref(),
+            lottery.payment_mint.as_ref()
+        ],
+        bump = player_account.bump
+    )]
+    pub player_account: Account<'info, PlayerAccount>,
+
+    /// CHECK: Switchboard requests only: randomness account, validated in the instruction
+    pub randomness_account: Option<AccountInfo<'info>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(lottery_seed: String)]
+pub struct FulfillQuickPick<'info> {
+    #[account(mut)]
+    pub lottery: Account<'info, Lottery>,
+
+    #[account(
+        mut,
+        has_one = lottery @ LottoError::KeyMismatch,
+        has_one = owner @ LottoError::KeyMismatch,
+        close = owner
+    )]
+    pub quick_pick: Account<'info, QuickPickRequest>,
+
+    #[account(
+        init,
+        payer = owner,
+        seeds = [
+            b"bundle",
+            lottery_seed.as_bytes(),
+            &quick_pick.tx_sig_hash
+        ],
+        bump,
+        space = get_transaction_bundle_size()
+    )]
+    pub bundle: Account<'info, TransactionBundle>,
+
+    /// Lotteries with unique codes only: sold codes, kept unique across bundles.
+    #[account(mut, address = lottery.ticket_registry @ LottoError::KeyMismatch)]
+    pub ticket_registry: Option<AccountLoader<'info, TicketRegistry>>,
+
+    /// Running ticket count of the owner, for the per-wallet cap.
+    #[account(
+        init_if_needed,
+        payer = owner,
+        seeds = [
+            b"player",
+            lottery.key().as_ref(),
+            owner.key().as_ref()
+        ],
+        bump,
+        space = get_player_tickets_size()
+    )]
+    pub player_tickets: Account<'info, PlayerTickets>,
+
+    /// Referred purchases only: earnings of the referrer.
+    #[account(
+        init_if_needed,
+        payer = owner,
+        seeds = [
+            b"referrer",
+            lottery.key().as_ref(),
+            quick_pick.referrer.unwrap_or_default().as_ref()
+        ],
+        bump,
+        space = get_referral_rewards_size()
+    )]
+    pub referral: Option<Account<'info, ReferralRewards>>,
+
+    /// Balance the tickets are paid from.
+    #[account(
+        mut,
+        seeds = [
+            b"player_account",
+            owner.key().as_ref(),
+            lottery.payment_mint.as_ref()
+        ],
+        bump = player_account.bump
+    )]
+    pub player_account: Account<'info, PlayerAccount>,
+
+    /// CHECK: Switchboard requests only: randomness account, validated in the instruction
+    pub randomness_account: Option<AccountInfo<'info>>,
+
+    /// CHECK: commit-reveal requests only: SlotHashes sysvar
+    #[account(address = slot_hashes::ID)]
+    pub slot_hashes: Option<AccountInfo<'info>>,
+
     #[account(mut)]
-    pub admin: Signer<'info>,
+    pub owner: Signer<'info>,
+
+    /// Token lotteries only: mint the lottery is paid in.
+    pub payment_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// Token lotteries only: token account holding the player's balance.
+    #[account(mut)]
+    pub balance_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token lotteries only: lottery-owned vault.
+    #[account(mut)]
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
 
     pub system_program: Program<'info, System>,
 }
 
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelQuickPick<'info> {
+    #[account(
+        mut,
+        has_one = owner @ LottoError::KeyMismatch,
+        close = owner
+    )]
+    pub quick_pick: Account<'info, QuickPickRequest>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
 /* -------------------------------------------------
-   CREATE LOTTERY PDA
+   DRAW WINNING CODE
 --------------------------------------------------*/
+#[event_cpi]
 #[derive(Accounts)]
-#[instruction(lottery_seed: String)]
-pub struct CreateLotteryPDA<'info> {
+pub struct DrawWinner<'info> {
     pub configuration: Account<'info, Configuration>,
 
-    #[account(
-        init,
-        payer = admin,
-        seeds = [
-            b"lottery",
-            lottery_seed.as_bytes()
-        ],
-        bump,
-        space = get_lottery_size()
-    )]
+    #[account(mut)]
     pub lottery: Account<'info, Lottery>,
 
+    /// CHECK
+    pub switchboard_feed_btc_block_decimal: AccountInfo<'info>,
+
+    pub admin: Signer<'info>,
+}
+
+/* -------------------------------------------------
+   REVEAL A COMMIT-REVEAL DRAW
+--------------------------------------------------*/
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RevealDraw<'info> {
+    pub configuration: Account<'info, Configuration>,
+
     #[account(mut)]
+    pub lottery: Account<'info, Lottery>,
+
+    /// CHECK: SlotHashes sysvar
+    #[account(address = slot_hashes::ID)]
+    pub slot_hashes: AccountInfo<'info>,
+
     pub admin: Signer<'info>,
+}
 
-    /// CHECK
-    pub switchboard_feed_btc_block_decimal: AccountInfo<'info>,
+/* -------------------------------------------------
+   CANCEL AN EXPIRED COMMIT-REVEAL DRAW (permissionless)
+--------------------------------------------------*/
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelExpiredDraw<'info> {
+    #[account(mut)]
+    pub lottery: Account<'info, Lottery>,
 
-    pub system_program: Program<'info, System>,
+    /// CHECK: SlotHashes sysvar
+    #[account(address = slot_hashes::ID)]
+    pub slot_hashes: AccountInfo<'info>,
 }
 
 /* -------------------------------------------------
-   CREATE TRANSACTION BUNDLE
-   PDA = ["bundle", lottery_seed, tx_sig_hash]
+   TALLY A TRANSACTION BUNDLE AGAINST THE WINNING CODE
 --------------------------------------------------*/
+#[event_cpi]
 #[derive(Accounts)]
-#[instruction(lottery_seed: String, tx_sig_hash: [u8; 32])]
-pub struct CreateTransactionBundle<'info> {
+pub struct TallyTransactionBundle<'info> {
+    pub configuration: Account<'info, Configuration>,
+
+    #[account(mut)]
+    pub lottery: Account<'info, Lottery>,
+
+    #[account(mut)]
+    pub bundle: Account<'info, TransactionBundle>,
+
+    pub admin: Signer<'info>,
+}
+
+/* -------------------------------------------------
+   REWARD USING A TRANSACTION BUNDLE
+--------------------------------------------------*/
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RewardTransactionBundle<'info> {
     pub configuration: Account<'info, Configuration>,
 
     #[account(mut)]
     pub lottery: Account<'info, Lottery>,
 
+    #[account(mut)]
+    pub bundle: Account<'info, TransactionBundle>,
+
+    /// CHECK
+    #[account(mut)]
+    pub owner: AccountInfo<'info>,
+
+    /// Credits the payout to the owner's player balance instead of their wallet
+    /// (`owner_token_account` is then the balance vault).
     #[account(
-        init,
-        payer = admin,
+        mut,
         seeds = [
-            b"bundle",
-            lottery_seed.as_bytes(),
-            &tx_sig_hash
+            b"player_account",
+            bundle.owner.as_ref(),
+            lottery.payment_mint.as_ref()
         ],
-        bump,
-        space = get_transaction_bundle_size()
+        bump = player_account.bump
     )]
+    pub player_account: Option<Account<'info, PlayerAccount>>,
+
+    pub admin: Signer<'info>,
+
+    /// Token lotteries only: mint the lottery is paid in.
+    pub payment_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// Token lotteries only: lottery-owned vault.
+    #[account(mut)]
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token lotteries only: winner's token account.
+    #[account(mut)]
+    pub owner_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/* -------------------------------------------------
+   CLAIM PRIZE (bundle owner)
+--------------------------------------------------*/
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimPrize<'info> {
+    #[account(mut)]
+    pub lottery: Account<'info, Lottery>,
+
+    #[account(mut)]
     pub bundle: Account<'info, TransactionBundle>,
 
     #[account(mut)]
-    pub admin: Signer<'info>,
+    pub owner: Signer<'info>,
+
+    /// Credits the payout to the owner's player balance instead of their wallet
+    /// (`owner_token_account` is then the balance vault).
+    #[account(
+        mut,
+        seeds = [
+            b"player_account",
+            bundle.owner.as_ref(),
+            lottery.payment_mint.as_ref()
+        ],
+        bump = player_account.bump
+    )]
+    pub player_account: Option<Account<'info, PlayerAccount>>,
+
+    /// Token lotteries only: mint the lottery is paid in.
+    pub payment_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// Token lotteries only: lottery-owned vault.
+    #[account(mut)]
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token lotteries only: winner's token account.
+    #[account(mut)]
+    pub owner_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
 
     pub system_program: Program<'info, System>,
 }
 
 /* -------------------------------------------------
-   REWARD USING A TRANSACTION BUNDLE
+   SWEEP UNCLAIMED PRIZES (permissionless)
 --------------------------------------------------*/
+#[event_cpi]
 #[derive(Accounts)]
-pub struct RewardTransactionBundle<'info> {
+pub struct SweepUnclaimedPrizes<'info> {
     pub configuration: Account<'info, Configuration>,
 
     #[account(mut)]
     pub lottery: Account<'info, Lottery>,
 
+    /// Series rounds only: later round of the series receiving the prizes.
     #[account(mut)]
-    pub bundle: Account<'info, TransactionBundle>,
+    pub rollover: Option<Account<'info, Lottery>>,
 
-    /// CHECK
-    pub switchboard_feed_btc_block_decimal: AccountInfo<'info>,
+    /// CHECK: treasury, the configured admin
+    #[account(mut, address = configuration.admin @ LottoError::KeyMismatch)]
+    pub treasury: AccountInfo<'info>,
 
-    /// CHECK
+    /// Token lotteries only: mint the lottery is paid in.
+    pub payment_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// Token lotteries only: lottery-owned vault.
     #[account(mut)]
-    pub owner: AccountInfo<'info>,
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
 
-    pub admin: Signer<'info>,
+    /// Token lotteries only: treasury's token account.
+    #[account(mut)]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
 
     pub system_program: Program<'info, System>,
 }
@@ -111,6 +994,7 @@ pub struct RewardTransactionBundle<'info> {
 /* -------------------------------------------------
    REFUND USING A TRANSACTION BUNDLE
 --------------------------------------------------*/
+#[event_cpi]
 #[derive(Accounts)]
 pub struct RefundTransactionBundle<'info> {
     pub configuration: Account<'info, Configuration>,
@@ -125,14 +1009,171 @@ pub struct RefundTransactionBundle<'info> {
     #[account(mut)]
     pub owner: AccountInfo<'info>,
 
+    /// Credits the refund to the owner's player balance instead of their wallet
+    /// (`owner_token_account` is then the balance vault). Required for bundles
+    /// bought from that balance.
+    #[account(
+        mut,
+        seeds = [
+            b"player_account",
+            bundle.owner.as_ref(),
+            lottery.payment_mint.as_ref()
+        ],
+        bump = player_account.bump
+    )]
+    pub player_account: Option<Account<'info, PlayerAccount>>,
+
+    pub admin: Signer<'info>,
+
+    /// Token lotteries only: mint the lottery is paid in.
+    pub payment_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// Token lotteries only: lottery-owned vault.
+    #[account(mut)]
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token lotteries only: refund target's token account.
+    #[account(mut)]
+    pub owner_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/* -------------------------------------------------
+   WITHDRAW PLATFORM FEE
+--------------------------------------------------*/
+#[event_cpi]
+#[derive(Accounts)]
+pub struct WithdrawPlatformFees<'info> {
+    pub configuration: Account<'info, Configuration>,
+
+    #[account(mut)]
+    pub lottery: Account<'info, Lottery>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// Token lotteries only: mint the lottery is paid in.
+    pub payment_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// Token lotteries only: lottery-owned vault.
+    #[account(mut)]
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token lotteries only: admin's token account.
+    #[account(mut)]
+    pub admin_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/* -------------------------------------------------
+   CLAIM REFERRAL REWARDS
+--------------------------------------------------*/
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimReferralRewards<'info> {
+    #[account(mut)]
+    pub lottery: Account<'info, Lottery>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"referrer",
+            lottery.key().as_ref(),
+            referrer.key().as_ref()
+        ],
+        bump = referral.bump
+    )]
+    pub referral: Account<'info, ReferralRewards>,
+
+    #[account(mut)]
+    pub referrer: Signer<'info>,
+
+    /// Token lotteries only: mint the lottery is paid in.
+    pub payment_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// Token lotteries only: lottery-owned vault.
+    #[account(mut)]
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token lotteries only: referrer's token account.
+    #[account(mut)]
+    pub referrer_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/* -------------------------------------------------
+   SEED PRIZE
+--------------------------------------------------*/
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SeedPrize<'info> {
+    pub configuration: Account<'info, Configuration>,
+
+    #[account(mut)]
+    pub lottery: Account<'info, Lottery>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// Token lotteries only: mint the lottery is paid in.
+    pub payment_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// Token lotteries only: lottery-owned vault.
+    #[account(mut)]
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token lotteries only: admin's token account.
+    #[account(mut)]
+    pub admin_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/* -------------------------------------------------
+   RECLAIM SEEDED PRIZE
+--------------------------------------------------*/
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ReclaimSeededPrize<'info> {
+    pub configuration: Account<'info, Configuration>,
+
+    #[account(mut)]
+    pub lottery: Account<'info, Lottery>,
+
+    #[account(mut)]
     pub admin: Signer<'info>,
 
+    /// Token lotteries only: mint the lottery is paid in.
+    pub payment_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// Token lotteries only: lottery-owned vault.
+    #[account(mut)]
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token lotteries only: admin's token account.
+    #[account(mut)]
+    pub admin_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
     pub system_program: Program<'info, System>,
 }
 
 /* -------------------------------------------------
    CLOSE LOTTERY
 --------------------------------------------------*/
+#[event_cpi]
 #[derive(Accounts)]
 pub struct CloseLottery<'info> {
     pub configuration: Account<'info, Configuration>,
@@ -150,49 +1191,80 @@ pub struct CloseLottery<'info> {
    CLOSE TRANSACTION BUNDLE
    PDA = ["bundle", lottery_seed, tx_sig_hash]
 --------------------------------------------------*/
+#[event_cpi]
 #[derive(Accounts)]
 pub struct CloseTransactionBundle<'info> {
     pub configuration: Account<'info, Configuration>,
 
+    #[account(mut, address = bundle.lottery_pda @ LottoError::KeyMismatch)]
+    pub lottery: Account<'info, Lottery>,
+
     #[account(
         mut,
-        close = admin   // rent goes to admin
+        close = rent_payer   // rent goes back to whoever paid it
     )]
     pub bundle: Account<'info, TransactionBundle>,
 
     #[account(mut)]
     pub admin: Signer<'info>,
 
+    /// CHECK: receives the rent, must be the recorded payer
+    #[account(mut, address = bundle.rent_payer @ LottoError::KeyMismatch)]
+    pub rent_payer: AccountInfo<'info>,
+
     pub system_program: Program<'info, System>,
 }
 
 /* -------------------------------------------------
    CLOSE LOTTERY PDA
 --------------------------------------------------*/
+#[event_cpi]
 #[derive(Accounts)]
 pub struct CloseLotteryPDA<'info> {
     pub configuration: Account<'info, Configuration>,
 
-    #[account(mut, close = admin)]
+    #[account(mut, close = rent_payer)]
     pub lottery: Account<'info, Lottery>,
 
+    /// Receives any funds left in the lottery.
     #[account(mut)]
     pub admin: Signer<'info>,
 
+    /// CHECK: receives the rent of the lottery and its vault, must be the recorded payer
+    #[account(mut, address = lottery.rent_payer @ LottoError::KeyMismatch)]
+    pub rent_payer: AccountInfo<'info>,
+
+    /// Token lotteries only: mint the lottery is paid in.
+    pub payment_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// Token lotteries only: lottery-owned vault, swept and closed.
+    #[account(mut)]
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token lotteries only: admin's token account.
+    #[account(mut)]
+    pub admin_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
     pub system_program: Program<'info, System>,
 }
 
 /* -------------------------------------------------
    CLOSE CONFIGURATION PDA
 --------------------------------------------------*/
+#[event_cpi]
 #[derive(Accounts)]
 pub struct CloseConfigurationPDA<'info> {
-    #[account(mut, close = admin)]
+    #[account(mut, close = rent_payer)]
     pub configuration: Account<'info, Configuration>,
 
-    // we're transfering the rent to the admin, to allow only admin to close the account we ask for signature.
-    #[account(mut)]
+    // only the admin can close the account, so we ask for its signature.
     pub admin: Signer<'info>,
 
+    /// CHECK: receives the rent, must be the recorded payer
+    #[account(mut, address = configuration.rent_payer @ LottoError::KeyMismatch)]
+    pub rent_payer: AccountInfo<'info>,
+
     pub system_program: Program<'info, System>,
 }
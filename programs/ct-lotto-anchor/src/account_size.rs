@@ -1,9 +1,15 @@
+use crate::account_struct::{
+    MAX_DRAWS, MAX_PRIZE_TIERS, MAX_REGISTRY_CODES, MAX_SERIES_SEED_LEN, MAX_TICKET_CODE_HEX_LEN,
+};
+
 pub fn get_configuration_size() -> usize {
     let discriminator = 8;
     let admin = 32;
+    let pending_admin = 32;
+    let rent_payer = 32;
     let buffer = 8;
 
-    discriminator + admin + buffer
+    discriminator + admin + pending_admin + rent_payer + buffer
 }
 
 pub fn get_lottery_size() -> usize {
@@ -14,15 +20,47 @@ pub fn get_lottery_size() -> usize {
     let lamports_per_ticket = 8; // u64
 
     // Strings in your struct: start + end hex
-    // Max hex length = 6 chars for your config (e.g., "fffff")
+    // Max hex length = "0x" + 16 digits, as ticket_code_from_hex accepts
     // Anchor stores String as 4 bytes len + UTF-8 bytes
-    let start_hex = 4 + 6;
-    let end_hex = 4 + 6;
+    let start_hex = 4 + MAX_TICKET_CODE_HEX_LEN;
+    let end_hex = 4 + MAX_TICKET_CODE_HEX_LEN;
 
-    let winner_settled = 1; // bool
     let refunds_settled = 4; // u32
     let open = 1; // bool
     let platform_fee_percentage = 2; // u16
+    let allow_shared_tickets = 1; // bool
+    let bundle_count = 4; // u32
+    let prize_pool = 8; // u64
+    let prize_tiers = 4 + MAX_PRIZE_TIERS * 2; // Vec<PrizeTier>
+    let game_type = 1; // enum
+    let draws = 4 + MAX_DRAWS * get_draw_size(); // Vec<Draw>
+    let lottery_seed = 4 + 32; // String, max PDA seed length
+    let bump = 1; // u8
+    let payment_mint = 32; // Pubkey
+    let payment_vault = 32; // Pubkey
+    let ticket_revenue = 8; // u64
+    let platform_fee_withdrawn = 1; // bool
+    let max_tickets_total = 4; // u32
+    let max_tickets_per_wallet = 4; // u32
+    let min_tickets_to_draw = 4; // u32
+    let cancelled = 1; // bool
+    let seeded_prize = 8; // u64
+    let pot_lamports = 8; // u64
+    let referral_bps = 2; // u16
+    let referral_fees_accrued = 8; // u64
+    let referral_fees_claimed = 8; // u64
+    let series = 32; // Pubkey
+    let round = 4; // u32
+    let sales_close_ts = 8; // i64
+    let claim_window = 8; // i64
+    let claim_deadline = 8; // i64
+    let prizes_swept = 1; // bool
+    let rent_payer = 32; // Pubkey
+    let ticket_registry = 32; // Pubkey
+    let draw_commitment = 32; // [u8; 32]
+    let draw_slot = 8; // u64
+    let bundles_closed = 4; // u32
+    let prizes_carried_over = 1; // bool
 
     let buffer = 32; // safety buffer
 
@@ -32,20 +70,215 @@ pub fn get_lottery_size() -> usize {
         + lamports_per_ticket
         + start_hex
         + end_hex
-        + winner_settled
         + refunds_settled
         + open
         + platform_fee_percentage
+        + allow_shared_tickets
+        + bundle_count
+        + prize_pool
+        + prize_tiers
+        + game_type
+        + draws
+        + lottery_seed
+        + bump
+        + payment_mint
+        + payment_vault
+        + ticket_revenue
+        + platform_fee_withdrawn
+        + max_tickets_total
+        + max_tickets_per_wallet
+        + min_tickets_to_draw
+        + cancelled
+        + seeded_prize
+        + pot_lamports
+        + referral_bps
+        + referral_fees_accrued
+        + referral_fees_claimed
+        + series
+        + round
+        + sales_close_ts
+        + claim_window
+        + claim_deadline
+        + prizes_swept
+        + rent_payer
+        + ticket_registry
+        + draw_commitment
+        + draw_slot
+        + bundles_closed
+        + prizes_carried_over
+        + buffer
+}
+
+pub fn get_draw_size() -> usize {
+    let pool_percentage = 1; // u8
+    let drawn = 1; // bool
+    let winning_code = 8; // [u8; 8]
+    let feed_slot = 8; // u64
+    let winning_ticket_index = 4; // u32
+    let bundles_tallied = 4; // u32
+    let tier_winning_tickets = MAX_PRIZE_TIERS * 4; // [u32; MAX_PRIZE_TIERS]
+    let tier_tickets_paid = MAX_PRIZE_TIERS * 4; // [u32; MAX_PRIZE_TIERS]
+    let settled = 1; // bool
+
+    pool_percentage
+        + drawn
+        + winning_code
+        + feed_slot
+        + winning_ticket_index
+        + bundles_tallied
+        + tier_winning_tickets
+        + tier_tickets_paid
+        + settled
+}
+
+pub fn get_lottery_template_size() -> usize {
+    let lamports_per_ticket = 8; // u64
+    let start_hex = 4 + MAX_TICKET_CODE_HEX_LEN; // String, same bound as the lottery
+    let end_hex = 4 + MAX_TICKET_CODE_HEX_LEN; // String
+    let platform_fee_percentage = 2; // u16
+    let allow_shared_tickets = 1; // bool
+    let prize_tiers = 4 + MAX_PRIZE_TIERS * 2; // Vec<PrizeTier>
+    let game_type = 1; // enum
+    let draw_percentages = 4 + MAX_DRAWS; // Vec<u8>
+    let max_tickets_total = 4; // u32
+    let max_tickets_per_wallet = 4; // u32
+    let min_tickets_to_draw = 4; // u32
+    let referral_bps = 2; // u16
+    let claim_window = 8; // i64
+
+    lamports_per_ticket
+        + start_hex
+        + end_hex
+        + platform_fee_percentage
+        + allow_shared_tickets
+        + prize_tiers
+        + game_type
+        + draw_percentages
+        + max_tickets_total
+        + max_tickets_per_wallet
+        + min_tickets_to_draw
+        + referral_bps
+        + claim_window
+}
+
+pub fn get_lottery_series_size() -> usize {
+    let discriminator = 8;
+    let series_seed = 4 + MAX_SERIES_SEED_LEN; // String
+    let bump = 1; // u8
+    let switchboard_feed = 32; // Pubkey
+    let round_duration = 8; // i64
+    let template = get_lottery_template_size();
+    let current_round = 4; // u32
+    let current_lottery = 32; // Pubkey
+    let buffer = 16;
+
+    discriminator
+        + series_seed
+        + bump
+        + switchboard_feed
+        + round_duration
+        + template
+        + current_round
+        + current_lottery
+        + buffer
+}
+
+pub fn get_player_tickets_size() -> usize {
+    let discriminator = 8;
+    let lottery = 32;
+    let owner = 32;
+    let tickets_bought = 4;
+    let bump = 1;
+    let buffer = 8;
+
+    discriminator + lottery + owner + tickets_bought + bump + buffer
+}
+
+pub fn get_referral_rewards_size() -> usize {
+    let discriminator = 8;
+    let lottery = 32;
+    let referrer = 32;
+    let accrued = 8;
+    let claimed = 8;
+    let bump = 1;
+    let buffer = 8;
+
+    discriminator + lottery + referrer + accrued + claimed + bump + buffer
+}
+
+pub fn get_player_account_size() -> usize {
+    let discriminator = 8;
+    let owner = 32;
+    let payment_mint = 32;
+    let balance_vault = 32;
+    let balance = 8;
+    let nonce = 8;
+    let bump = 1;
+    let buffer = 8;
+
+    discriminator + owner + payment_mint + balance_vault + balance + nonce + bump + buffer
+}
+
+pub fn get_quick_pick_request_size() -> usize {
+    let discriminator = 8;
+    let lottery = 32;
+    let owner = 32;
+    let tx_sig_hash = 32;
+    let ticket_count = 1;
+    let source = 1 + 32; // enum, largest variant
+    let referrer = 1 + 32;
+    let request_slot = 8;
+    let bump = 1;
+    let buffer = 8;
+
+    discriminator
+        + lottery
+        + owner
+        + tx_sig_hash
+        + ticket_count
+        + source
+        + referrer
+        + request_slot
+        + bump
         + buffer
 }
 
+/// Zero-copy account, no buffer: the layout is fixed.
+pub fn get_ticket_registry_size() -> usize {
+    let discriminator = 8;
+    let lottery = 32;
+    let rent_payer = 32;
+    let sold = (MAX_REGISTRY_CODES / 8) as usize;
+
+    discriminator + lottery + rent_payer + sold
+}
+
 pub fn get_transaction_bundle_size() -> usize {
     let discriminator = 8;
     let lottery = 32;
     let owner = 32;
     let tickets = 100 * 8;
     let refudned = 1;
+    let ticket_count = 2;
+    let first_ticket_index = 4;
+    let draws_tallied = 1;
+    let tier_winning_tickets = MAX_DRAWS * MAX_PRIZE_TIERS * 2;
+    let draws_rewarded = 1;
+    let rent_payer = 32;
+    let paid_from_balance = 1;
     let buffer = 16;
 
-    discriminator + lottery + owner + tickets + refudned + buffer
+    discriminator
+        + lottery
+        + owner
+        + tickets
+        + refudned
+        + ticket_count
+        + first_ticket_index
+        + draws_tallied
+        + tier_winning_tickets
+        + draws_rewarded
+        + rent_payer
+        + paid_from_balance
+        + buffer
 }
@@ -1,9 +1,39 @@
 use anchor_lang::prelude::*;
 
+/// Maximum number of prize tiers a lottery can define.
+pub const MAX_PRIZE_TIERS: usize = 4;
+
+/// Maximum number of sequential draws a lottery can perform.
+pub const MAX_DRAWS: usize = 5;
+
+/// Number of hex digits in a ticket code, i.e. a full match.
+pub const TICKET_CODE_DIGITS: u8 = 16;
+
+/// Longest hex string a ticket code range bound can be stored as: an optional
+/// `0x` prefix and `TICKET_CODE_DIGITS` digits (see `ticket_code_from_hex`).
+pub const MAX_TICKET_CODE_HEX_LEN: usize = 2 + TICKET_CODE_DIGITS as usize;
+
+/// Maximum length of a series seed, leaving room for the round suffix of
+/// `lottery_seed` within the 32-byte PDA seed limit.
+pub const MAX_SERIES_SEED_LEN: usize = 20;
+
+/// Number of ticket codes a `TicketRegistry` can track, one bit each.
+pub const MAX_REGISTRY_CODES: u64 = 65_536;
+
+/// Most tickets a single quick pick can request, the capacity of a bundle.
+pub const MAX_QUICK_PICK_TICKETS: u8 = 100;
+
 #[account]
 pub struct Configuration {
-    /// Global admin of the entire lottery program.
+    /// Global admin of the entire lottery program. May be a wallet or the PDA
+    /// of an on-chain multisig (e.g. a Squads vault) signing through CPI.
     pub admin: Pubkey,
+
+    /// Admin proposed by `propose_admin`, `Pubkey::default()` when none.
+    pub pending_admin: Pubkey,
+
+    /// Account that paid the rent, refunded to it on close.
+    pub rent_payer: Pubkey,
 }
 
 #[account]
@@ -14,7 +44,7 @@ pub struct Lottery {
     /// Total number of tickets sold for this lottery.
     pub tickets_sold: u32,
 
-    /// Lamports charged per ticket.
+    /// Lamports charged per ticket (token base units when `payment_mint` is set).
     pub lamports_per_ticket: u64,
 
     /// Starting hex (e.g. "0", "00", "000").
@@ -23,9 +53,6 @@ pub struct Lottery {
     /// Ending hex (e.g. "F", "FF", "FFF").
     pub ticket_code_end_hex: String,
 
-    /// Whether winner payout is completed.
-    pub winner_settled: bool,
-
     /// Number of refunds completed.
     pub refunds_settled: u32,
 
@@ -34,6 +61,418 @@ pub struct Lottery {
 
     /// Platform fee in percentage (0–100).
     pub platform_fee_percentage: u16,
+
+    /// Whether several bundles may hold the same ticket code.
+    /// When true the prize is split between every holder of the winning code.
+    pub allow_shared_tickets: bool,
+
+    /// Number of transaction bundles created for this lottery.
+    pub bundle_count: u32,
+
+    /// Lamports available to winners, fixed at the first draw (platform fee excluded).
+    pub prize_pool: u64,
+
+    /// Prize table, ordered from the strictest match to the loosest.
+    pub prize_tiers: Vec<PrizeTier>,
+
+    /// How the winner is determined.
+    pub game_type: GameType,
+
+    /// Sequential draws (first prize, second prize, ...) performed in order.
+    pub draws: Vec<Draw>,
+
+    /// Seed the lottery PDA was derived from, kept to sign vault transfers.
+    pub lottery_seed: String,
+
+    /// Bump of the lottery PDA.
+    pub bump: u8,
+
+    /// SPL mint tickets are paid in, `Pubkey::default()` for SOL lotteries.
+    pub payment_mint: Pubkey,
+
+    /// Lottery-owned associated token account escrowing token payments.
+    pub payment_vault: Pubkey,
+
+    /// Ticket sale proceeds actually received, net of Token-2022 transfer fees.
+    pub ticket_revenue: u64,
+
+    /// Whether the platform fee has been withdrawn.
+    pub platform_fee_withdrawn: bool,
+
+    /// Maximum number of tickets the lottery can sell.
+    /// Never exceeds the code space when tickets are unique.
+    pub max_tickets_total: u32,
+
+    /// Maximum number of tickets a single owner can buy, 0 for no limit.
+    pub max_tickets_per_wallet: u32,
+
+    /// Tickets that must be sold for the lottery to be drawn.
+    pub min_tickets_to_draw: u32,
+
+    /// Set when sales close below `min_tickets_to_draw`: the lottery can
+    /// no longer be drawn and every bundle is refunded in full.
+    pub cancelled: bool,
+
+    /// Guaranteed prize deposited by the operator, added to the prize pool at the
+    /// first draw. Kept apart from `ticket_revenue` so no fee is taken on it.
+    pub seeded_prize: u64,
+
+    /// Funds the lottery holds on behalf of players and the platform (token base
+    /// units for token lotteries): credited by every purchase and seed, debited by
+    /// every payout. Rent and untracked transfers are not part of it.
+    pub pot_lamports: u64,
+
+    /// Share of the platform fee paid to referrers, in basis points.
+    pub referral_bps: u16,
+
+    /// Referral earnings accrued across all referrers, carved out of the platform fee.
+    pub referral_fees_accrued: u64,
+
+    /// Referral earnings already claimed.
+    pub referral_fees_claimed: u64,
+
+    /// Series this lottery is a round of, `Pubkey::default()` for standalone lotteries.
+    pub series: Pubkey,
+
+    /// Round number within `series`, 0 for standalone lotteries.
+    pub round: u32,
+
+    /// Unix timestamp sales end at, 0 when sales only close on `close_lottery`.
+    pub sales_close_ts: i64,
+
+    /// Seconds winners have to claim once the last draw is fully tallied, 0 for no deadline.
+    pub claim_window: i64,
+
+    /// Unix timestamp after which unpaid prizes can be swept, 0 until the last draw is
+    /// fully tallied.
+    pub claim_deadline: i64,
+
+    /// Whether unpaid prizes have been swept after the claim deadline.
+    pub prizes_swept: bool,
+
+    /// Account that paid the rent of the lottery (and its vault), refunded to it on close.
+    pub rent_payer: Pubkey,
+
+    /// Registry of sold codes keeping codes unique across bundles; unique lotteries
+    /// sell only once it exists. Default when none.
+    pub ticket_registry: Pubkey,
+
+    /// `hash(secret)` committed by the admin at creation for a commit-reveal draw,
+    /// zeroed when draws read the Switchboard feed.
+    pub draw_commitment: [u8; 32],
+
+    /// Slot sales closed in, whose hash a commit-reveal draw mixes in. Once that hash
+    /// leaves SlotHashes unrevealed, the lottery can only be cancelled.
+    pub draw_slot: u64,
+
+    /// Number of settled transaction bundles closed so far.
+    pub bundles_closed: u32,
+
+    /// Series rounds only: whether the prizes this round left behind have moved on
+    /// to a later round, at the roll or through `carry_over_prizes`.
+    pub prizes_carried_over: bool,
+}
+
+impl Lottery {
+    /// Whether this lottery is a round of a `LotterySeries`.
+    pub fn is_series_round(&self) -> bool {
+        self.series != Pubkey::default()
+    }
+
+    /// Whether any draw has taken place.
+    pub fn has_drawn(&self) -> bool {
+        self.draws.first().is_some_and(|d| d.drawn)
+    }
+
+    /// Index of the next draw to perform, if any remain.
+    pub fn next_draw_index(&self) -> Option<usize> {
+        self.draws.iter().position(|d| !d.drawn)
+    }
+
+    /// Whether sold codes are tracked in a `TicketRegistry`.
+    pub fn has_ticket_registry(&self) -> bool {
+        self.ticket_registry != Pubkey::default()
+    }
+
+    /// Whether draws reveal a committed secret instead of reading the Switchboard feed.
+    pub fn is_commit_reveal(&self) -> bool {
+        self.draw_commitment != [0; 32]
+    }
+
+    /// Whether tickets are paid in an SPL token rather than SOL.
+    pub fn is_token_lottery(&self) -> bool {
+        self.payment_mint != Pubkey::default()
+    }
+
+    /// Platform fee taken from ticket revenue.
+    pub fn platform_fee(&self) -> u64 {
+        self.ticket_revenue * (self.platform_fee_percentage as u64) / 100
+    }
+
+    /// Referral share of the platform fee on a purchase of `amount`.
+    pub fn referral_fee(&self, amount: u64) -> u64 {
+        ((amount as u128) * (self.platform_fee_percentage as u128) * (self.referral_bps as u128)
+            / 1_000_000) as u64
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct Draw {
+    /// Share of the prize pool paid out by this draw, in percentage.
+    pub pool_percentage: u8,
+
+    /// Whether this draw has taken place.
+    pub drawn: bool,
+
+    /// Winning ticket code read from the Switchboard feed for this draw.
+    pub winning_code: [u8; 8],
+
+    /// Slot of the feed result the winning code was read from, 0 for commit-reveal draws.
+    pub feed_slot: u64,
+
+    /// Raffle only: index of the winning ticket among all tickets sold.
+    pub winning_ticket_index: u32,
+
+    /// Number of bundles checked against this draw.
+    pub bundles_tallied: u32,
+
+    /// Number of winning tickets per prize tier across all bundles.
+    pub tier_winning_tickets: [u32; MAX_PRIZE_TIERS],
+
+    /// Number of winning tickets already paid out per prize tier.
+    pub tier_tickets_paid: [u32; MAX_PRIZE_TIERS],
+
+    /// Whether every winning share of this draw has been paid.
+    pub settled: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum GameType {
+    /// Tickets win by matching the drawn code (see `prize_tiers`).
+    CodeMatch,
+
+    /// Each draw picks a winning ticket among those sold, so every draw has a winner; a
+    /// ticket wins again only once draws outnumber tickets. Drawn by commit-reveal only:
+    /// a feed value is known before the draw.
+    Raffle,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct PrizeTier {
+    /// Trailing hex digits of the winning code a ticket must match (16 = jackpot).
+    pub match_digits: u8,
+
+    /// Share of the prize pool paid to this tier, in percentage.
+    pub pool_percentage: u8,
+}
+
+/// Settings every lottery is created from, kept by a series to open each round.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct LotteryTemplate {
+    /// Lamports charged per ticket.
+    pub lamports_per_ticket: u64,
+
+    /// Starting hex of the ticket code range.
+    pub ticket_code_start_hex: String,
+
+    /// Ending hex of the ticket code range.
+    pub ticket_code_end_hex: String,
+
+    /// Platform fee in percentage (0–100).
+    pub platform_fee_percentage: u16,
+
+    /// Whether several bundles may hold the same ticket code.
+    pub allow_shared_tickets: bool,
+
+    /// Prize table, ordered from the strictest match to the loosest (empty = jackpot only).
+    pub prize_tiers: Vec<PrizeTier>,
+
+    /// How the winner is determined.
+    pub game_type: GameType,
+
+    /// Share of the pool per sequential draw (empty = a single draw).
+    pub draw_percentages: Vec<u8>,
+
+    /// Ticket cap of the lottery, 0 for as many as the code space allows.
+    pub max_tickets_total: u32,
+
+    /// Ticket cap per owner, 0 for no limit.
+    pub max_tickets_per_wallet: u32,
+
+    /// Tickets that must be sold for the lottery to be drawn.
+    pub min_tickets_to_draw: u32,
+
+    /// Share of the platform fee paid to referrers, in basis points.
+    pub referral_bps: u16,
+
+    /// Seconds winners have to claim once a draw is tallied, 0 for no deadline.
+    pub claim_window: i64,
+}
+
+/// Ticket purchase a player authorizes off-chain by signing its borsh bytes
+/// with ed25519. A relayer submits it together with the signature, so the
+/// player never needs to hold SOL for fees.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct PurchaseIntent {
+    /// Lottery the tickets are bought in.
+    pub lottery: Pubkey,
+
+    /// Player signing the intent and owning the tickets.
+    pub owner: Pubkey,
+
+    /// Ticket codes to buy.
+    pub tickets: Vec<[u8; 8]>,
+
+    /// Most the player agrees to pay for all tickets, in lamports or token base units.
+    pub max_price: u64,
+
+    /// Unix timestamp after which the intent can no longer be submitted.
+    pub expiry: i64,
+
+    /// Must equal the player account's nonce; every accepted intent bumps it.
+    pub nonce: u64,
+
+    /// Earns the referral share of the purchase, if any.
+    pub referrer: Option<Pubkey>,
+}
+
+#[account]
+pub struct LotterySeries {
+    /// Seed the series PDA was derived from.
+    pub series_seed: String,
+
+    /// Bump of the series PDA.
+    pub bump: u8,
+
+    /// Switchboard feed every round draws from.
+    pub switchboard_feed_btc_block_decimal: Pubkey,
+
+    /// Seconds each round is open for sales.
+    pub round_duration: i64,
+
+    /// Settings of every round (SOL lotteries only).
+    pub template: LotteryTemplate,
+
+    /// Number of the round currently running, starting at 1.
+    pub current_round: u32,
+
+    /// Lottery PDA of the current round.
+    pub current_lottery: Pubkey,
+}
+
+#[account]
+pub struct PlayerTickets {
+    /// Lottery the tickets were bought in.
+    pub lottery: Pubkey,
+
+    /// Owner of the tickets.
+    pub owner: Pubkey,
+
+    /// Tickets bought by this owner across all of their bundles.
+    pub tickets_bought: u32,
+
+    /// Bump of the player PDA.
+    pub bump: u8,
+}
+
+#[account]
+pub struct ReferralRewards {
+    /// Lottery the referrals were made in.
+    pub lottery: Pubkey,
+
+    /// Referrer earning a share of the platform fee.
+    pub referrer: Pubkey,
+
+    /// Total earnings accrued by purchases this referrer brought.
+    pub accrued: u64,
+
+    /// Earnings already claimed.
+    pub claimed: u64,
+
+    /// Bump of the referrer PDA.
+    pub bump: u8,
+}
+
+#[account]
+pub struct PlayerAccount {
+    /// Player owning the deposited balance.
+    pub owner: Pubkey,
+
+    /// Mint of the deposited balance; the default pubkey for SOL.
+    pub payment_mint: Pubkey,
+
+    /// Token balances only: token account owned by this PDA holding the balance.
+    pub balance_vault: Pubkey,
+
+    /// Deposited balance, plus refunds and prizes credited to it, available for purchases.
+    pub balance: u64,
+
+    /// Nonce the next purchase intent must carry.
+    pub nonce: u64,
+
+    /// Bump of the player account PDA.
+    pub bump: u8,
+}
+
+/// Randomness a quick pick derives its ticket codes from.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum QuickPickSource {
+    /// Switchboard on-demand randomness account, committed when the pick is requested
+    /// and revealed in the fulfilment transaction.
+    Switchboard { randomness_account: Pubkey },
+
+    /// sha256 of a player secret revealed on fulfilment, mixed with the hash of the
+    /// request slot (unknown when the secret is committed).
+    CommitReveal { commitment: [u8; 32] },
+}
+
+#[account]
+pub struct QuickPickRequest {
+    /// Lottery the tickets are bought in.
+    pub lottery: Pubkey,
+
+    /// Player the tickets are for, paying from their player balance.
+    pub owner: Pubkey,
+
+    /// Seeds the bundle created on fulfilment.
+    pub tx_sig_hash: [u8; 32],
+
+    /// Number of codes to pick.
+    pub ticket_count: u8,
+
+    /// Randomness the codes are derived from.
+    pub source: QuickPickSource,
+
+    /// Earns the referral share of the purchase on fulfilment, if any.
+    pub referrer: Option<Pubkey>,
+
+    /// Slot the pick was requested in.
+    pub request_slot: u64,
+
+    /// Bump of the quick pick PDA.
+    pub bump: u8,
+}
+
+#[account(zero_copy)]
+pub struct TicketRegistry {
+    /// Lottery whose sold codes are tracked.
+    pub lottery: Pubkey,
+
+    /// Account that paid the rent of the registry, refunded to it on close.
+    pub rent_payer: Pubkey,
+
+    /// One bit per code of the lottery range (offset from its start), set once sold.
+    pub sold: [u8; (MAX_REGISTRY_CODES / 8) as usize],
+}
+
+impl TicketRegistry {
+    pub fn is_sold(&self, offset: u64) -> bool {
+        self.sold[(offset / 8) as usize] & (1 << (offset % 8)) != 0
+    }
+
+    pub fn mark_sold(&mut self, offset: u64) {
+        self.sold[(offset / 8) as usize] |= 1 << (offset % 8);
+    }
 }
 
 #[account]
@@ -44,10 +483,33 @@ pub struct TransactionBundle {
     /// The owner of all the tickets in this bundle.
     pub owner: Pubkey,
 
-    /// Each ticket is stored as hex bytes (max 8 chars).  
-    /// Example: "1A2B3C" -> [ '1','A','2','B','3','C',0,0 ]
+    /// Each ticket code is stored as the big-endian bytes of its hex value (max 16 chars).
+    /// Example: "1A2B3C" -> [ 0,0,0,0,0,0x1A,0x2B,0x3C ] (see `ticket_code_from_hex`)
     pub tickets: [[u8; 8]; 100],
 
     /// To know whether the refund as been issues
     pub refunded: bool,
+
+    /// Number of tickets actually stored in `tickets`.
+    pub ticket_count: u16,
+
+    /// Index of this bundle's first ticket in the lottery's sale order.
+    /// The bundle holds tickets `first_ticket_index..first_ticket_index + ticket_count`.
+    pub first_ticket_index: u32,
+
+    /// Number of draws this bundle has been checked against (tallied in draw order).
+    pub draws_tallied: u8,
+
+    /// Number of tickets in this bundle winning each prize tier, per draw.
+    pub tier_winning_tickets: [[u16; MAX_PRIZE_TIERS]; MAX_DRAWS],
+
+    /// Bitmask of draws whose share has been paid to this bundle.
+    pub draws_rewarded: u8,
+
+    /// Account that paid the rent of the bundle (e.g. a relayer or the player),
+    /// refunded to it on close.
+    pub rent_payer: Pubkey,
+
+    /// Paid from the owner's player balance; refunds are credited back to it.
+    pub paid_from_balance: bool,
 }
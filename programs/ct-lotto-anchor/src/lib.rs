@@ -2,12 +2,19 @@ pub mod account_instruction;
 pub mod account_size;
 pub mod account_struct;
 pub mod lotto_enum;
+pub mod lotto_event;
 pub mod lotto_util;
 use crate::account_instruction::*;
+use crate::account_struct::{
+    GameType, LotteryTemplate, PrizeTier, PurchaseIntent, QuickPickSource, MAX_QUICK_PICK_TICKETS,
+    MAX_REGISTRY_CODES, MAX_SERIES_SEED_LEN,
+};
 use crate::lotto_enum::*;
+use crate::lotto_event::*;
 use crate::lotto_util::*;
 use anchor_lang::prelude::*;
 use solana_security_txt::security_txt;
+use solana_sha256_hasher::hash;
 
 declare_id!("9pJW6zuXvTweg5doiWn9wAsqKcnbMsJPWLq9zWyGApmA");
 
@@ -30,86 +37,1373 @@ pub mod ct_lotto_anchor {
     --------------------------------------------------*/
     pub fn create_configuration_pda(ctx: Context<CreateConfigurationPDA>) -> Result<()> {
         ctx.accounts.configuration.admin = ctx.accounts.admin.key();
+        ctx.accounts.configuration.pending_admin = Pubkey::default();
+        ctx.accounts.configuration.rent_payer = ctx.accounts.payer.key();
 
         msg!(
             "CONFIGURATION_PDA_CREATED: {}",
             ctx.accounts.configuration.key()
         );
 
+        emit_cpi!(ConfigUpdated {
+            configuration: ctx.accounts.configuration.key(),
+            admin: ctx.accounts.configuration.admin,
+            pending_admin: ctx.accounts.configuration.pending_admin,
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+
+    /* -------------------------------------------------
+       PROPOSE ADMIN (e.g. a multisig vault PDA)
+    --------------------------------------------------*/
+    pub fn propose_admin(ctx: Context<ProposeAdmin>, new_admin: Pubkey) -> Result<()> {
+        require_eq!(
+            ctx.accounts.admin.key(),
+            ctx.accounts.configuration.admin,
+            LottoError::AdminOnlyAction
+        );
+
+        // Proposing the default key withdraws a pending proposal
+        ctx.accounts.configuration.pending_admin = new_admin;
+
+        msg!("ADMIN_PROPOSED: {}", new_admin);
+
+        emit_cpi!(ConfigUpdated {
+            configuration: ctx.accounts.configuration.key(),
+            admin: ctx.accounts.configuration.admin,
+            pending_admin: new_admin,
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+
+    /* -------------------------------------------------
+       ACCEPT ADMIN (signed by the proposed admin)
+    --------------------------------------------------*/
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        let configuration = &mut ctx.accounts.configuration;
+
+        require!(
+            configuration.pending_admin != Pubkey::default(),
+            LottoError::PendingAdminOnly
+        );
+        require_keys_eq!(
+            ctx.accounts.pending_admin.key(),
+            configuration.pending_admin,
+            LottoError::PendingAdminOnly
+        );
+
+        configuration.admin = configuration.pending_admin;
+        configuration.pending_admin = Pubkey::default();
+
+        msg!("ADMIN_ACCEPTED: {}", configuration.admin);
+
+        emit_cpi!(ConfigUpdated {
+            configuration: configuration.key(),
+            admin: configuration.admin,
+            pending_admin: configuration.pending_admin,
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+
+    /* -------------------------------------------------
+       CREATE LOTTERY
+    --------------------------------------------------*/
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_lottery_pda(
+        ctx: Context<CreateLotteryPDA>,
+        lottery_seed: String,
+        lamports_per_ticket: u64,
+        start_hex: String,
+        end_hex: String,
+        fee_percent: u16,
+        allow_shared_tickets: bool,
+        prize_tiers: Vec<PrizeTier>,
+        game_type: GameType,
+        draw_percentages: Vec<u8>,
+        max_tickets_total: u32,
+        max_tickets_per_wallet: u32,
+        min_tickets_to_draw: u32,
+        referral_bps: u16,
+        claim_window: i64,
+        draw_commitment: Option<[u8; 32]>,
+    ) -> Result<()> {
+        require_eq!(
+            ctx.accounts.admin.key(),
+            ctx.accounts.configuration.admin,
+            LottoError::AdminOnlyAction
+        );
+
+        let template = LotteryTemplate {
+            lamports_per_ticket,
+            ticket_code_start_hex: start_hex,
+            ticket_code_end_hex: end_hex,
+            platform_fee_percentage: fee_percent,
+            allow_shared_tickets,
+            prize_tiers,
+            game_type,
+            draw_percentages,
+            max_tickets_total,
+            max_tickets_per_wallet,
+            min_tickets_to_draw,
+            referral_bps,
+            claim_window,
+        };
+
+        apply_template(&mut ctx.accounts.lottery, &template)?;
+
+        // Draws either read the Switchboard feed or reveal the committed secret
+        match (
+            &ctx.accounts.switchboard_feed_btc_block_decimal,
+            draw_commitment,
+        ) {
+            (Some(feed), None) => {
+                // The block height picks a raffle index anyone can compute ahead
+                require!(
+                    ctx.accounts.lottery.game_type != GameType::Raffle,
+                    LottoError::RaffleRequiresCommitReveal
+                );
+
+                ctx.accounts.lottery.switchboard_feed_btc_block_decimal = feed.key();
+            }
+            (None, Some(commitment)) if commitment != [0; 32] => {
+                ctx.accounts.lottery.switchboard_feed_btc_block_decimal = Pubkey::default();
+                ctx.accounts.lottery.draw_commitment = commitment;
+            }
+            _ => return err!(LottoError::DrawSourceMismatch),
+        }
+
+        ctx.accounts.lottery.lottery_seed = lottery_seed;
+        ctx.accounts.lottery.bump = ctx.bumps.lottery;
+        ctx.accounts.lottery.rent_payer = ctx.accounts.payer.key();
+
+        // Standalone lottery: sales close on `close_lottery`
+        ctx.accounts.lottery.series = Pubkey::default();
+        ctx.accounts.lottery.round = 0;
+        ctx.accounts.lottery.sales_close_ts = 0;
+
+        // Token lotteries escrow payments in a lottery-owned vault
+        match (&ctx.accounts.payment_mint, &ctx.accounts.vault) {
+            (Some(mint), Some(vault)) => {
+                ctx.accounts.lottery.payment_mint = mint.key();
+                ctx.accounts.lottery.payment_vault = vault.key();
+            }
+            (None, None) => {
+                ctx.accounts.lottery.payment_mint = Pubkey::default();
+                ctx.accounts.lottery.payment_vault = Pubkey::default();
+            }
+            _ => return err!(LottoError::TokenAccountsMissing),
+        }
+
+        msg!("LOTTERY_PDA_CREATED: {}", ctx.accounts.lottery.key());
+
+        let lottery = &ctx.accounts.lottery;
+
+        emit_cpi!(LotteryCreated {
+            lottery: lottery.key(),
+            lottery_seed: lottery.lottery_seed.clone(),
+            payment_mint: lottery.payment_mint,
+            lamports_per_ticket: lottery.lamports_per_ticket,
+            ticket_code_start_hex: lottery.ticket_code_start_hex.clone(),
+            ticket_code_end_hex: lottery.ticket_code_end_hex.clone(),
+            platform_fee_percentage: lottery.platform_fee_percentage,
+            allow_shared_tickets: lottery.allow_shared_tickets,
+            game_type: lottery.game_type,
+            draw_count: lottery.draws.len() as u8,
+            max_tickets_total: lottery.max_tickets_total,
+            max_tickets_per_wallet: lottery.max_tickets_per_wallet,
+            min_tickets_to_draw: lottery.min_tickets_to_draw,
+            referral_bps: lottery.referral_bps,
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+
+    /* -------------------------------------------------
+       CREATE LOTTERY SERIES (opens round 1)
+    --------------------------------------------------*/
+    pub fn create_lottery_series(
+        ctx: Context<CreateLotterySeries>,
+        series_seed: String,
+        template: LotteryTemplate,
+        round_duration: i64,
+    ) -> Result<()> {
+        require_eq!(
+            ctx.accounts.admin.key(),
+            ctx.accounts.configuration.admin,
+            LottoError::AdminOnlyAction
+        );
+
+        require!(
+            !series_seed.is_empty() && series_seed.len() <= MAX_SERIES_SEED_LEN,
+            LottoError::InvalidSeriesSeed
+        );
+        require!(round_duration > 0, LottoError::InvalidRoundDuration);
+
+        let series_key = ctx.accounts.series.key();
+        let now = Clock::get()?.unix_timestamp;
+
+        // Opening the first round validates the template once and for all
+        let lottery = &mut ctx.accounts.lottery;
+
+        // Rounds draw from the feed, whose block height would pick a predictable raffle index
+        require!(
+            template.game_type != GameType::Raffle,
+            LottoError::RaffleRequiresCommitReveal
+        );
+
+        apply_template(lottery, &template)?;
+
+        lottery.switchboard_feed_btc_block_decimal =
+            ctx.accounts.switchboard_feed_btc_block_decimal.key();
+        lottery.lottery_seed = series_round_seed(&series_seed, 1);
+        lottery.bump = ctx.bumps.lottery;
+        lottery.rent_payer = ctx.accounts.payer.key();
+        lottery.payment_mint = Pubkey::default();
+        lottery.payment_vault = Pubkey::default();
+        lottery.series = series_key;
+        lottery.round = 1;
+        lottery.sales_close_ts = now + round_duration;
+
+        let series = &mut ctx.accounts.series;
+
+        series.series_seed = series_seed;
+        series.bump = ctx.bumps.series;
+        series.switchboard_feed_btc_block_decimal = lottery.switchboard_feed_btc_block_decimal;
+        series.round_duration = round_duration;
+        series.template = template;
+        series.current_round = 1;
+        series.current_lottery = lottery.key();
+
+        msg!("LOTTERY_SERIES_CREATED: {}", series.key());
+
+        emit_cpi!(SeriesCreated {
+            series: series.key(),
+            series_seed: series.series_seed.clone(),
+            lottery: lottery.key(),
+            round_duration,
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+
+    /* -------------------------------------------------
+       ROLL LOTTERY SERIES (permissionless)
+       - closes the current round once its window is over
+         and opens the next, without waiting for its draws
+       - carries unwon prizes (or a cancelled round's seed)
+         into the next round if the round is already settled,
+         otherwise `carry_over_prizes` does once it is
+    --------------------------------------------------*/
+    pub fn roll_series(ctx: Context<RollSeries>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let current = &mut ctx.accounts.current_lottery;
+
+        require!(now >= current.sales_close_ts, LottoError::RoundStillOpen);
+
+        if current.open {
+            close_sales(current, Clock::get()?.slot);
+
+            msg!("LOTTERY_CLOSED: {}", current.key());
+
+            emit_cpi!(LotteryClosed {
+                lottery: current.key(),
+                tickets_sold: current.tickets_sold,
+                ticket_revenue: current.ticket_revenue,
+                account_closed: false,
+                cancelled: current.cancelled,
+                slot: Clock::get()?.slot,
+            });
+        }
+
+        let series = &mut ctx.accounts.series;
+        let round = series.current_round + 1;
+        let next = &mut ctx.accounts.next_lottery;
+
+        apply_template(next, &series.template)?;
+
+        next.switchboard_feed_btc_block_decimal = series.switchboard_feed_btc_block_decimal;
+        next.lottery_seed = series_round_seed(&series.series_seed, round);
+        next.bump = ctx.bumps.next_lottery;
+        next.rent_payer = ctx.accounts.payer.key();
+        next.payment_mint = Pubkey::default();
+        next.payment_vault = Pubkey::default();
+        next.series = series.key();
+        next.round = round;
+        next.sales_close_ts = now + series.round_duration;
+
+        let carried_over = if current.cancelled || draws_complete(current) {
+            carry_over_round_prizes(current, next)?
+        } else {
+            0
+        };
+
+        series.current_round = round;
+        series.current_lottery = next.key();
+
+        msg!("LOTTERY_SERIES_ROLLED: {} (round {})", series.key(), round);
+
+        emit_cpi!(SeriesRolled {
+            series: series.key(),
+            round,
+            lottery: next.key(),
+            previous_lottery: current.key(),
+            carried_over,
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+
+    /* -------------------------------------------------
+       CARRY OVER PRIZES (permissionless)
+       - once an earlier round is settled, what it left
+         behind joins the pool of the current round
+    --------------------------------------------------*/
+    pub fn carry_over_prizes(ctx: Context<CarryOverPrizes>) -> Result<()> {
+        let round = &mut ctx.accounts.round;
+        let current = &mut ctx.accounts.current_lottery;
+
+        require!(!round.open, LottoError::LotteryStillOpen);
+
+        // The carried prize must still make it into the current round's pool
+        require!(
+            current.open && !current.has_drawn(),
+            LottoError::LotteryClosed
+        );
+
+        let amount = carry_over_round_prizes(round, current)?;
+
+        msg!("PRIZES_CARRIED_OVER: {} -> {}", round.key(), current.key());
+
+        emit_cpi!(PrizesCarriedOver {
+            series: ctx.accounts.series.key(),
+            lottery: round.key(),
+            to_lottery: current.key(),
+            amount,
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+
+    /* -------------------------------------------------
+       CREATE TRANSACTION BUNDLE (with purchased numbers)
+    --------------------------------------------------*/
+    pub fn create_transaction_bundle(
+        ctx: Context<CreateTransactionBundle>,
+        _lottery_seed: String,
+        tx_sig_hash: [u8; 32],
+        owner: Pubkey,
+        purchased_numbers: Vec<[u8; 8]>,
+        referrer: Option<Pubkey>,
+    ) -> Result<()> {
+        require_eq!(
+            ctx.accounts.admin.key(),
+            ctx.accounts.configuration.admin,
+            LottoError::AdminOnlyAction
+        );
+
+        let bundle = &mut ctx.accounts.bundle;
+        let lottery = &mut ctx.accounts.lottery;
+        let player = &mut ctx.accounts.player_tickets;
+
+        if player.lottery == Pubkey::default() {
+            player.lottery = lottery.key();
+            player.owner = owner;
+            player.bump = ctx.bumps.player_tickets;
+        }
+
+        fill_bundle(
+            lottery,
+            bundle,
+            player,
+            ctx.accounts.ticket_registry.as_ref(),
+            owner,
+            &purchased_numbers,
+        )?;
+
+        bundle.rent_payer = ctx.accounts.payer.key();
+
+        // Token-2022 mints may withhold a transfer fee from the payment
+        let ticket_amount = lottery.lamports_per_ticket * (purchased_numbers.len() as u64);
+
+        let received_amount = match &ctx.accounts.funder {
+            // Paid here, which also works when this instruction is a CPI
+            Some(funder) => {
+                let token = vault_transfer(
+                    lottery,
+                    &ctx.accounts.payment_mint,
+                    &ctx.accounts.vault,
+                    &ctx.accounts.funder_token_account,
+                    &ctx.accounts.token_program,
+                    &funder.key(),
+                )?;
+
+                pay_into_lottery(
+                    lottery,
+                    funder,
+                    &ctx.accounts.system_program,
+                    token.as_ref(),
+                    ticket_amount,
+                )?
+            }
+            None => {
+                // The owner's payment for exactly these tickets must precede this instruction
+                verify_purchase_payment(
+                    &ctx.accounts.instructions,
+                    lottery,
+                    &owner,
+                    ticket_amount,
+                )?;
+
+                if lottery.is_token_lottery() {
+                    let mint = ctx
+                        .accounts
+                        .payment_mint
+                        .as_ref()
+                        .ok_or(LottoError::TokenAccountsMissing)?;
+
+                    require_keys_eq!(
+                        mint.key(),
+                        lottery.payment_mint,
+                        LottoError::TokenAccountMismatch
+                    );
+
+                    net_of_transfer_fee(mint, ticket_amount)?
+                } else {
+                    ticket_amount
+                }
+            }
+        };
+
+        record_sale(lottery, bundle.ticket_count, received_amount);
+
+        // The referrer's cut comes out of the platform fee, never the prize pot
+        if let (Some(referrer), Some(amount)) = (
+            referrer,
+            accrue_referral(
+                lottery.key(),
+                lottery,
+                ctx.accounts.referral.as_deref_mut(),
+                ctx.bumps.referral,
+                referrer,
+                &owner,
+                received_amount,
+            )?,
+        ) {
+            emit_cpi!(ReferralAccrued {
+                lottery: lottery.key(),
+                bundle: bundle.key(),
+                referrer,
+                amount,
+                slot: Clock::get()?.slot,
+            });
+        }
+
+        msg!("TRANSACTION_BUNDLE_CREATED: {}", bundle.key());
+
+        emit_cpi!(BundleCreated {
+            lottery: lottery.key(),
+            bundle: bundle.key(),
+            owner,
+            tx_sig_hash,
+            ticket_count: bundle.ticket_count,
+            first_ticket_index: bundle.first_ticket_index,
+            amount: received_amount,
+            tickets_sold: lottery.tickets_sold,
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+
+    /* -------------------------------------------------
+       PURCHASE WITH SIGNED INTENT (relayed, permissionless)
+       - the player signs the intent off-chain; any relayer
+         submits it right after the Ed25519 program instruction
+       - tickets are paid from the player's deposited balance
+    --------------------------------------------------*/
+    pub fn purchase_with_intent(
+        ctx: Context<PurchaseWithIntent>,
+        _lottery_seed: String,
+        tx_sig_hash: [u8; 32],
+        intent: PurchaseIntent,
+    ) -> Result<()> {
+        let message = intent.try_to_vec()?;
+
+        // The bundle address commits to exactly this intent
+        require!(
+            hash(&message).to_bytes() == tx_sig_hash,
+            LottoError::IntentMismatch
+        );
+
+        verify_intent_signature(&ctx.accounts.instructions, &intent.owner, &message)?;
+
+        require!(
+            Clock::get()?.unix_timestamp <= intent.expiry,
+            LottoError::IntentExpired
+        );
+
+        let player_account = &mut ctx.accounts.player_account;
+
+        // Each nonce is accepted once, so a signed intent cannot be replayed
+        require_eq!(
+            intent.nonce,
+            player_account.nonce,
+            LottoError::IntentNonceMismatch
+        );
+        player_account.nonce += 1;
+
+        let bundle = &mut ctx.accounts.bundle;
+        let lottery = &mut ctx.accounts.lottery;
+        let player = &mut ctx.accounts.player_tickets;
+
+        if player.lottery == Pubkey::default() {
+            player.lottery = lottery.key();
+            player.owner = intent.owner;
+            player.bump = ctx.bumps.player_tickets;
+        }
+
+        fill_bundle(
+            lottery,
+            bundle,
+            player,
+            ctx.accounts.ticket_registry.as_ref(),
+            intent.owner,
+            &intent.tickets,
+        )?;
+
+        bundle.rent_payer = ctx.accounts.payer.key();
+        bundle.paid_from_balance = true;
+
+        let ticket_amount = lottery.lamports_per_ticket * (intent.tickets.len() as u64);
+
+        require!(
+            ticket_amount <= intent.max_price,
+            LottoError::IntentPriceExceeded
+        );
+
+        let received_amount = pay_tickets_from_balance(
+            lottery,
+            player_account,
+            &ctx.accounts.payment_mint,
+            &ctx.accounts.balance_vault,
+            &ctx.accounts.vault,
+            &ctx.accounts.token_program,
+            ticket_amount,
+        )?;
+
+        record_sale(lottery, bundle.ticket_count, received_amount);
+
+        // The referrer is part of the signed intent, so a relayer cannot credit itself
+        if let (Some(referrer), Some(amount)) = (
+            intent.referrer,
+            accrue_referral(
+                lottery.key(),
+                lottery,
+                ctx.accounts.referral.as_deref_mut(),
+                ctx.bumps.referral,
+                intent.referrer,
+                &intent.owner,
+                received_amount,
+            )?,
+        ) {
+            emit_cpi!(ReferralAccrued {
+                lottery: lottery.key(),
+                bundle: bundle.key(),
+                referrer,
+                amount,
+                slot: Clock::get()?.slot,
+            });
+        }
+
+        msg!("TRANSACTION_BUNDLE_CREATED: {}", bundle.key());
+
+        emit_cpi!(BundleCreated {
+            lottery: lottery.key(),
+            bundle: bundle.key(),
+            owner: intent.owner,
+            tx_sig_hash,
+            ticket_count: bundle.ticket_count,
+            first_ticket_index: bundle.first_ticket_index,
+            amount: received_amount,
+            tickets_sold: lottery.tickets_sold,
+            slot: Clock::get()?.slot,
+        });
+
+        emit_cpi!(IntentExecuted {
+            lottery: lottery.key(),
+            bundle: bundle.key(),
+            owner: intent.owner,
+            relayer: ctx.accounts.payer.key(),
+            nonce: intent.nonce,
+            amount: received_amount,
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+
+    /* -------------------------------------------------
+       PURCHASE FROM PLAYER BALANCE (signed by the owner)
+       - no payment transfer needed, the balance pays
+    --------------------------------------------------*/
+    pub fn purchase_from_balance(
+        ctx: Context<PurchaseFromBalance>,
+        _lottery_seed: String,
+        tx_sig_hash: [u8; 32],
+        purchased_numbers: Vec<[u8; 8]>,
+        referrer: Option<Pubkey>,
+    ) -> Result<()> {
+        let owner = ctx.accounts.owner.key();
+        let bundle = &mut ctx.accounts.bundle;
+        let lottery = &mut ctx.accounts.lottery;
+        let player = &mut ctx.accounts.player_tickets;
+
+        if player.lottery == Pubkey::default() {
+            player.lottery = lottery.key();
+            player.owner = owner;
+            player.bump = ctx.bumps.player_tickets;
+        }
+
+        fill_bundle(
+            lottery,
+            bundle,
+            player,
+            ctx.accounts.ticket_registry.as_ref(),
+            owner,
+            &purchased_numbers,
+        )?;
+
+        bundle.rent_payer = owner;
+        bundle.paid_from_balance = true;
+
+        let ticket_amount = lottery.lamports_per_ticket * (purchased_numbers.len() as u64);
+
+        let received_amount = pay_tickets_from_balance(
+            lottery,
+            &mut ctx.accounts.player_account,
+            &ctx.accounts.payment_mint,
+            &ctx.accounts.balance_vault,
+            &ctx.accounts.vault,
+            &ctx.accounts.token_program,
+            ticket_amount,
+        )?;
+
+        record_sale(lottery, bundle.ticket_count, received_amount);
+
+        if let (Some(referrer), Some(amount)) = (
+            referrer,
+            accrue_referral(
+                lottery.key(),
+                lottery,
+                ctx.accounts.referral.as_deref_mut(),
+                ctx.bumps.referral,
+                referrer,
+                &owner,
+                received_amount,
+            )?,
+        ) {
+            emit_cpi!(ReferralAccrued {
+                lottery: lottery.key(),
+                bundle: bundle.key(),
+                referrer,
+                amount,
+                slot: Clock::get()?.slot,
+            });
+        }
+
+        msg!("TRANSACTION_BUNDLE_CREATED: {}", bundle.key());
+
+        emit_cpi!(BundleCreated {
+            lottery: lottery.key(),
+            bundle: bundle.key(),
+            owner,
+            tx_sig_hash,
+            ticket_count: bundle.ticket_count,
+            first_ticket_index: bundle.first_ticket_index,
+            amount: received_amount,
+            tickets_sold: lottery.tickets_sold,
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+
+    /* -------------------------------------------------
+       CREATE TICKET REGISTRY (unique codes across bundles)
+       - unique lotteries sell only once it exists, so it
+         sees every code
+    --------------------------------------------------*/
+    pub fn create_ticket_registry(ctx: Context<CreateTicketRegistry>) -> Result<()> {
+        require_eq!(
+            ctx.accounts.admin.key(),
+            ctx.accounts.configuration.admin,
+            LottoError::AdminOnlyAction
+        );
+
+        let lottery = &mut ctx.accounts.lottery;
+
+        let (start, end) =
+            ticket_code_range(&lottery.ticket_code_start_hex, &lottery.ticket_code_end_hex)?;
+
+        require!(
+            !lottery.allow_shared_tickets
+                && lottery.tickets_sold == 0
+                && end - start < MAX_REGISTRY_CODES,
+            LottoError::InvalidTicketRegistry
+        );
+
+        let mut registry = ctx.accounts.ticket_registry.load_init()?;
+        registry.lottery = lottery.key();
+        registry.rent_payer = ctx.accounts.payer.key();
+
+        lottery.ticket_registry = ctx.accounts.ticket_registry.key();
+
+        msg!("TICKET_REGISTRY_CREATED: {}", lottery.ticket_registry);
+
+        emit_cpi!(TicketRegistryCreated {
+            lottery: lottery.key(),
+            registry: lottery.ticket_registry,
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+
+    /* -------------------------------------------------
+       CLOSE TICKET REGISTRY (after sales close)
+    --------------------------------------------------*/
+    pub fn close_ticket_registry(ctx: Context<CloseTicketRegistry>) -> Result<()> {
+        require_eq!(
+            ctx.accounts.admin.key(),
+            ctx.accounts.configuration.admin,
+            LottoError::AdminOnlyAction
+        );
+
+        let lottery = &mut ctx.accounts.lottery;
+
+        require!(!lottery.open, LottoError::LotteryStillOpen);
+
+        lottery.ticket_registry = Pubkey::default();
+
+        msg!(
+            "TICKET_REGISTRY_CLOSED: {}",
+            ctx.accounts.ticket_registry.key()
+        );
+
+        emit_cpi!(TicketRegistryClosed {
+            lottery: lottery.key(),
+            registry: ctx.accounts.ticket_registry.key(),
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+
+    /* -------------------------------------------------
+       REQUEST QUICK PICK (signed by the owner)
+       - commits to the randomness the codes will come from
+       - tickets are paid from the owner's player balance
+         when the pick is fulfilled
+    --------------------------------------------------*/
+    pub fn request_quick_pick(
+        ctx: Context<RequestQuickPick>,
+        tx_sig_hash: [u8; 32],
+        ticket_count: u8,
+        source: QuickPickSource,
+        referrer: Option<Pubkey>,
+    ) -> Result<()> {
+        let lottery = &ctx.accounts.lottery;
+
+        require!(lottery.open, LottoError::LotteryClosed);
+
+        require!(
+            ticket_count > 0 && ticket_count <= MAX_QUICK_PICK_TICKETS,
+            LottoError::InvalidQuickPickCount
+        );
+
+        require!(
+            lottery.allow_shared_tickets || lottery.has_ticket_registry(),
+            LottoError::TicketRegistryRequired
+        );
+
+        // Fail now rather than once the randomness is spent
+        require!(
+            ctx.accounts.player_account.balance
+                >= lottery.lamports_per_ticket * ticket_count as u64,
+            LottoError::InsufficientBalance
+        );
+
+        let clock = Clock::get()?;
+
+        if let QuickPickSource::Switchboard { randomness_account } = source {
+            let account = ctx
+                .accounts
+                .randomness_account
+                .as_ref()
+                .ok_or(LottoError::RandomnessMismatch)?;
+
+            require_keys_eq!(
+                account.key(),
+                randomness_account,
+                LottoError::RandomnessMismatch
+            );
+
+            require_fresh_randomness(account, clock.slot)?;
+        }
+
+        let quick_pick = &mut ctx.accounts.quick_pick;
+        quick_pick.lottery = lottery.key();
+        quick_pick.owner = ctx.accounts.owner.key();
+        quick_pick.tx_sig_hash = tx_sig_hash;
+        quick_pick.ticket_count = ticket_count;
+        quick_pick.source = source;
+        quick_pick.referrer = referrer;
+        quick_pick.request_slot = clock.slot;
+        quick_pick.bump = ctx.bumps.quick_pick;
+
+        msg!("QUICK_PICK_REQUESTED: {}", quick_pick.key());
+
+        emit_cpi!(QuickPickRequested {
+            lottery: lottery.key(),
+            quick_pick: quick_pick.key(),
+            owner: quick_pick.owner,
+            ticket_count,
+            slot: clock.slot,
+        });
+
+        Ok(())
+    }
+
+    /* -------------------------------------------------
+       FULFILL QUICK PICK (signed by the owner)
+       - Switchboard: the randomness reveal must be in this transaction
+       - commit-reveal: `secret` is mixed with the request slot hash
+    --------------------------------------------------*/
+    pub fn fulfill_quick_pick(
+        ctx: Context<FulfillQuickPick>,
+        _lottery_seed: String,
+        secret: Option<[u8; 32]>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let quick_pick = &ctx.accounts.quick_pick;
+
+        let random_value = match quick_pick.source {
+            QuickPickSource::Switchboard { randomness_account } => {
+                let account = ctx
+                    .accounts
+                    .randomness_account
+                    .as_ref()
+                    .ok_or(LottoError::RandomnessMismatch)?;
+
+                require_keys_eq!(
+                    account.key(),
+                    randomness_account,
+                    LottoError::RandomnessMismatch
+                );
+
+                revealed_randomness(account, quick_pick.request_slot, clock.slot)?
+            }
+            QuickPickSource::CommitReveal { commitment } => {
+                let slot_hashes = ctx
+                    .accounts
+                    .slot_hashes
+                    .as_ref()
+                    .ok_or(LottoError::RandomnessExpired)?;
+
+                reveal_with_slot_hash(
+                    slot_hashes,
+                    quick_pick.request_slot,
+                    &commitment,
+                    &secret.ok_or(LottoError::InvalidSecret)?,
+                )?
+            }
+        };
+
+        let owner = ctx.accounts.owner.key();
+        let bundle = &mut ctx.accounts.bundle;
+        let lottery = &mut ctx.accounts.lottery;
+
+        let code_range =
+            ticket_code_range(&lottery.ticket_code_start_hex, &lottery.ticket_code_end_hex)?;
+
+        let purchased_numbers = {
+            let registry = match &ctx.accounts.ticket_registry {
+                Some(registry) => Some(registry.load()?),
+                None => None,
+            };
+
+            quick_pick_codes(
+                &random_value,
+                quick_pick.ticket_count,
+                code_range,
+                lottery.allow_shared_tickets,
+                registry.as_deref(),
+            )?
+        };
+
+        let player = &mut ctx.accounts.player_tickets;
+
+        if player.lottery == Pubkey::default() {
+            player.lottery = lottery.key();
+            player.owner = owner;
+            player.bump = ctx.bumps.player_tickets;
+        }
+
+        fill_bundle(
+            lottery,
+            bundle,
+            player,
+            ctx.accounts.ticket_registry.as_ref(),
+            owner,
+            &purchased_numbers,
+        )?;
+
+        bundle.rent_payer = owner;
+        bundle.paid_from_balance = true;
+
+        let ticket_amount = lottery.lamports_per_ticket * (purchased_numbers.len() as u64);
+
+        let received_amount = pay_tickets_from_balance(
+            lottery,
+            &mut ctx.accounts.player_account,
+            &ctx.accounts.payment_mint,
+            &ctx.accounts.balance_vault,
+            &ctx.accounts.vault,
+            &ctx.accounts.token_program,
+            ticket_amount,
+        )?;
+
+        record_sale(lottery, bundle.ticket_count, received_amount);
+
+        if let (Some(referrer), Some(amount)) = (
+            quick_pick.referrer,
+            accrue_referral(
+                lottery.key(),
+                lottery,
+                ctx.accounts.referral.as_deref_mut(),
+                ctx.bumps.referral,
+                quick_pick.referrer,
+                &owner,
+                received_amount,
+            )?,
+        ) {
+            emit_cpi!(ReferralAccrued {
+                lottery: lottery.key(),
+                bundle: bundle.key(),
+                referrer,
+                amount,
+                slot: clock.slot,
+            });
+        }
+
+        msg!("TRANSACTION_BUNDLE_CREATED: {}", bundle.key());
+
+        emit_cpi!(BundleCreated {
+            lottery: lottery.key(),
+            bundle: bundle.key(),
+            owner,
+            tx_sig_hash: quick_pick.tx_sig_hash,
+            ticket_count: bundle.ticket_count,
+            first_ticket_index: bundle.first_ticket_index,
+            amount: received_amount,
+            tickets_sold: lottery.tickets_sold,
+            slot: clock.slot,
+        });
+
+        emit_cpi!(QuickPickFulfilled {
+            lottery: lottery.key(),
+            quick_pick: quick_pick.key(),
+            bundle: bundle.key(),
+            owner,
+            random_value,
+            slot: clock.slot,
+        });
+
+        Ok(())
+    }
+
+    /* -------------------------------------------------
+       CANCEL QUICK PICK (signed by the owner)
+       - e.g. once a commit-reveal slot hash has expired
+    --------------------------------------------------*/
+    pub fn cancel_quick_pick(ctx: Context<CancelQuickPick>) -> Result<()> {
+        let quick_pick = &ctx.accounts.quick_pick;
+
+        msg!("QUICK_PICK_CANCELLED: {}", quick_pick.key());
+
+        emit_cpi!(QuickPickCancelled {
+            lottery: quick_pick.lottery,
+            quick_pick: quick_pick.key(),
+            owner: quick_pick.owner,
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+
+    /* -------------------------------------------------
+       DEPOSIT PLAYER BALANCE (SOL or tokens)
+    --------------------------------------------------*/
+    pub fn deposit_balance(ctx: Context<DepositBalance>, amount: u64) -> Result<()> {
+        let player_account = &mut ctx.accounts.player_account;
+
+        if player_account.owner == Pubkey::default() {
+            player_account.owner = ctx.accounts.owner.key();
+            player_account.bump = ctx.bumps.player_account;
+
+            // Token balances are held in a token account of the player account PDA
+            match (&ctx.accounts.payment_mint, &ctx.accounts.balance_vault) {
+                (Some(mint), Some(vault)) => {
+                    player_account.payment_mint = mint.key();
+                    player_account.balance_vault = vault.key();
+                }
+                (None, None) => {
+                    player_account.payment_mint = Pubkey::default();
+                    player_account.balance_vault = Pubkey::default();
+                }
+                _ => return err!(LottoError::TokenAccountsMissing),
+            }
+        }
+
+        let token = balance_transfer(
+            player_account,
+            &ctx.accounts.payment_mint,
+            &ctx.accounts.balance_vault,
+            &ctx.accounts.owner_token_account,
+            &ctx.accounts.token_program,
+            &ctx.accounts.owner.key(),
+        )?;
+
+        let received_amount = pay_into_balance(
+            player_account,
+            &ctx.accounts.owner,
+            &ctx.accounts.system_program,
+            token.as_ref(),
+            amount,
+        )?;
+
+        msg!("BALANCE_DEPOSITED: {}", player_account.key());
+
+        emit_cpi!(BalanceDeposited {
+            player_account: player_account.key(),
+            owner: player_account.owner,
+            amount: received_amount,
+            balance: player_account.balance,
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+
+    /* -------------------------------------------------
+       WITHDRAW PLAYER BALANCE
+    --------------------------------------------------*/
+    pub fn withdraw_balance(ctx: Context<WithdrawBalance>, amount: u64) -> Result<()> {
+        let token = balance_transfer(
+            &ctx.accounts.player_account,
+            &ctx.accounts.payment_mint,
+            &ctx.accounts.balance_vault,
+            &ctx.accounts.owner_token_account,
+            &ctx.accounts.token_program,
+            &ctx.accounts.owner.key(),
+        )?;
+
+        let player_account = &mut ctx.accounts.player_account;
+
+        pay_from_balance(
+            player_account,
+            &ctx.accounts.owner.to_account_info(),
+            token.as_ref(),
+            amount,
+        )?;
+
+        msg!("BALANCE_WITHDRAWN: {}", player_account.key());
+
+        emit_cpi!(BalanceWithdrawn {
+            player_account: player_account.key(),
+            owner: player_account.owner,
+            amount,
+            balance: player_account.balance,
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+
+    /* -------------------------------------------------
+       DRAW WINNING CODE
+    --------------------------------------------------*/
+    pub fn draw_winner(ctx: Context<DrawWinner>, winning_bytes: [u8; 8]) -> Result<()> {
+        require_eq!(
+            ctx.accounts.admin.key(),
+            ctx.accounts.configuration.admin,
+            LottoError::AdminOnlyAction
+        );
+
+        let lottery = &mut ctx.accounts.lottery;
+
+        require!(!lottery.open, LottoError::LotteryStillOpen);
+        require!(!lottery.cancelled, LottoError::LotteryCancelled);
+        require!(lottery.refunds_settled == 0, LottoError::DuplicateRequest);
+        require!(!lottery.is_commit_reveal(), LottoError::CommitRevealDraw);
+        require!(
+            lottery.game_type != GameType::Raffle,
+            LottoError::RaffleRequiresCommitReveal
+        );
+
+        let draw_index = lottery
+            .next_draw_index()
+            .ok_or(LottoError::AllDrawsCompleted)?;
+
+        // Verify correct Switchboard feed used
+        require_keys_eq!(
+            ctx.accounts.switchboard_feed_btc_block_decimal.key(),
+            lottery.switchboard_feed_btc_block_decimal,
+            LottoError::SwitchboardFeedMismatch
+        );
+
+        // read switchboard value
+        let (sb_winning_bytes, feed_slot) =
+            read_feed_value(&ctx.accounts.switchboard_feed_btc_block_decimal)
+                .map_err(|_| LottoError::FeedParseError)?;
+
+        msg!("winning_bytes = {:?}", winning_bytes);
+        msg!("sb_winning_bytes = {:?}", sb_winning_bytes);
+
+        // client value must equal switchboard feed value
+        require!(
+            winning_bytes == sb_winning_bytes,
+            LottoError::FeedValueMismatch
+        );
+
+        // Each draw must come from a fresh oracle value
+        require!(
+            lottery.draws[..draw_index]
+                .iter()
+                .all(|d| d.winning_code != winning_bytes),
+            LottoError::DrawValueRepeated
+        );
+
+        // The feed moves on: the slot pins the exact result the draw used
+        record_draw(lottery, draw_index, winning_bytes, feed_slot)?;
+
+        msg!(
+            "LOTTERY_WINNER_DRAWN: {} (draw {})",
+            lottery.key(),
+            draw_index
+        );
+
+        emit_cpi!(WinnerDrawn {
+            lottery: lottery.key(),
+            draw_index: draw_index as u8,
+            winning_code: winning_bytes,
+            feed_slot,
+            winning_ticket_index: lottery.draws[draw_index].winning_ticket_index,
+            prize_pool: lottery.prize_pool,
+            tickets_sold: lottery.tickets_sold,
+            slot: Clock::get()?.slot,
+        });
+
         Ok(())
     }
 
     /* -------------------------------------------------
-       CREATE LOTTERY
+       REVEAL A COMMIT-REVEAL DRAW
+       - the secret committed at creation, mixed with the
+         hash of the slot sales closed in, unknown until then
+       - every draw is revealed at once: the secret is public
+         afterwards, so later draws could not stay secret
     --------------------------------------------------*/
-    pub fn create_lottery_pda(
-        ctx: Context<CreateLotteryPDA>,
-        _lottery_seed: String,
-        lamports_per_ticket: u64,
-        start_hex: String,
-        end_hex: String,
-        fee_percent: u16,
-    ) -> Result<()> {
+    pub fn reveal_draw(ctx: Context<RevealDraw>, secret: [u8; 32]) -> Result<()> {
         require_eq!(
             ctx.accounts.admin.key(),
             ctx.accounts.configuration.admin,
             LottoError::AdminOnlyAction
         );
 
-        ctx.accounts.lottery.switchboard_feed_btc_block_decimal =
-            ctx.accounts.switchboard_feed_btc_block_decimal.key();
+        let clock = Clock::get()?;
+        let lottery = &mut ctx.accounts.lottery;
+
+        require!(!lottery.open, LottoError::LotteryStillOpen);
+        require!(!lottery.cancelled, LottoError::LotteryCancelled);
+        require!(lottery.refunds_settled == 0, LottoError::DuplicateRequest);
+        require!(lottery.is_commit_reveal(), LottoError::NotCommitRevealDraw);
+        require!(!lottery.has_drawn(), LottoError::AllDrawsCompleted);
+
+        require!(
+            hash(&secret).to_bytes() == lottery.draw_commitment,
+            LottoError::InvalidSecret
+        );
+        require!(clock.slot > lottery.draw_slot, LottoError::DrawSlotPending);
+
+        // Past the SlotHashes window the lottery can only be cancelled (see cancel_expired_draw)
+        let slot_hash = slot_hash(&ctx.accounts.slot_hashes, lottery.draw_slot)?
+            .ok_or(LottoError::DrawSlotExpired)?;
+
+        let random_value = mix_slot_hash(&secret, &slot_hash);
+
+        let code_range = commit_reveal_code_range(lottery)?;
+
+        msg!("DRAW_REVEALED: {}", lottery.key());
+
+        emit_cpi!(DrawRevealed {
+            lottery: lottery.key(),
+            secret,
+            draw_slot: lottery.draw_slot,
+            slot_hash,
+            slot: clock.slot,
+        });
+
+        for draw_index in 0..lottery.draws.len() {
+            let drawn_codes: Vec<[u8; 8]> = lottery.draws[..draw_index]
+                .iter()
+                .map(|d| d.winning_code)
+                .collect();
+
+            let winning_code = commit_reveal_winning_code(
+                &random_value,
+                draw_index as u8,
+                code_range,
+                &drawn_codes,
+            );
+
+            record_draw(lottery, draw_index, winning_code, 0)?;
+
+            msg!(
+                "LOTTERY_WINNER_DRAWN: {} (draw {})",
+                lottery.key(),
+                draw_index
+            );
+
+            emit_cpi!(WinnerDrawn {
+                lottery: lottery.key(),
+                draw_index: draw_index as u8,
+                winning_code,
+                feed_slot: 0,
+                winning_ticket_index: lottery.draws[draw_index].winning_ticket_index,
+                prize_pool: lottery.prize_pool,
+                tickets_sold: lottery.tickets_sold,
+                slot: clock.slot,
+            });
+        }
+
+        Ok(())
+    }
 
-        ctx.accounts.lottery.tickets_sold = 0;
-        ctx.accounts.lottery.refunds_settled = 0;
-        ctx.accounts.lottery.winner_settled = false;
+    /* -------------------------------------------------
+       CANCEL AN EXPIRED COMMIT-REVEAL DRAW (permissionless)
+       - once the draw slot hash has left SlotHashes the draw
+         can no longer be revealed; players are refunded in full
+         instead of the admin getting a fresh slot to reroll
+    --------------------------------------------------*/
+    pub fn cancel_expired_draw(ctx: Context<CancelExpiredDraw>) -> Result<()> {
+        let clock = Clock::get()?;
+        let lottery = &mut ctx.accounts.lottery;
 
-        ctx.accounts.lottery.open = true;
+        require!(!lottery.open, LottoError::LotteryStillOpen);
+        require!(!lottery.cancelled, LottoError::LotteryCancelled);
+        require!(lottery.is_commit_reveal(), LottoError::NotCommitRevealDraw);
+        require!(!lottery.has_drawn(), LottoError::AllDrawsCompleted);
 
-        ctx.accounts.lottery.lamports_per_ticket = lamports_per_ticket;
-        ctx.accounts.lottery.ticket_code_start_hex = start_hex;
-        ctx.accounts.lottery.ticket_code_end_hex = end_hex;
+        require!(
+            draw_slot_expired(&ctx.accounts.slot_hashes, lottery.draw_slot, clock.slot)?,
+            LottoError::DrawSlotNotExpired
+        );
 
-        ctx.accounts.lottery.platform_fee_percentage = fee_percent;
+        lottery.cancelled = true;
 
-        msg!("LOTTERY_PDA_CREATED: {}", ctx.accounts.lottery.key());
+        msg!("LOTTERY_CANCELLED: {}", lottery.key());
+
+        emit_cpi!(DrawExpired {
+            lottery: lottery.key(),
+            draw_slot: lottery.draw_slot,
+            slot: clock.slot,
+        });
 
         Ok(())
     }
 
     /* -------------------------------------------------
-       CREATE TRANSACTION BUNDLE (with purchased numbers)
+       TALLY WINNING TICKETS IN A BUNDLE
     --------------------------------------------------*/
-    pub fn create_transaction_bundle(
-        ctx: Context<CreateTransactionBundle>,
-        _lottery_seed: String,
-        _tx_sig_hash: [u8; 32],
-        owner: Pubkey,
-        purchased_numbers: Vec<[u8; 8]>,
-    ) -> Result<()> {
+    pub fn tally_transaction_bundle(ctx: Context<TallyTransactionBundle>) -> Result<()> {
         require_eq!(
             ctx.accounts.admin.key(),
             ctx.accounts.configuration.admin,
             LottoError::AdminOnlyAction
         );
 
-        let bundle = &mut ctx.accounts.bundle;
         let lottery = &mut ctx.accounts.lottery;
+        let bundle = &mut ctx.accounts.bundle;
+
+        require_keys_eq!(bundle.lottery_pda, lottery.key(), LottoError::KeyMismatch);
+
+        // Bundles are tallied against draws in order
+        let draw_index = bundle.draws_tallied as usize;
+
+        require!(
+            draw_index < lottery.draws.len(),
+            LottoError::DuplicateRequest
+        );
+        require!(lottery.draws[draw_index].drawn, LottoError::WinnerNotDrawn);
+
+        let matches = match lottery.game_type {
+            GameType::CodeMatch => tally_bundle(
+                bundle,
+                &lottery.draws[draw_index].winning_code,
+                &lottery.prize_tiers,
+            ),
+            GameType::Raffle => {
+                tally_raffle_bundle(bundle, lottery.draws[draw_index].winning_ticket_index)
+            }
+        };
+
+        let draw = &mut lottery.draws[draw_index];
+
+        for (i, count) in matches.iter().enumerate() {
+            draw.tier_winning_tickets[i] += *count as u32;
+        }
 
-        require!(purchased_numbers.len() <= 100, LottoError::BundleFull);
+        draw.bundles_tallied += 1;
 
-        // store owner + lottery link
-        bundle.owner = owner;
-        bundle.lottery_pda = lottery.key();
+        let tally_complete = draw.bundles_tallied == lottery.bundle_count;
+        let last_draw = draw_index + 1 == lottery.draws.len();
 
-        // Store raw bytes
-        for (i, ticket_bytes) in purchased_numbers.iter().enumerate() {
-            bundle.tickets[i] = *ticket_bytes;
+        bundle.tier_winning_tickets[draw_index] = matches;
+        bundle.draws_tallied += 1;
+
+        // Every winner is known once the last draw is fully tallied: the claim
+        // window opens then, once, so it covers every draw's prizes
+        if tally_complete && last_draw && lottery.claim_window > 0 {
+            lottery.claim_deadline = Clock::get()?.unix_timestamp + lottery.claim_window;
+
+            emit_cpi!(ClaimWindowOpened {
+                lottery: lottery.key(),
+                draw_index: draw_index as u8,
+                claim_deadline: lottery.claim_deadline,
+                slot: Clock::get()?.slot,
+            });
         }
 
-        // update total sold tickets
-        lottery.tickets_sold += purchased_numbers.len() as u32;
+        msg!("TRANSACTION_BUNDLE_TALLIED: {}", bundle.key());
 
-        msg!("TRANSACTION_BUNDLE_CREATED: {}", ctx.accounts.bundle.key());
+        emit_cpi!(BundleTallied {
+            lottery: lottery.key(),
+            bundle: bundle.key(),
+            draw_index: draw_index as u8,
+            tier_winning_tickets: matches,
+            slot: Clock::get()?.slot,
+        });
 
         Ok(())
     }
@@ -119,7 +1413,7 @@ pub mod ct_lotto_anchor {
     --------------------------------------------------*/
     pub fn reward_transaction_bundle(
         ctx: Context<RewardTransactionBundle>,
-        winning_bytes: [u8; 8],
+        draw_index: u8,
     ) -> Result<()> {
         require_eq!(
             ctx.accounts.admin.key(),
@@ -128,59 +1422,187 @@ pub mod ct_lotto_anchor {
         );
 
         let lottery = &mut ctx.accounts.lottery;
-        let bundle = &ctx.accounts.bundle;
+        let bundle = &mut ctx.accounts.bundle;
+        let draw_index = draw_index as usize;
 
-        require!(!lottery.winner_settled, LottoError::DuplicateRequest);
-        require!(lottery.refunds_settled == 0, LottoError::DuplicateRequest);
+        let payout_amount =
+            bundle_payout_due(lottery, bundle, draw_index, Clock::get()?.unix_timestamp)?;
 
-        // Verify correct Switchboard feed used
+        // Verify the payout goes to the bundle owner
         require_keys_eq!(
-            ctx.accounts.switchboard_feed_btc_block_decimal.key(),
-            lottery.switchboard_feed_btc_block_decimal,
-            LottoError::SwitchboardFeedMismatch
+            bundle.owner,
+            ctx.accounts.owner.key(),
+            LottoError::WinnerMismatch
         );
 
-        // read switchboard value
-        let sb_winning_bytes = read_feed_value(&ctx.accounts.switchboard_feed_btc_block_decimal)
-            .map_err(|_| LottoError::FeedParseError)?;
+        // Prizes and refunds credited to a balance go to its token account
+        let recipient = ctx
+            .accounts
+            .player_account
+            .as_ref()
+            .map_or(bundle.owner, |player_account| player_account.key());
+
+        let token = vault_transfer(
+            lottery,
+            &ctx.accounts.payment_mint,
+            &ctx.accounts.vault,
+            &ctx.accounts.owner_token_account,
+            &ctx.accounts.token_program,
+            &recipient,
+        )?;
 
-        msg!("winning_bytes = {:?}", winning_bytes);
-        msg!("sb_winning_bytes = {:?}", sb_winning_bytes);
+        // Transfer to winner
+        pay_to_owner(
+            lottery,
+            &ctx.accounts.owner.to_account_info(),
+            ctx.accounts.player_account.as_mut(),
+            token.as_ref(),
+            payout_amount,
+        )?;
 
-        // client value must equal switchboard feed value
-        require!(
-            winning_bytes == sb_winning_bytes,
-            LottoError::FeedValueMismatch
-        );
+        record_bundle_payout(lottery, bundle, draw_index);
+
+        msg!("TRANSACTION_BUNDLE_REWARDED: {}", bundle.key());
+
+        emit_cpi!(PrizePaid {
+            lottery: lottery.key(),
+            bundle: bundle.key(),
+            owner: bundle.owner,
+            draw_index: draw_index as u8,
+            amount: payout_amount,
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+
+    /* -------------------------------------------------
+       CLAIM PRIZE (signed by the bundle owner)
+    --------------------------------------------------*/
+    pub fn claim_prize(ctx: Context<ClaimPrize>, draw_index: u8) -> Result<()> {
+        let lottery = &mut ctx.accounts.lottery;
+        let bundle = &mut ctx.accounts.bundle;
+        let draw_index = draw_index as usize;
+
+        let payout_amount =
+            bundle_payout_due(lottery, bundle, draw_index, Clock::get()?.unix_timestamp)?;
 
-        // Verify this bundle belongs to this lottery and owner
-        require_keys_eq!(bundle.lottery_pda, lottery.key(), LottoError::KeyMismatch);
         require_keys_eq!(
             bundle.owner,
             ctx.accounts.owner.key(),
             LottoError::WinnerMismatch
         );
 
-        // Check if the winning ticket exists inside the bundle
-        let found = bundle.tickets.iter().any(|t| *t == winning_bytes);
-
-        require!(found, LottoError::TicketNotInBundle);
-
-        // 4. Calculate payout
-        let total_lamports = lottery.lamports_per_ticket * (lottery.tickets_sold as u64);
-        let platform_fee = total_lamports * (lottery.platform_fee_percentage as u64) / 100;
-        let payout_amount = total_lamports - platform_fee;
+        // Prizes and refunds credited to a balance go to its token account
+        let recipient = ctx
+            .accounts
+            .player_account
+            .as_ref()
+            .map_or(bundle.owner, |player_account| player_account.key());
+
+        let token = vault_transfer(
+            lottery,
+            &ctx.accounts.payment_mint,
+            &ctx.accounts.vault,
+            &ctx.accounts.owner_token_account,
+            &ctx.accounts.token_program,
+            &recipient,
+        )?;
 
-        // Transfer to winner
-        transfer_lamports(
-            &lottery.to_account_info(),
+        pay_to_owner(
+            lottery,
             &ctx.accounts.owner.to_account_info(),
+            ctx.accounts.player_account.as_mut(),
+            token.as_ref(),
             payout_amount,
         )?;
 
-        lottery.winner_settled = true;
+        record_bundle_payout(lottery, bundle, draw_index);
+
+        msg!("PRIZE_CLAIMED: {}", bundle.key());
+
+        emit_cpi!(PrizePaid {
+            lottery: lottery.key(),
+            bundle: bundle.key(),
+            owner: bundle.owner,
+            draw_index: draw_index as u8,
+            amount: payout_amount,
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+
+    /* -------------------------------------------------
+       SWEEP UNCLAIMED PRIZES (permissionless, after the claim deadline)
+       - series rounds roll them into a later round of the series
+       - standalone lotteries send them to the treasury (configured admin)
+    --------------------------------------------------*/
+    pub fn sweep_unclaimed_prizes(ctx: Context<SweepUnclaimedPrizes>) -> Result<()> {
+        let lottery = &mut ctx.accounts.lottery;
+
+        require!(!lottery.prizes_swept, LottoError::DuplicateRequest);
+        require!(
+            lottery.has_drawn()
+                && draws_complete(lottery)
+                && claim_deadline_passed(lottery, Clock::get()?.unix_timestamp),
+            LottoError::ClaimWindowOpen
+        );
+
+        let amount = unpaid_prizes(lottery);
+
+        let destination = if lottery.is_series_round() {
+            let rollover = ctx
+                .accounts
+                .rollover
+                .as_mut()
+                .ok_or(LottoError::InvalidRollover)?;
+
+            // Only a later round still building its pool can take the prizes in
+            require!(
+                rollover.series == lottery.series
+                    && rollover.round > lottery.round
+                    && !rollover.has_drawn()
+                    && !rollover.cancelled,
+                LottoError::InvalidRollover
+            );
+
+            pay_from_lottery(lottery, &rollover.to_account_info(), None, amount)?;
+
+            rollover.seeded_prize += amount;
+            rollover.pot_lamports += amount;
+
+            rollover.key()
+        } else {
+            let token = vault_transfer(
+                lottery,
+                &ctx.accounts.payment_mint,
+                &ctx.accounts.vault,
+                &ctx.accounts.treasury_token_account,
+                &ctx.accounts.token_program,
+                &ctx.accounts.configuration.admin,
+            )?;
+
+            pay_from_lottery(
+                lottery,
+                &ctx.accounts.treasury.to_account_info(),
+                token.as_ref(),
+                amount,
+            )?;
+
+            ctx.accounts.treasury.key()
+        };
+
+        lottery.prizes_swept = true;
+
+        msg!("UNCLAIMED_PRIZES_SWEPT: {}", lottery.key());
 
-        msg!("TRANSACTION_BUNDLE_REWARDED: {}", ctx.accounts.bundle.key());
+        emit_cpi!(UnclaimedPrizesSwept {
+            lottery: lottery.key(),
+            destination,
+            amount,
+            slot: Clock::get()?.slot,
+        });
 
         Ok(())
     }
@@ -205,24 +1627,43 @@ pub mod ct_lotto_anchor {
 
         require!(!bundle.refunded, LottoError::DuplicateRequest);
 
-        require!(!lottery.winner_settled, LottoError::DuplicateRequest);
+        require!(!lottery.has_drawn(), LottoError::DuplicateRequest);
 
         require_keys_eq!(bundle.lottery_pda, lottery.key(), LottoError::KeyMismatch);
 
-        let total_lamports = lottery.lamports_per_ticket * (lottery.tickets_sold as u64);
+        // Players of a cancelled lottery are always refunded in full
+        let deduct_fee = deduct_fee && !lottery.cancelled;
 
-        let platform_fee = if deduct_fee {
-            total_lamports * (lottery.platform_fee_percentage as u64) / 100
-        } else {
-            0
-        };
+        let refund_amount = bundle_refund(lottery, bundle, deduct_fee);
+
+        // A balance purchase is refunded to the balance it was paid from
+        require!(
+            !bundle.paid_from_balance || ctx.accounts.player_account.is_some(),
+            LottoError::PlayerAccountRequired
+        );
 
-        let refund_per_user = (total_lamports - platform_fee) / (lottery.tickets_sold as u64);
+        // Prizes and refunds credited to a balance go to its token account
+        let recipient = ctx
+            .accounts
+            .player_account
+            .as_ref()
+            .map_or(bundle.owner, |player_account| player_account.key());
+
+        let token = vault_transfer(
+            lottery,
+            &ctx.accounts.payment_mint,
+            &ctx.accounts.vault,
+            &ctx.accounts.owner_token_account,
+            &ctx.accounts.token_program,
+            &recipient,
+        )?;
 
-        transfer_lamports(
-            &lottery.to_account_info(),
+        pay_to_owner(
+            lottery,
             &ctx.accounts.owner.to_account_info(),
-            refund_per_user,
+            ctx.accounts.player_account.as_mut(),
+            token.as_ref(),
+            refund_amount,
         )?;
 
         lottery.refunds_settled += 1;
@@ -231,6 +1672,212 @@ pub mod ct_lotto_anchor {
 
         msg!("TRANSACTION_BUNDLE_REFUNDED: {}", bundle.key());
 
+        emit_cpi!(BundleRefunded {
+            lottery: lottery.key(),
+            bundle: bundle.key(),
+            owner: bundle.owner,
+            amount: refund_amount,
+            fee_deducted: deduct_fee,
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+
+    /* -------------------------------------------------
+       WITHDRAW PLATFORM FEE
+    --------------------------------------------------*/
+    pub fn withdraw_platform_fees(ctx: Context<WithdrawPlatformFees>) -> Result<()> {
+        require_eq!(
+            ctx.accounts.admin.key(),
+            ctx.accounts.configuration.admin,
+            LottoError::AdminOnlyAction
+        );
+
+        let lottery = &mut ctx.accounts.lottery;
+
+        // The fee is only final once the prize pool has been fixed by a draw
+        require!(lottery.has_drawn(), LottoError::WinnerNotDrawn);
+        require!(
+            !lottery.platform_fee_withdrawn,
+            LottoError::DuplicateRequest
+        );
+
+        let token = vault_transfer(
+            lottery,
+            &ctx.accounts.payment_mint,
+            &ctx.accounts.vault,
+            &ctx.accounts.admin_token_account,
+            &ctx.accounts.token_program,
+            &ctx.accounts.admin.key(),
+        )?;
+
+        // Referral earnings are carved out of the fee and claimed by the referrers
+        let platform_fee = lottery.platform_fee() - lottery.referral_fees_accrued;
+
+        pay_from_lottery(
+            lottery,
+            &ctx.accounts.admin.to_account_info(),
+            token.as_ref(),
+            platform_fee,
+        )?;
+
+        lottery.platform_fee_withdrawn = true;
+
+        msg!("PLATFORM_FEE_WITHDRAWN: {}", lottery.key());
+
+        emit_cpi!(FeesWithdrawn {
+            lottery: lottery.key(),
+            admin: ctx.accounts.admin.key(),
+            amount: platform_fee,
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+
+    /* -------------------------------------------------
+       CLAIM REFERRAL REWARDS (signed by the referrer)
+    --------------------------------------------------*/
+    pub fn claim_referral_rewards(ctx: Context<ClaimReferralRewards>) -> Result<()> {
+        let lottery = &mut ctx.accounts.lottery;
+        let referral = &mut ctx.accounts.referral;
+
+        require_keys_eq!(referral.lottery, lottery.key(), LottoError::KeyMismatch);
+
+        // Like the platform fee, referral earnings are only final once the lottery is drawn
+        require!(lottery.has_drawn(), LottoError::WinnerNotDrawn);
+
+        let amount = referral.accrued - referral.claimed;
+
+        require!(amount > 0, LottoError::NothingToClaim);
+
+        let token = vault_transfer(
+            lottery,
+            &ctx.accounts.payment_mint,
+            &ctx.accounts.vault,
+            &ctx.accounts.referrer_token_account,
+            &ctx.accounts.token_program,
+            &ctx.accounts.referrer.key(),
+        )?;
+
+        pay_from_lottery(
+            lottery,
+            &ctx.accounts.referrer.to_account_info(),
+            token.as_ref(),
+            amount,
+        )?;
+
+        referral.claimed += amount;
+        lottery.referral_fees_claimed += amount;
+
+        msg!("REFERRAL_REWARDS_CLAIMED: {}", referral.key());
+
+        emit_cpi!(ReferralClaimed {
+            lottery: lottery.key(),
+            referrer: referral.referrer,
+            amount,
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+
+    /* -------------------------------------------------
+       SEED GUARANTEED PRIZE
+    --------------------------------------------------*/
+    pub fn seed_prize(ctx: Context<SeedPrize>, amount: u64) -> Result<()> {
+        require_eq!(
+            ctx.accounts.admin.key(),
+            ctx.accounts.configuration.admin,
+            LottoError::AdminOnlyAction
+        );
+
+        let lottery = &mut ctx.accounts.lottery;
+
+        // The pool is fixed at the first draw
+        require!(
+            !lottery.has_drawn() && !lottery.cancelled,
+            LottoError::SeedingClosed
+        );
+
+        let token = vault_transfer(
+            lottery,
+            &ctx.accounts.payment_mint,
+            &ctx.accounts.vault,
+            &ctx.accounts.admin_token_account,
+            &ctx.accounts.token_program,
+            &ctx.accounts.admin.key(),
+        )?;
+
+        let received = pay_into_lottery(
+            lottery,
+            &ctx.accounts.admin,
+            &ctx.accounts.system_program,
+            token.as_ref(),
+            amount,
+        )?;
+
+        lottery.seeded_prize += received;
+        lottery.pot_lamports += received;
+
+        msg!("LOTTERY_PRIZE_SEEDED: {}", lottery.key());
+
+        emit_cpi!(PrizeSeeded {
+            lottery: lottery.key(),
+            admin: ctx.accounts.admin.key(),
+            amount: received,
+            seeded_prize: lottery.seeded_prize,
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+
+    /* -------------------------------------------------
+       RECLAIM SEEDED PRIZE (cancelled lotteries)
+    --------------------------------------------------*/
+    pub fn reclaim_seeded_prize(ctx: Context<ReclaimSeededPrize>) -> Result<()> {
+        require_eq!(
+            ctx.accounts.admin.key(),
+            ctx.accounts.configuration.admin,
+            LottoError::AdminOnlyAction
+        );
+
+        let lottery = &mut ctx.accounts.lottery;
+
+        require!(lottery.cancelled, LottoError::LotteryNotCancelled);
+        require!(lottery.seeded_prize > 0, LottoError::DuplicateRequest);
+
+        let token = vault_transfer(
+            lottery,
+            &ctx.accounts.payment_mint,
+            &ctx.accounts.vault,
+            &ctx.accounts.admin_token_account,
+            &ctx.accounts.token_program,
+            &ctx.accounts.admin.key(),
+        )?;
+
+        let amount = lottery.seeded_prize;
+
+        pay_from_lottery(
+            lottery,
+            &ctx.accounts.admin.to_account_info(),
+            token.as_ref(),
+            amount,
+        )?;
+
+        lottery.seeded_prize = 0;
+
+        msg!("LOTTERY_SEEDED_PRIZE_RECLAIMED: {}", lottery.key());
+
+        emit_cpi!(SeededPrizeReclaimed {
+            lottery: lottery.key(),
+            admin: ctx.accounts.admin.key(),
+            amount,
+            slot: Clock::get()?.slot,
+        });
+
         Ok(())
     }
 
@@ -244,9 +1891,28 @@ pub mod ct_lotto_anchor {
             LottoError::AdminOnlyAction
         );
 
-        ctx.accounts.lottery.open = false;
+        let lottery = &mut ctx.accounts.lottery;
+
+        // Closing again would move the draw slot, rerolling a commit-reveal draw
+        require!(lottery.open, LottoError::LotteryClosed);
+
+        // Below the participation threshold the lottery becomes refund-only
+        close_sales(lottery, Clock::get()?.slot);
 
-        msg!("LOTTERY_CLOSED: {}", ctx.accounts.lottery.key());
+        if lottery.cancelled {
+            msg!("LOTTERY_CANCELLED: {}", lottery.key());
+        }
+
+        msg!("LOTTERY_CLOSED: {}", lottery.key());
+
+        emit_cpi!(LotteryClosed {
+            lottery: lottery.key(),
+            tickets_sold: lottery.tickets_sold,
+            ticket_revenue: lottery.ticket_revenue,
+            account_closed: false,
+            cancelled: lottery.cancelled,
+            slot: Clock::get()?.slot,
+        });
 
         Ok(())
     }
@@ -261,9 +1927,24 @@ pub mod ct_lotto_anchor {
             LottoError::AdminOnlyAction
         );
 
+        let lottery = &mut ctx.accounts.lottery;
+
+        // Tallies and payouts count every bundle, so only settled ones may go
+        require!(
+            bundle_settled(lottery, &ctx.accounts.bundle),
+            LottoError::PDACloseConditionNotMet
+        );
+
+        lottery.bundles_closed += 1;
+
         // Anchor automatically closes the bundle and returns rent
         msg!("TRANSACTION_BUNDLE_CLOSED: {}", ctx.accounts.bundle.key());
 
+        emit_cpi!(BundleClosed {
+            bundle: ctx.accounts.bundle.key(),
+            slot: Clock::get()?.slot,
+        });
+
         Ok(())
     }
 
@@ -278,8 +1959,70 @@ pub mod ct_lotto_anchor {
             LottoError::AdminOnlyAction
         );
 
+        // Its address lives in the lottery, so the registry must be closed first
+        require!(
+            !ctx.accounts.lottery.has_ticket_registry(),
+            LottoError::PDACloseConditionNotMet
+        );
+
+        // Unwon prizes of a series round belong to the later rounds
+        require!(
+            !ctx.accounts.lottery.is_series_round() || ctx.accounts.lottery.prizes_carried_over,
+            LottoError::PDACloseConditionNotMet
+        );
+
+        // Bundles are checked against their lottery when closed, so they go first
+        require_eq!(
+            ctx.accounts.lottery.bundles_closed,
+            ctx.accounts.lottery.bundle_count,
+            LottoError::PDACloseConditionNotMet
+        );
+
+        // Sweep whatever is left in the vault to the admin before closing it
+        let token = vault_transfer(
+            &ctx.accounts.lottery,
+            &ctx.accounts.payment_mint,
+            &ctx.accounts.vault,
+            &ctx.accounts.admin_token_account,
+            &ctx.accounts.token_program,
+            &ctx.accounts.admin.key(),
+        )?;
+
+        if let Some(token) = token {
+            if token.vault.amount > 0 {
+                transfer_from_vault(&ctx.accounts.lottery, &token, token.vault.amount)?;
+            }
+
+            close_vault(
+                &ctx.accounts.lottery,
+                &token,
+                &ctx.accounts.rent_payer.to_account_info(),
+            )?;
+        }
+
+        // Funds above rent go to the admin, the rent itself back to its payer on close
+        let lottery_info = ctx.accounts.lottery.to_account_info();
+        let leftover = withdrawable_lamports(&lottery_info)?;
+
+        if leftover > 0 {
+            transfer_lamports(
+                &lottery_info,
+                &ctx.accounts.admin.to_account_info(),
+                leftover,
+            )?;
+        }
+
         msg!("LOTTERY_PDA_CLOSED: {}", ctx.accounts.lottery.key());
 
+        emit_cpi!(LotteryClosed {
+            lottery: ctx.accounts.lottery.key(),
+            tickets_sold: ctx.accounts.lottery.tickets_sold,
+            ticket_revenue: ctx.accounts.lottery.ticket_revenue,
+            account_closed: true,
+            cancelled: ctx.accounts.lottery.cancelled,
+            slot: Clock::get()?.slot,
+        });
+
         Ok(())
     }
 
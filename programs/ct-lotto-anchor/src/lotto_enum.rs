@@ -8,12 +8,24 @@ pub enum LottoError {
     #[msg("Only admin can perform this action.")]
     AdminOnlyAction,
 
+    #[msg("Only the proposed admin can accept the admin role.")]
+    PendingAdminOnly,
+
     /* ------------------------------ */
     /*  LOTTERY ERRORS                */
     /* ------------------------------ */
     #[msg("Lottery is closed.")]
     LotteryClosed,
 
+    #[msg("Lottery was cancelled for lack of participation and can only be refunded.")]
+    LotteryCancelled,
+
+    #[msg("Prize can only be seeded before the first draw of a lottery that is not cancelled.")]
+    SeedingClosed,
+
+    #[msg("Seeded prize can only be reclaimed from a cancelled lottery.")]
+    LotteryNotCancelled,
+
     #[msg("Cannot close PDA because the required conditions are not met.")]
     PDACloseConditionNotMet,
 
@@ -26,27 +38,195 @@ pub enum LottoError {
     #[msg("The lottery has not tickets to issue refund.")]
     NoTicketSoldToRefund,
 
+    #[msg("Lottery must be closed for purchases before drawing.")]
+    LotteryStillOpen,
+
+    #[msg("Winning code has not been drawn yet.")]
+    WinnerNotDrawn,
+
+    #[msg("Every transaction bundle must be tallied before rewarding.")]
+    TallyIncomplete,
+
+    #[msg("The lottery has no tickets sold to draw from.")]
+    NoTicketsSold,
+
+    #[msg("Draw percentages must each be positive and add up to 100%.")]
+    InvalidDrawTable,
+
+    #[msg("Draw index does not exist for this lottery.")]
+    InvalidDrawIndex,
+
+    #[msg("Every draw of this lottery has already taken place.")]
+    AllDrawsCompleted,
+
+    #[msg("Drawn value must differ from the values of previous draws.")]
+    DrawValueRepeated,
+
     /* ------------------------------ */
     /*  TRANSACTION BUNDLE ERRORS     */
     /* ------------------------------ */
     #[msg("Transaction bundle already contains the maximum limit of 100 tickets.")]
     BundleFull,
 
-    #[msg("Hex exceeds maximum allowed length of 8 characters.")]
+    #[msg("Hex exceeds maximum allowed length of 16 characters.")]
     HexTooLong,
 
+    #[msg("Ticket code is not a valid hex string.")]
+    InvalidHex,
+
     #[msg("Ticket not found inside this transaction bundle.")]
     TicketNotInBundle,
 
     #[msg("The bundle owner does not match the provided owner account.")]
     WinnerMismatch,
 
+    #[msg("Ticket code appears more than once and this lottery does not allow shared tickets.")]
+    DuplicateTicketCode,
+
     /* ------------------------------ */
     /*  RANGE / VALIDATION ERRORS     */
     /* ------------------------------ */
     #[msg("Requested ticket code is outside the valid lottery range.")]
     TicketCodeOutOfRange,
 
+    #[msg("Ticket code range is invalid or smaller than the ticket cap.")]
+    InvalidTicketRange,
+
+    #[msg("Purchase exceeds the total number of tickets this lottery can sell.")]
+    LotterySoldOut,
+
+    #[msg("Purchase exceeds the number of tickets allowed per wallet.")]
+    WalletTicketCapExceeded,
+
+    #[msg("Prize tiers must be ordered by decreasing match digits and add up to 100%.")]
+    InvalidPrizeTable,
+
+    /* ------------------------------ */
+    /*  REFERRAL ERRORS               */
+    /* ------------------------------ */
+    #[msg("Referral share must be at most 10000 basis points.")]
+    InvalidReferralShare,
+
+    #[msg("Referrer and referral account must be provided together, and differ from the owner.")]
+    InvalidReferrer,
+
+    #[msg("No referral earnings left to claim.")]
+    NothingToClaim,
+
+    /* ------------------------------ */
+    /*  CLAIM ERRORS                  */
+    /* ------------------------------ */
+    #[msg("Claim window must not be negative.")]
+    InvalidClaimWindow,
+
+    #[msg("The claim deadline of this lottery has passed.")]
+    ClaimDeadlinePassed,
+
+    #[msg("Unpaid prizes can only be swept once every draw is tallied and the claim deadline has passed.")]
+    ClaimWindowOpen,
+
+    #[msg("Unpaid prizes of a series round must roll over into a later open round of the series.")]
+    InvalidRollover,
+
+    /* ------------------------------ */
+    /*  SERIES ERRORS                 */
+    /* ------------------------------ */
+    #[msg("Series seed must be between 1 and 20 bytes long.")]
+    InvalidSeriesSeed,
+
+    #[msg("Round duration must be positive.")]
+    InvalidRoundDuration,
+
+    #[msg("The current round is still selling tickets.")]
+    RoundStillOpen,
+
+    #[msg("Every draw of the round must be drawn and tallied before its prizes carry over.")]
+    RoundNotSettled,
+
+    #[msg("The prizes of this round have already carried over.")]
+    PrizesAlreadyCarriedOver,
+
+    /* ------------------------------ */
+    /*  PURCHASE INTENT ERRORS        */
+    /* ------------------------------ */
+    #[msg("Ed25519 signature verification of the purchase intent not found.")]
+    IntentSignatureNotFound,
+
+    #[msg("Purchase intent does not match this purchase.")]
+    IntentMismatch,
+
+    #[msg("Purchase intent has expired.")]
+    IntentExpired,
+
+    #[msg("Purchase intent nonce does not match the player account.")]
+    IntentNonceMismatch,
+
+    #[msg("Ticket price exceeds the intent's maximum price.")]
+    IntentPriceExceeded,
+
+    /* ------------------------------ */
+    /*  PLAYER BALANCE ERRORS         */
+    /* ------------------------------ */
+    #[msg("Player account balance is too low.")]
+    InsufficientBalance,
+
+    #[msg("Bundles bought from a player balance must be refunded to that balance.")]
+    PlayerAccountRequired,
+
+    /* ------------------------------ */
+    /*  QUICK PICK ERRORS             */
+    /* ------------------------------ */
+    #[msg("Quick pick must request between 1 and 100 tickets.")]
+    InvalidQuickPickCount,
+
+    #[msg("Lotteries with unique codes need a ticket registry for this operation.")]
+    TicketRegistryRequired,
+
+    #[msg("Ticket registry can only be created for unique codes, before any sale, within 65536 codes.")]
+    InvalidTicketRegistry,
+
+    #[msg("Lotteries with unique codes cover at most 65536 codes.")]
+    UniqueCodeRangeTooLarge,
+
+    #[msg("Randomness account does not match the quick pick request.")]
+    RandomnessMismatch,
+
+    #[msg("Randomness must be committed in the previous slot and not yet revealed.")]
+    RandomnessAlreadyRevealed,
+
+    #[msg("Randomness has not been revealed in this slot.")]
+    RandomnessNotResolved,
+
+    #[msg("The request slot hash is no longer available; cancel and request again.")]
+    RandomnessExpired,
+
+    #[msg("Revealed secret does not match the commitment.")]
+    InvalidSecret,
+
+    /* ------------------------------ */
+    /*  COMMIT-REVEAL DRAW ERRORS     */
+    /* ------------------------------ */
+    #[msg("Provide either a Switchboard feed or a draw commitment.")]
+    DrawSourceMismatch,
+
+    #[msg("Lottery is drawn by revealing its committed secret.")]
+    CommitRevealDraw,
+
+    #[msg("Lottery is drawn from the Switchboard feed.")]
+    NotCommitRevealDraw,
+
+    #[msg("The draw slot hash is not available yet; reveal in a later slot.")]
+    DrawSlotPending,
+
+    #[msg("The draw slot hash is no longer available; the lottery can only be cancelled.")]
+    DrawSlotExpired,
+
+    #[msg("The draw slot hash is still available for the reveal.")]
+    DrawSlotNotExpired,
+
+    #[msg("Raffles are drawn by commit-reveal: the feed value is known ahead of the draw.")]
+    RaffleRequiresCommitReveal,
+
     /* ------------------------------ */
     /*  SWITCHBOARD FEED ERRORS       */
     /* ------------------------------ */
@@ -67,4 +247,31 @@ pub enum LottoError {
     /* ------------------------------ */
     #[msg("Source account does not have enough lamports.")]
     InsufficientLamports,
+
+    #[msg("Payout exceeds the funds tracked in the lottery pot.")]
+    PotInsufficient,
+
+    #[msg("Transfer would leave the source account below rent exemption.")]
+    RentExemptionViolation,
+
+    /* ------------------------------ */
+    /*  TOKEN PAYMENT ERRORS          */
+    /* ------------------------------ */
+    #[msg("Token accounts are required for lotteries paid in an SPL token.")]
+    TokenAccountsMissing,
+
+    #[msg("Token account does not match the lottery mint, vault or owner.")]
+    TokenAccountMismatch,
+
+    #[msg("Unable to read the Token-2022 transfer fee configuration.")]
+    TransferFeeError,
+
+    /* ------------------------------ */
+    /*  PURCHASE PAYMENT ERRORS       */
+    /* ------------------------------ */
+    #[msg("The payment transfer must directly precede the bundle instruction.")]
+    PaymentNotFound,
+
+    #[msg("Payment transfer does not match the owner, lottery or ticket price.")]
+    PaymentMismatch,
 }
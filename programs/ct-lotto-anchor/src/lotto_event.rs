@@ -0,0 +1,286 @@
+use anchor_lang::prelude::*;
+
+use crate::account_struct::{GameType, MAX_PRIZE_TIERS};
+
+/* -------------------------------------------------
+   CONFIGURATION EVENTS
+--------------------------------------------------*/
+#[event]
+pub struct ConfigUpdated {
+    pub configuration: Pubkey,
+    pub admin: Pubkey,
+    pub pending_admin: Pubkey,
+    pub slot: u64,
+}
+
+/* -------------------------------------------------
+   LOTTERY EVENTS
+--------------------------------------------------*/
+#[event]
+pub struct LotteryCreated {
+    pub lottery: Pubkey,
+    pub lottery_seed: String,
+    pub payment_mint: Pubkey,
+    pub lamports_per_ticket: u64,
+    pub ticket_code_start_hex: String,
+    pub ticket_code_end_hex: String,
+    pub platform_fee_percentage: u16,
+    pub allow_shared_tickets: bool,
+    pub game_type: GameType,
+    pub draw_count: u8,
+    pub max_tickets_total: u32,
+    pub max_tickets_per_wallet: u32,
+    pub min_tickets_to_draw: u32,
+    pub referral_bps: u16,
+    pub slot: u64,
+}
+
+#[event]
+pub struct ClaimWindowOpened {
+    pub lottery: Pubkey,
+    pub draw_index: u8,
+    pub claim_deadline: i64,
+    pub slot: u64,
+}
+
+#[event]
+pub struct UnclaimedPrizesSwept {
+    pub lottery: Pubkey,
+    /// Later round the prizes rolled into, or the treasury.
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub slot: u64,
+}
+
+#[event]
+pub struct SeriesCreated {
+    pub series: Pubkey,
+    pub series_seed: String,
+    pub lottery: Pubkey,
+    pub round_duration: i64,
+    pub slot: u64,
+}
+
+#[event]
+pub struct SeriesRolled {
+    pub series: Pubkey,
+    pub round: u32,
+    pub lottery: Pubkey,
+    pub previous_lottery: Pubkey,
+    pub carried_over: u64,
+    pub slot: u64,
+}
+
+#[event]
+pub struct PrizesCarriedOver {
+    pub series: Pubkey,
+    pub lottery: Pubkey,
+    pub to_lottery: Pubkey,
+    pub amount: u64,
+    pub slot: u64,
+}
+
+#[event]
+pub struct LotteryClosed {
+    pub lottery: Pubkey,
+    pub tickets_sold: u32,
+    pub ticket_revenue: u64,
+    /// True when the lottery account itself was closed, false when only sales stopped.
+    pub account_closed: bool,
+    /// True when sales closed below the minimum participation.
+    pub cancelled: bool,
+    pub slot: u64,
+}
+
+#[event]
+pub struct WinnerDrawn {
+    pub lottery: Pubkey,
+    pub draw_index: u8,
+    pub winning_code: [u8; 8],
+    pub feed_slot: u64,
+    pub winning_ticket_index: u32,
+    pub prize_pool: u64,
+    pub tickets_sold: u32,
+    pub slot: u64,
+}
+
+#[event]
+pub struct DrawRevealed {
+    pub lottery: Pubkey,
+    pub secret: [u8; 32],
+    pub draw_slot: u64,
+    pub slot_hash: [u8; 32],
+    pub slot: u64,
+}
+
+#[event]
+pub struct DrawExpired {
+    pub lottery: Pubkey,
+    pub draw_slot: u64,
+    pub slot: u64,
+}
+
+#[event]
+pub struct PrizeSeeded {
+    pub lottery: Pubkey,
+    pub admin: Pubkey,
+    /// Amount received, net of Token-2022 transfer fees.
+    pub amount: u64,
+    pub seeded_prize: u64,
+    pub slot: u64,
+}
+
+#[event]
+pub struct SeededPrizeReclaimed {
+    pub lottery: Pubkey,
+    pub admin: Pubkey,
+    pub amount: u64,
+    pub slot: u64,
+}
+
+#[event]
+pub struct FeesWithdrawn {
+    pub lottery: Pubkey,
+    pub admin: Pubkey,
+    pub amount: u64,
+    pub slot: u64,
+}
+
+/* -------------------------------------------------
+   TRANSACTION BUNDLE EVENTS
+--------------------------------------------------*/
+#[event]
+pub struct BundleCreated {
+    pub lottery: Pubkey,
+    pub bundle: Pubkey,
+    pub owner: Pubkey,
+    pub tx_sig_hash: [u8; 32],
+    pub ticket_count: u16,
+    pub first_ticket_index: u32,
+    /// Amount credited to the pot, net of Token-2022 transfer fees.
+    pub amount: u64,
+    pub tickets_sold: u32,
+    pub slot: u64,
+}
+
+#[event]
+pub struct ReferralAccrued {
+    pub lottery: Pubkey,
+    pub bundle: Pubkey,
+    pub referrer: Pubkey,
+    pub amount: u64,
+    pub slot: u64,
+}
+
+#[event]
+pub struct ReferralClaimed {
+    pub lottery: Pubkey,
+    pub referrer: Pubkey,
+    pub amount: u64,
+    pub slot: u64,
+}
+
+#[event]
+pub struct IntentExecuted {
+    pub lottery: Pubkey,
+    pub bundle: Pubkey,
+    pub owner: Pubkey,
+    pub relayer: Pubkey,
+    pub nonce: u64,
+    pub amount: u64,
+    pub slot: u64,
+}
+
+#[event]
+pub struct BalanceDeposited {
+    pub player_account: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub balance: u64,
+    pub slot: u64,
+}
+
+#[event]
+pub struct BalanceWithdrawn {
+    pub player_account: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub balance: u64,
+    pub slot: u64,
+}
+
+#[event]
+pub struct QuickPickRequested {
+    pub lottery: Pubkey,
+    pub quick_pick: Pubkey,
+    pub owner: Pubkey,
+    pub ticket_count: u8,
+    pub slot: u64,
+}
+
+#[event]
+pub struct QuickPickFulfilled {
+    pub lottery: Pubkey,
+    pub quick_pick: Pubkey,
+    pub bundle: Pubkey,
+    pub owner: Pubkey,
+    pub random_value: [u8; 32],
+    pub slot: u64,
+}
+
+#[event]
+pub struct QuickPickCancelled {
+    pub lottery: Pubkey,
+    pub quick_pick: Pubkey,
+    pub owner: Pubkey,
+    pub slot: u64,
+}
+
+#[event]
+pub struct TicketRegistryCreated {
+    pub lottery: Pubkey,
+    pub registry: Pubkey,
+    pub slot: u64,
+}
+
+#[event]
+pub struct TicketRegistryClosed {
+    pub lottery: Pubkey,
+    pub registry: Pubkey,
+    pub slot: u64,
+}
+
+#[event]
+pub struct BundleTallied {
+    pub lottery: Pubkey,
+    pub bundle: Pubkey,
+    pub draw_index: u8,
+    pub tier_winning_tickets: [u16; MAX_PRIZE_TIERS],
+    pub slot: u64,
+}
+
+#[event]
+pub struct PrizePaid {
+    pub lottery: Pubkey,
+    pub bundle: Pubkey,
+    pub owner: Pubkey,
+    pub draw_index: u8,
+    pub amount: u64,
+    pub slot: u64,
+}
+
+#[event]
+pub struct BundleRefunded {
+    pub lottery: Pubkey,
+    pub bundle: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub fee_deducted: bool,
+    pub slot: u64,
+}
+
+#[event]
+pub struct BundleClosed {
+    pub bundle: Pubkey,
+    pub slot: u64,
+}
@@ -1,16 +1,58 @@
 use anchor_lang::prelude::*;
-use switchboard_on_demand::PullFeedAccountData;
+use anchor_lang::solana_program::sysvar::instructions::{
+    get_instruction_relative, load_current_index_checked, load_instruction_at_checked,
+};
+use anchor_spl::token_2022::spl_token_2022::{
+    extension::{transfer_fee::TransferFeeConfig, BaseStateWithExtensions, StateWithExtensions},
+    state::Mint as MintState,
+};
+use anchor_spl::token_interface::{
+    close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+    TransferChecked,
+};
+use solana_sdk_ids::ed25519_program;
+use solana_sha256_hasher::{hash, hashv};
+use switchboard_on_demand::{
+    PullFeedAccountData, RandomnessAccountData, ON_DEMAND_DEVNET_PID, ON_DEMAND_MAINNET_PID,
+};
 
+use crate::account_struct::{
+    Draw, GameType, Lottery, LotteryTemplate, PlayerAccount, PlayerTickets, PrizeTier,
+    ReferralRewards, TicketRegistry, TransactionBundle, MAX_DRAWS, MAX_PRIZE_TIERS,
+    MAX_REGISTRY_CODES, MAX_TICKET_CODE_HEX_LEN, TICKET_CODE_DIGITS,
+};
 use crate::lotto_enum::LottoError;
 
 /* -------------------------------------------------
    READ SWITCHBOARD FEED (BTC block height decimal)
 --------------------------------------------------*/
-pub fn read_feed_value(feed_account: &AccountInfo) -> Result<[u8; 8]> {
+/// Value of the feed (BTC block height, as a ticket code) and the slot it was produced in.
+pub fn read_feed_value(feed_account: &AccountInfo) -> Result<([u8; 8], u64)> {
     let data = feed_account.data.borrow();
 
     let feed = PullFeedAccountData::parse(data).map_err(|_| LottoError::FeedParseError)?;
 
+    feed_value(&feed)
+}
+
+/// Same as `read_feed_value` on raw account data, for off-chain verification.
+/// Copies the feed out of the buffer, so it is not meant for on-chain use.
+pub fn feed_value_from_data(data: &[u8]) -> Result<([u8; 8], u64)> {
+    let size = std::mem::size_of::<PullFeedAccountData>();
+
+    require!(
+        data.len() >= 8 + size
+            && data[..8]
+                == *<PullFeedAccountData as switchboard_on_demand::Discriminator>::DISCRIMINATOR,
+        LottoError::FeedParseError
+    );
+
+    let feed: PullFeedAccountData = bytemuck::pod_read_unaligned(&data[8..8 + size]);
+
+    feed_value(&feed)
+}
+
+pub fn feed_value(feed: &PullFeedAccountData) -> Result<([u8; 8], u64)> {
     // When feed is not updated
     require!(feed.last_update_timestamp != 0, LottoError::FeedNoValue);
 
@@ -28,15 +70,955 @@ pub fn read_feed_value(feed_account: &AccountInfo) -> Result<[u8; 8]> {
     // Convert u64 → 8-byte big-endian representation
     let bytes = value.to_be_bytes();
 
-    Ok(bytes) // exactly [u8; 8]
+    Ok((bytes, feed.result.slot)) // exactly [u8; 8]
+}
+
+/* -------------------------------------------------
+   TICKET CODE ENCODING
+   - codes are hex strings of up to 16 digits ("0x" optional)
+   - stored as the big-endian bytes of their value,
+     the same layout read_feed_value produces
+--------------------------------------------------*/
+pub fn ticket_code_from_hex(hex: &str) -> Result<[u8; 8]> {
+    let hex = hex.strip_prefix("0x").unwrap_or(hex);
+
+    require!(!hex.is_empty(), LottoError::InvalidHex);
+    require!(
+        hex.len() <= TICKET_CODE_DIGITS as usize,
+        LottoError::HexTooLong
+    );
+
+    let value = u64::from_str_radix(hex, 16).map_err(|_| LottoError::InvalidHex)?;
+
+    Ok(value.to_be_bytes())
+}
+
+pub fn ticket_code_to_hex(code: &[u8; 8]) -> String {
+    format!("{:x}", u64::from_be_bytes(*code))
+}
+
+/* -------------------------------------------------
+   TICKET MATCHING
+--------------------------------------------------*/
+pub fn has_duplicate_codes(codes: &[[u8; 8]]) -> bool {
+    codes
+        .iter()
+        .enumerate()
+        .any(|(i, code)| codes[i + 1..].contains(code))
+}
+
+/// Inclusive bounds of the ticket code range of a lottery.
+pub fn ticket_code_range(start_hex: &str, end_hex: &str) -> Result<(u64, u64)> {
+    let start = u64::from_be_bytes(ticket_code_from_hex(start_hex)?);
+    let end = u64::from_be_bytes(ticket_code_from_hex(end_hex)?);
+
+    require!(start <= end, LottoError::InvalidTicketRange);
+
+    Ok((start, end))
+}
+
+pub fn codes_in_range(codes: &[[u8; 8]], (start, end): (u64, u64)) -> bool {
+    codes
+        .iter()
+        .map(|code| u64::from_be_bytes(*code))
+        .all(|code| code >= start && code <= end)
+}
+
+/// Effective ticket cap of a lottery. Unique tickets can never outnumber the code
+/// space; `requested == 0` means as many as the lottery can hold.
+pub fn max_tickets_total(
+    requested: u32,
+    (start, end): (u64, u64),
+    allow_shared_tickets: bool,
+) -> Result<u32> {
+    let code_space = (end - start).saturating_add(1).min(u32::MAX as u64) as u32;
+
+    let limit = if allow_shared_tickets {
+        u32::MAX
+    } else {
+        code_space
+    };
+
+    if requested == 0 {
+        return Ok(limit);
+    }
+
+    require!(requested <= limit, LottoError::InvalidTicketRange);
+
+    Ok(requested)
+}
+
+/// Number of trailing hex digits the ticket shares with the winning code.
+pub fn matching_suffix_digits(ticket: &[u8; 8], winning_code: &[u8; 8]) -> u8 {
+    let diff = u64::from_be_bytes(*ticket) ^ u64::from_be_bytes(*winning_code);
+
+    if diff == 0 {
+        return TICKET_CODE_DIGITS;
+    }
+
+    (diff.trailing_zeros() / 4) as u8
+}
+
+/// Index of the best prize tier this ticket wins, if any.
+pub fn score_ticket(
+    ticket: &[u8; 8],
+    winning_code: &[u8; 8],
+    tiers: &[PrizeTier],
+) -> Option<usize> {
+    let digits = matching_suffix_digits(ticket, winning_code);
+
+    tiers.iter().position(|tier| digits >= tier.match_digits)
+}
+
+pub fn tally_bundle(
+    bundle: &TransactionBundle,
+    winning_code: &[u8; 8],
+    tiers: &[PrizeTier],
+) -> [u16; MAX_PRIZE_TIERS] {
+    let mut matches = [0u16; MAX_PRIZE_TIERS];
+
+    for ticket in bundle.tickets[..bundle.ticket_count as usize].iter() {
+        if let Some(tier) = score_ticket(ticket, winning_code, tiers) {
+            matches[tier] += 1;
+        }
+    }
+
+    matches
+}
+
+/* -------------------------------------------------
+   RAFFLE
+   - winner picked by index over tickets actually sold
+   - bundles own contiguous index ranges in sale order
+--------------------------------------------------*/
+pub fn raffle_winning_index(drawn_value: &[u8; 8], tickets_sold: u32) -> u32 {
+    (u64::from_be_bytes(*drawn_value) % (tickets_sold as u64)) as u32
+}
+
+/// Records draw `draw_index`, whatever source the winning code came from.
+/// `feed_slot` is the slot of the feed result it was read from, 0 for commit-reveal draws.
+pub fn record_draw(
+    lottery: &mut Lottery,
+    draw_index: usize,
+    winning_code: [u8; 8],
+    feed_slot: u64,
+) -> Result<()> {
+    // Fix the prize pool at the first draw so every share is computed from the same amount
+    if draw_index == 0 {
+        lottery.prize_pool = lottery.pot_lamports - lottery.platform_fee();
+    }
+
+    let tickets_sold = lottery.tickets_sold;
+    let game_type = lottery.game_type;
+    let draw = &mut lottery.draws[draw_index];
+
+    if game_type == GameType::Raffle {
+        require!(tickets_sold > 0, LottoError::NoTicketsSold);
+
+        draw.winning_ticket_index = raffle_winning_index(&winning_code, tickets_sold);
+    }
+
+    draw.winning_code = winning_code;
+    draw.feed_slot = feed_slot;
+    draw.drawn = true;
+
+    Ok(())
+}
+
+pub fn bundle_contains_ticket_index(bundle: &TransactionBundle, index: u32) -> bool {
+    index >= bundle.first_ticket_index
+        && index < bundle.first_ticket_index + bundle.ticket_count as u32
+}
+
+pub fn tally_raffle_bundle(
+    bundle: &TransactionBundle,
+    winning_index: u32,
+) -> [u16; MAX_PRIZE_TIERS] {
+    let mut matches = [0u16; MAX_PRIZE_TIERS];
+
+    if bundle_contains_ticket_index(bundle, winning_index) {
+        matches[0] = 1;
+    }
+
+    matches
+}
+
+/* -------------------------------------------------
+   PRIZE TABLE
+   - empty table means a single jackpot tier
+   - strictest tier first, percentages total 100
+--------------------------------------------------*/
+pub fn validate_prize_tiers(tiers: Vec<PrizeTier>) -> Result<Vec<PrizeTier>> {
+    if tiers.is_empty() {
+        return Ok(vec![PrizeTier {
+            match_digits: TICKET_CODE_DIGITS,
+            pool_percentage: 100,
+        }]);
+    }
+
+    require!(
+        tiers.len() <= MAX_PRIZE_TIERS,
+        LottoError::InvalidPrizeTable
+    );
+
+    let total: u32 = tiers.iter().map(|t| t.pool_percentage as u32).sum();
+    require!(total == 100, LottoError::InvalidPrizeTable);
+
+    for (i, tier) in tiers.iter().enumerate() {
+        require!(
+            tier.match_digits > 0 && tier.match_digits <= TICKET_CODE_DIGITS,
+            LottoError::InvalidPrizeTable
+        );
+
+        if i > 0 {
+            require!(
+                tier.match_digits < tiers[i - 1].match_digits,
+                LottoError::InvalidPrizeTable
+            );
+        }
+    }
+
+    Ok(tiers)
+}
+
+/* -------------------------------------------------
+   DRAW TABLE
+   - empty table means a single draw paying the whole pool
+   - percentages total 100
+--------------------------------------------------*/
+pub fn build_draws(draw_percentages: Vec<u8>) -> Result<Vec<Draw>> {
+    let percentages = if draw_percentages.is_empty() {
+        vec![100]
+    } else {
+        draw_percentages
+    };
+
+    require!(percentages.len() <= MAX_DRAWS, LottoError::InvalidDrawTable);
+    require!(
+        percentages.iter().all(|p| *p > 0),
+        LottoError::InvalidDrawTable
+    );
+
+    let total: u32 = percentages.iter().map(|p| *p as u32).sum();
+    require!(total == 100, LottoError::InvalidDrawTable);
+
+    Ok(percentages
+        .into_iter()
+        .map(|pool_percentage| Draw {
+            pool_percentage,
+            drawn: false,
+            winning_code: [0; 8],
+            feed_slot: 0,
+            winning_ticket_index: 0,
+            bundles_tallied: 0,
+            tier_winning_tickets: [0; MAX_PRIZE_TIERS],
+            tier_tickets_paid: [0; MAX_PRIZE_TIERS],
+            settled: false,
+        })
+        .collect())
+}
+
+/* -------------------------------------------------
+   LOTTERY TEMPLATE
+   - validates and applies the settings of a new lottery
+   - resets every counter, so a fresh PDA starts clean
+--------------------------------------------------*/
+pub fn apply_template(lottery: &mut Lottery, template: &LotteryTemplate) -> Result<()> {
+    // Bounds the space both strings take in the lottery and series accounts
+    require!(
+        template.ticket_code_start_hex.len() <= MAX_TICKET_CODE_HEX_LEN
+            && template.ticket_code_end_hex.len() <= MAX_TICKET_CODE_HEX_LEN,
+        LottoError::HexTooLong
+    );
+
+    let code_range = ticket_code_range(
+        &template.ticket_code_start_hex,
+        &template.ticket_code_end_hex,
+    )?;
+
+    // Unique codes are sold only through a registry, one bit per code
+    require!(
+        template.allow_shared_tickets || code_range.1 - code_range.0 < MAX_REGISTRY_CODES,
+        LottoError::UniqueCodeRangeTooLarge
+    );
+
+    lottery.lamports_per_ticket = template.lamports_per_ticket;
+    lottery.ticket_code_start_hex = template.ticket_code_start_hex.clone();
+    lottery.ticket_code_end_hex = template.ticket_code_end_hex.clone();
+
+    lottery.max_tickets_total = max_tickets_total(
+        template.max_tickets_total,
+        code_range,
+        template.allow_shared_tickets,
+    )?;
+    lottery.max_tickets_per_wallet = template.max_tickets_per_wallet;
+
+    // A threshold above the cap could never be reached
+    require!(
+        template.min_tickets_to_draw <= lottery.max_tickets_total,
+        LottoError::InvalidTicketRange
+    );
+
+    lottery.min_tickets_to_draw = template.min_tickets_to_draw;
+
+    require!(
+        template.referral_bps <= 10_000,
+        LottoError::InvalidReferralShare
+    );
+
+    lottery.referral_bps = template.referral_bps;
+
+    require!(template.claim_window >= 0, LottoError::InvalidClaimWindow);
+
+    lottery.claim_window = template.claim_window;
+    lottery.claim_deadline = 0;
+    lottery.prizes_swept = false;
+    lottery.platform_fee_percentage = template.platform_fee_percentage;
+    lottery.allow_shared_tickets = template.allow_shared_tickets;
+
+    // A raffle always pays a single winner
+    if template.game_type == GameType::Raffle {
+        require!(
+            template.prize_tiers.is_empty(),
+            LottoError::InvalidPrizeTable
+        );
+    }
+
+    lottery.game_type = template.game_type;
+    lottery.prize_tiers = validate_prize_tiers(template.prize_tiers.clone())?;
+    lottery.draws = build_draws(template.draw_percentages.clone())?;
+
+    lottery.open = true;
+    lottery.cancelled = false;
+    lottery.tickets_sold = 0;
+    lottery.refunds_settled = 0;
+    lottery.bundle_count = 0;
+    lottery.bundles_closed = 0;
+    lottery.prize_pool = 0;
+    lottery.ticket_revenue = 0;
+    lottery.platform_fee_withdrawn = false;
+    lottery.seeded_prize = 0;
+    lottery.pot_lamports = 0;
+    lottery.referral_fees_accrued = 0;
+    lottery.referral_fees_claimed = 0;
+    lottery.ticket_registry = Pubkey::default();
+    lottery.draw_commitment = [0; 32];
+    lottery.draw_slot = 0;
+    lottery.prizes_carried_over = false;
+
+    Ok(())
+}
+
+/// `lottery_seed` of a series round, also the seed of its bundle PDAs.
+pub fn series_round_seed(series_seed: &str, round: u32) -> String {
+    format!("{series_seed}-{round}")
+}
+
+/// Stops sales in `slot`; below the participation threshold the lottery becomes
+/// refund-only, as does a raffle without tickets, which has no one to draw.
+pub fn close_sales(lottery: &mut Lottery, slot: u64) {
+    lottery.open = false;
+    lottery.draw_slot = slot;
+
+    if lottery.tickets_sold < lottery.min_tickets_to_draw
+        || (lottery.game_type == GameType::Raffle && lottery.tickets_sold == 0)
+    {
+        lottery.cancelled = true;
+    }
+}
+
+/// Whether every draw has taken place and been tallied against every bundle.
+pub fn draws_complete(lottery: &Lottery) -> bool {
+    lottery
+        .draws
+        .iter()
+        .all(|d| d.drawn && d.bundles_tallied == lottery.bundle_count)
+}
+
+/// Prize pools of tiers nobody won, across every draw. Only final once
+/// every draw is complete (see `draws_complete`).
+pub fn unwon_prizes(lottery: &Lottery) -> u64 {
+    lottery
+        .draws
+        .iter()
+        .filter(|d| d.drawn)
+        .map(|draw| {
+            let draw_pool = lottery.prize_pool * (draw.pool_percentage as u64) / 100;
+
+            lottery
+                .prize_tiers
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| draw.tier_winning_tickets[*i] == 0)
+                .map(|(_, tier)| draw_pool * (tier.pool_percentage as u64) / 100)
+                .sum::<u64>()
+        })
+        .sum()
+}
+
+/// Moves what a settled series round leaves behind into a later, open round:
+/// the seeded prize of a cancelled round, or the pools of tiers nobody won.
+/// It joins the pool of `to` like a seeded prize, free of fees.
+pub fn carry_over_round_prizes<'info>(
+    from: &mut Account<'info, Lottery>,
+    to: &mut Account<'info, Lottery>,
+) -> Result<u64> {
+    require!(
+        !from.prizes_carried_over,
+        LottoError::PrizesAlreadyCarriedOver
+    );
+
+    // Players of a cancelled round get refunds, the jackpot moves on
+    let amount = if from.cancelled {
+        let seeded_prize = from.seeded_prize;
+        from.seeded_prize = 0;
+        seeded_prize
+    } else {
+        require!(draws_complete(from), LottoError::RoundNotSettled);
+        unwon_prizes(from)
+    };
+
+    if amount > 0 {
+        pay_from_lottery(from, &to.to_account_info(), None, amount)?;
+    }
+
+    to.seeded_prize += amount;
+    to.pot_lamports += amount;
+    from.prizes_carried_over = true;
+
+    Ok(amount)
+}
+
+/* -------------------------------------------------
+   TICKET SALE
+   - shared by paid and relayed purchases
+--------------------------------------------------*/
+/// Checks a purchase against the lottery's sales window, code range and caps,
+/// then writes the tickets into the new bundle. `player` must already be linked
+/// to the lottery; the caller books the payment with `record_sale`.
+pub fn fill_bundle(
+    lottery: &Account<Lottery>,
+    bundle: &mut TransactionBundle,
+    player: &mut PlayerTickets,
+    registry: Option<&AccountLoader<TicketRegistry>>,
+    owner: Pubkey,
+    purchased_numbers: &[[u8; 8]],
+) -> Result<()> {
+    require!(lottery.open, LottoError::LotteryClosed);
+
+    // Series rounds stop selling at the end of their window
+    if lottery.sales_close_ts != 0 {
+        require!(
+            Clock::get()?.unix_timestamp < lottery.sales_close_ts,
+            LottoError::LotteryClosed
+        );
+    }
+
+    require!(purchased_numbers.len() <= 100, LottoError::BundleFull);
+
+    if !lottery.allow_shared_tickets {
+        require!(
+            !has_duplicate_codes(purchased_numbers),
+            LottoError::DuplicateTicketCode
+        );
+    }
+
+    let code_range =
+        ticket_code_range(&lottery.ticket_code_start_hex, &lottery.ticket_code_end_hex)?;
+
+    require!(
+        codes_in_range(purchased_numbers, code_range),
+        LottoError::TicketCodeOutOfRange
+    );
+
+    let ticket_count = purchased_numbers.len() as u32;
+
+    require!(
+        lottery.tickets_sold + ticket_count <= lottery.max_tickets_total,
+        LottoError::LotterySoldOut
+    );
+
+    player.tickets_bought += ticket_count;
+
+    require!(
+        lottery.max_tickets_per_wallet == 0
+            || player.tickets_bought <= lottery.max_tickets_per_wallet,
+        LottoError::WalletTicketCapExceeded
+    );
+
+    register_codes(lottery, registry, purchased_numbers, code_range)?;
+
+    // store owner + lottery link
+    bundle.owner = owner;
+    bundle.lottery_pda = lottery.key();
+
+    // Store raw bytes
+    for (i, ticket_bytes) in purchased_numbers.iter().enumerate() {
+        bundle.tickets[i] = *ticket_bytes;
+    }
+    bundle.ticket_count = purchased_numbers.len() as u16;
+    bundle.first_ticket_index = lottery.tickets_sold;
+
+    Ok(())
+}
+
+/// Books a bundle of `ticket_count` tickets whose payment put `received` into the pot.
+pub fn record_sale(lottery: &mut Lottery, ticket_count: u16, received: u64) {
+    lottery.ticket_revenue += received;
+    lottery.pot_lamports += received;
+
+    // update total sold tickets
+    lottery.tickets_sold += ticket_count as u32;
+    lottery.bundle_count += 1;
+}
+
+/// Credits the referrer of a purchase its cut of `received`, carved out of the
+/// platform fee. Returns the amount credited, `None` for purchases without a referrer.
+pub fn accrue_referral(
+    lottery_key: Pubkey,
+    lottery: &mut Lottery,
+    referral: Option<&mut ReferralRewards>,
+    referral_bump: Option<u8>,
+    referrer: Option<Pubkey>,
+    owner: &Pubkey,
+    received: u64,
+) -> Result<Option<u64>> {
+    match (referrer, referral) {
+        (Some(referrer), Some(referral)) => {
+            require_keys_neq!(referrer, *owner, LottoError::InvalidReferrer);
+
+            if referral.lottery == Pubkey::default() {
+                referral.lottery = lottery_key;
+                referral.referrer = referrer;
+                referral.bump = referral_bump.unwrap_or_default();
+            }
+
+            let amount = lottery.referral_fee(received);
+
+            referral.accrued += amount;
+            lottery.referral_fees_accrued += amount;
+
+            Ok(Some(amount))
+        }
+        (None, None) => Ok(None),
+        _ => err!(LottoError::InvalidReferrer),
+    }
+}
+
+/* -------------------------------------------------
+   TICKET REGISTRY
+   - one bit per code of the lottery range
+   - every purchase of a lottery with unique codes marks
+     them, so codes stay unique across bundles
+--------------------------------------------------*/
+pub fn register_codes(
+    lottery: &Lottery,
+    registry: Option<&AccountLoader<TicketRegistry>>,
+    codes: &[[u8; 8]],
+    (start, _): (u64, u64),
+) -> Result<()> {
+    if lottery.allow_shared_tickets {
+        return Ok(());
+    }
+
+    require!(
+        lottery.has_ticket_registry(),
+        LottoError::TicketRegistryRequired
+    );
+
+    // The account constraint pins it to `lottery.ticket_registry`
+    let registry = registry.ok_or(LottoError::TicketRegistryRequired)?;
+    let mut registry = registry.load_mut()?;
+
+    for code in codes {
+        let offset = u64::from_be_bytes(*code) - start;
+
+        require!(!registry.is_sold(offset), LottoError::DuplicateTicketCode);
+
+        registry.mark_sold(offset);
+    }
+
+    Ok(())
+}
+
+/* -------------------------------------------------
+   QUICK PICK
+   - codes are derived from a 32-byte random value
+   - unique lotteries probe forward from each derived
+     code to the next one neither sold nor picked
+--------------------------------------------------*/
+pub fn quick_pick_codes(
+    random_value: &[u8; 32],
+    ticket_count: u8,
+    (start, end): (u64, u64),
+    allow_shared_tickets: bool,
+    registry: Option<&TicketRegistry>,
+) -> Result<Vec<[u8; 8]>> {
+    require!(
+        allow_shared_tickets || registry.is_some(),
+        LottoError::TicketRegistryRequired
+    );
+
+    // `None` when the range covers every u64
+    let space = (end - start).checked_add(1);
+    let mut offsets: Vec<u64> = Vec::with_capacity(ticket_count as usize);
+
+    for i in 0..ticket_count {
+        let digest = hashv(&[random_value, &[i]]).to_bytes();
+        let value = u64::from_le_bytes(digest[..8].try_into().unwrap());
+
+        let mut offset = space.map_or(value, |space| value % space);
+
+        if let (false, Some(registry), Some(space)) = (allow_shared_tickets, registry, space) {
+            let mut probes = 0;
+
+            while registry.is_sold(offset) || offsets.contains(&offset) {
+                probes += 1;
+                require!(probes < space, LottoError::LotterySoldOut);
+
+                offset = (offset + 1) % space;
+            }
+        }
+
+        offsets.push(offset);
+    }
+
+    Ok(offsets
+        .into_iter()
+        .map(|offset| (start + offset).to_be_bytes())
+        .collect())
+}
+
+/// Hash of `slot` from the SlotHashes sysvar (newest first), `None` once the
+/// slot is older than the entries the sysvar keeps.
+pub fn slot_hash(slot_hashes: &AccountInfo, slot: u64) -> Result<Option<[u8; 32]>> {
+    let data = slot_hashes.try_borrow_data()?;
+
+    require!(data.len() >= 8, LottoError::RandomnessExpired);
+
+    let count = u64::from_le_bytes(data[..8].try_into().unwrap()) as usize;
+
+    for entry in data[8..].chunks_exact(40).take(count) {
+        let entry_slot = u64::from_le_bytes(entry[..8].try_into().unwrap());
+
+        if entry_slot == slot {
+            return Ok(Some(entry[8..].try_into().unwrap()));
+        }
+
+        if entry_slot < slot {
+            break;
+        }
+    }
+
+    Ok(None)
+}
+
+/// Checks a commit-reveal secret and mixes it with the hash of `slot`.
+pub fn reveal_with_slot_hash(
+    slot_hashes: &AccountInfo,
+    slot: u64,
+    commitment: &[u8; 32],
+    secret: &[u8; 32],
+) -> Result<[u8; 32]> {
+    require!(
+        hash(secret).to_bytes() == *commitment,
+        LottoError::InvalidSecret
+    );
+
+    let slot_hash = slot_hash(slot_hashes, slot)?.ok_or(LottoError::RandomnessExpired)?;
+
+    Ok(mix_slot_hash(secret, &slot_hash))
+}
+
+/// Whether the hash of `slot` has left SlotHashes (it only keeps the most recent
+/// slots), so it can no longer be revealed against.
+pub fn draw_slot_expired(slot_hashes: &AccountInfo, slot: u64, clock_slot: u64) -> Result<bool> {
+    Ok(clock_slot > slot && slot_hash(slot_hashes, slot)?.is_none())
+}
+
+/// Random value of a revealed secret and a slot hash neither party chose alone.
+pub fn mix_slot_hash(secret: &[u8; 32], slot_hash: &[u8; 32]) -> [u8; 32] {
+    hashv(&[secret, slot_hash]).to_bytes()
+}
+
+/* -------------------------------------------------
+   COMMIT-REVEAL DRAW
+   - secret committed at creation, revealed after sales close
+   - mixed with the hash of the slot sales closed in
+--------------------------------------------------*/
+
+/// Range commit-reveal draws pick from: the lottery code range, or for raffles
+/// the indices of the tickets sold, so that draws pick distinct tickets while
+/// there are more tickets than draws (a ticket can win again only past that).
+pub fn commit_reveal_code_range(lottery: &Lottery) -> Result<(u64, u64)> {
+    match lottery.game_type {
+        GameType::Raffle => {
+            require!(lottery.tickets_sold > 0, LottoError::NoTicketsSold);
+
+            Ok((0, lottery.tickets_sold as u64 - 1))
+        }
+        GameType::CodeMatch => {
+            ticket_code_range(&lottery.ticket_code_start_hex, &lottery.ticket_code_end_hex)
+        }
+    }
+}
+
+/// Winning code of a commit-reveal draw, inside the lottery code range and
+/// distinct from the codes of earlier draws while the range allows it.
+pub fn commit_reveal_winning_code(
+    random_value: &[u8; 32],
+    draw_index: u8,
+    (start, end): (u64, u64),
+    drawn_codes: &[[u8; 8]],
+) -> [u8; 8] {
+    let digest = hashv(&[random_value, &[draw_index]]).to_bytes();
+    let value = u64::from_le_bytes(digest[..8].try_into().unwrap());
+
+    // `None` when the range covers every u64
+    let Some(space) = (end - start).checked_add(1) else {
+        return value.to_be_bytes();
+    };
+
+    let mut offset = value % space;
+
+    for _ in 0..space.min(drawn_codes.len() as u64) {
+        if !drawn_codes.contains(&(start + offset).to_be_bytes()) {
+            break;
+        }
+
+        offset = (offset + 1) % space;
+    }
+
+    (start + offset).to_be_bytes()
+}
+
+/* -------------------------------------------------
+   SWITCHBOARD RANDOMNESS
+   - committed in the slot before the request
+   - revealed in the same slot as the fulfilment
+--------------------------------------------------*/
+fn parse_randomness(account: &AccountInfo) -> Result<RandomnessAccountData> {
+    require!(
+        *account.owner == ON_DEMAND_MAINNET_PID || *account.owner == ON_DEMAND_DEVNET_PID,
+        LottoError::RandomnessMismatch
+    );
+
+    let data = RandomnessAccountData::parse(account.data.borrow())
+        .map_err(|_| LottoError::RandomnessMismatch)?;
+
+    Ok(*data)
+}
+
+/// Checks a randomness account was just committed and is not revealed yet.
+pub fn require_fresh_randomness(account: &AccountInfo, clock_slot: u64) -> Result<()> {
+    let randomness = parse_randomness(account)?;
+
+    require_eq!(
+        randomness.seed_slot + 1,
+        clock_slot,
+        LottoError::RandomnessAlreadyRevealed
+    );
+
+    Ok(())
+}
+
+/// Value of a randomness account committed right before `request_slot`,
+/// revealed in the current slot.
+pub fn revealed_randomness(
+    account: &AccountInfo,
+    request_slot: u64,
+    clock_slot: u64,
+) -> Result<[u8; 32]> {
+    let randomness = parse_randomness(account)?;
+
+    // Recommitting after the request would let the value be chosen again
+    require_eq!(
+        randomness.seed_slot + 1,
+        request_slot,
+        LottoError::RandomnessMismatch
+    );
+
+    randomness
+        .get_value(clock_slot)
+        .map_err(|_| error!(LottoError::RandomnessNotResolved))
+}
+
+/* -------------------------------------------------
+   PRIZE SHARE
+   - pool split evenly across every winning ticket
+   - u128 to avoid overflow on large pools
+--------------------------------------------------*/
+pub fn prize_share(pool: u64, tickets: u32, total_tickets: u32) -> u64 {
+    if total_tickets == 0 {
+        return 0;
+    }
+
+    ((pool as u128) * (tickets as u128) / (total_tickets as u128)) as u64
+}
+
+/// Amount owed to a bundle for one draw, across every prize tier it won.
+pub fn bundle_payout(lottery: &Lottery, bundle: &TransactionBundle, draw_index: usize) -> u64 {
+    let draw = &lottery.draws[draw_index];
+    let draw_pool = lottery.prize_pool * (draw.pool_percentage as u64) / 100;
+
+    lottery
+        .prize_tiers
+        .iter()
+        .enumerate()
+        .map(|(i, tier)| {
+            let tier_pool = draw_pool * (tier.pool_percentage as u64) / 100;
+
+            prize_share(
+                tier_pool,
+                bundle.tier_winning_tickets[draw_index][i] as u32,
+                draw.tier_winning_tickets[i],
+            )
+        })
+        .sum()
+}
+
+/// Winning shares not paid out yet, across every drawn draw.
+pub fn unpaid_prizes(lottery: &Lottery) -> u64 {
+    lottery
+        .draws
+        .iter()
+        .filter(|d| d.drawn)
+        .map(|draw| {
+            let draw_pool = lottery.prize_pool * (draw.pool_percentage as u64) / 100;
+
+            lottery
+                .prize_tiers
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| draw.tier_winning_tickets[*i] > 0)
+                .map(|(i, tier)| {
+                    let tier_pool = draw_pool * (tier.pool_percentage as u64) / 100;
+                    let unpaid_tickets = draw.tier_winning_tickets[i] - draw.tier_tickets_paid[i];
+
+                    // Shares are floored per bundle, so this never exceeds what is still held
+                    prize_share(tier_pool, unpaid_tickets, draw.tier_winning_tickets[i])
+                })
+                .sum::<u64>()
+        })
+        .sum()
+}
+
+/// Whether the claim window of a lottery has closed.
+pub fn claim_deadline_passed(lottery: &Lottery, now: i64) -> bool {
+    lottery.claim_deadline != 0 && now > lottery.claim_deadline
+}
+
+/// Checks a bundle can be paid its share of a draw and returns that share.
+pub fn bundle_payout_due(
+    lottery: &Account<Lottery>,
+    bundle: &TransactionBundle,
+    draw_index: usize,
+    now: i64,
+) -> Result<u64> {
+    require!(
+        draw_index < lottery.draws.len(),
+        LottoError::InvalidDrawIndex
+    );
+
+    require!(!lottery.cancelled, LottoError::LotteryCancelled);
+    require!(
+        !claim_deadline_passed(lottery, now) && !lottery.prizes_swept,
+        LottoError::ClaimDeadlinePassed
+    );
+
+    let draw = &lottery.draws[draw_index];
+
+    require!(draw.drawn, LottoError::WinnerNotDrawn);
+    require!(!draw.settled, LottoError::DuplicateRequest);
+    require!(
+        bundle.draws_rewarded & (1u8 << draw_index) == 0,
+        LottoError::DuplicateRequest
+    );
+
+    // Shares are only known once every bundle has been checked
+    require!(
+        draw.bundles_tallied == lottery.bundle_count,
+        LottoError::TallyIncomplete
+    );
+
+    require_keys_eq!(bundle.lottery_pda, lottery.key(), LottoError::KeyMismatch);
+
+    require!(
+        bundle.tier_winning_tickets[draw_index]
+            .iter()
+            .any(|t| *t > 0),
+        LottoError::TicketNotInBundle
+    );
+
+    // Each tier's pool is split evenly across that tier's winning tickets
+    Ok(bundle_payout(lottery, bundle, draw_index))
+}
+
+/// Marks the share of a bundle in a draw as paid; the draw is settled once every share is.
+pub fn record_bundle_payout(
+    lottery: &mut Lottery,
+    bundle: &mut TransactionBundle,
+    draw_index: usize,
+) {
+    bundle.draws_rewarded |= 1u8 << draw_index;
+
+    let draw = &mut lottery.draws[draw_index];
+
+    for (i, count) in bundle.tier_winning_tickets[draw_index].iter().enumerate() {
+        draw.tier_tickets_paid[i] += *count as u32;
+    }
+
+    if draw.tier_tickets_paid == draw.tier_winning_tickets {
+        draw.settled = true;
+    }
+}
+
+/// Whether nothing is left to do with a bundle: refunded, or tallied against every
+/// draw with each winning share paid (or swept after the claim deadline).
+pub fn bundle_settled(lottery: &Lottery, bundle: &TransactionBundle) -> bool {
+    if bundle.refunded {
+        return true;
+    }
+
+    bundle.draws_tallied as usize == lottery.draws.len()
+        && (0..lottery.draws.len()).all(|draw_index| {
+            bundle.tier_winning_tickets[draw_index]
+                .iter()
+                .all(|t| *t == 0)
+                || bundle.draws_rewarded & (1u8 << draw_index) != 0
+                || lottery.prizes_swept
+        })
+}
+
+/// Amount refunded for a bundle: its share of the ticket revenue actually
+/// received, in proportion to the tickets it holds.
+pub fn bundle_refund(lottery: &Lottery, bundle: &TransactionBundle, deduct_fee: bool) -> u64 {
+    let platform_fee = if deduct_fee {
+        lottery.platform_fee()
+    } else {
+        0
+    };
+
+    prize_share(
+        lottery.ticket_revenue - platform_fee,
+        bundle.ticket_count as u32,
+        lottery.tickets_sold,
+    )
 }
 
 /* -------------------------------------------------
    SAFE LAMPORT TRANSFER
    - Checks balance
-   - No rent-exemption issues for PDA paying out
+   - Never takes the source below rent exemption
    - Direct lamport movement
 --------------------------------------------------*/
+/// Lamports an account can pay out while staying rent-exempt.
+pub fn withdrawable_lamports(account: &AccountInfo) -> Result<u64> {
+    let rent_exempt_minimum = Rent::get()?.minimum_balance(account.data_len());
+
+    Ok(account.lamports().saturating_sub(rent_exempt_minimum))
+}
+
 pub fn transfer_lamports<'info>(
     from: &AccountInfo<'info>,
     to: &AccountInfo<'info>,
@@ -47,6 +1029,11 @@ pub fn transfer_lamports<'info>(
         LottoError::InsufficientLamports
     );
 
+    require!(
+        lamports <= withdrawable_lamports(from)?,
+        LottoError::RentExemptionViolation
+    );
+
     // subtract from source
     **from.try_borrow_mut_lamports()? -= lamports;
 
@@ -55,3 +1042,1114 @@ pub fn transfer_lamports<'info>(
 
     Ok(())
 }
+
+/* -------------------------------------------------
+   PURCHASE PAYMENT VERIFICATION
+   - the funding transfer must be the instruction right
+     before create_transaction_bundle, in the same transaction
+   - SOL: System Program transfer owner -> lottery PDA
+   - tokens: transfer_checked owner -> lottery vault
+   - top-level calls only: under CPI the previous top-level
+     instruction is unrelated, so a `funder` pays in-program
+--------------------------------------------------*/
+const SYSTEM_TRANSFER_TAG: u32 = 2;
+const TOKEN_TRANSFER_CHECKED_TAG: u8 = 12;
+
+pub fn verify_purchase_payment(
+    instructions_sysvar: &AccountInfo,
+    lottery: &Account<Lottery>,
+    owner: &Pubkey,
+    amount: u64,
+) -> Result<()> {
+    // Only a top-level call has the payment as its previous instruction
+    let current = get_instruction_relative(0, instructions_sysvar)?;
+    require_keys_eq!(current.program_id, crate::ID, LottoError::PaymentNotFound);
+
+    let current_index = load_current_index_checked(instructions_sysvar)?;
+    require!(current_index > 0, LottoError::PaymentNotFound);
+
+    let payment = load_instruction_at_checked((current_index - 1) as usize, instructions_sysvar)?;
+
+    let (payer, destination, paid) = if lottery.is_token_lottery() {
+        require!(
+            payment.program_id == anchor_spl::token::ID
+                || payment.program_id == anchor_spl::token_2022::ID,
+            LottoError::PaymentNotFound
+        );
+        require!(
+            payment.data.len() == 10 && payment.data[0] == TOKEN_TRANSFER_CHECKED_TAG,
+            LottoError::PaymentNotFound
+        );
+        require!(payment.accounts.len() >= 4, LottoError::PaymentNotFound);
+
+        // [source, mint, destination, authority]
+        require_keys_eq!(
+            payment.accounts[1].pubkey,
+            lottery.payment_mint,
+            LottoError::PaymentMismatch
+        );
+
+        let paid = u64::from_le_bytes(payment.data[1..9].try_into().unwrap());
+
+        (payment.accounts[3].pubkey, payment.accounts[2].pubkey, paid)
+    } else {
+        require_keys_eq!(
+            payment.program_id,
+            system_program::ID,
+            LottoError::PaymentNotFound
+        );
+        require!(
+            payment.data.len() == 12 && payment.data[..4] == SYSTEM_TRANSFER_TAG.to_le_bytes(),
+            LottoError::PaymentNotFound
+        );
+        require!(payment.accounts.len() >= 2, LottoError::PaymentNotFound);
+
+        let paid = u64::from_le_bytes(payment.data[4..12].try_into().unwrap());
+
+        (payment.accounts[0].pubkey, payment.accounts[1].pubkey, paid)
+    };
+
+    let escrow = if lottery.is_token_lottery() {
+        lottery.payment_vault
+    } else {
+        lottery.key()
+    };
+
+    require_keys_eq!(payer, *owner, LottoError::PaymentMismatch);
+    require_keys_eq!(destination, escrow, LottoError::PaymentMismatch);
+    require_eq!(paid, amount, LottoError::PaymentMismatch);
+
+    Ok(())
+}
+
+/* -------------------------------------------------
+   PURCHASE INTENT VERIFICATION
+   - the Ed25519 program instruction must directly precede
+     purchase_with_intent and verify exactly one signature
+   - key, signature and message must all be stored in that
+     instruction's own data
+--------------------------------------------------*/
+const ED25519_OFFSETS_START: usize = 2;
+const ED25519_OFFSETS_SIZE: usize = 14;
+const ED25519_CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// Ensures the transaction verified `signer`'s ed25519 signature over `message`.
+/// The Ed25519 program fails the whole transaction on a bad signature, so only
+/// the key and message it checked need to be matched here.
+pub fn verify_intent_signature(
+    instructions_sysvar: &AccountInfo,
+    signer: &Pubkey,
+    message: &[u8],
+) -> Result<()> {
+    let current = get_instruction_relative(0, instructions_sysvar)?;
+    require_keys_eq!(
+        current.program_id,
+        crate::ID,
+        LottoError::IntentSignatureNotFound
+    );
+
+    let current_index = load_current_index_checked(instructions_sysvar)?;
+    require!(current_index > 0, LottoError::IntentSignatureNotFound);
+
+    let verify = load_instruction_at_checked((current_index - 1) as usize, instructions_sysvar)?;
+
+    require_keys_eq!(
+        verify.program_id,
+        ed25519_program::ID,
+        LottoError::IntentSignatureNotFound
+    );
+
+    let data = &verify.data;
+
+    require!(
+        data.len() >= ED25519_OFFSETS_START + ED25519_OFFSETS_SIZE && data[0] == 1,
+        LottoError::IntentSignatureNotFound
+    );
+
+    let read_u16 = |at: usize| {
+        let at = ED25519_OFFSETS_START + at;
+        u16::from_le_bytes([data[at], data[at + 1]])
+    };
+
+    // [signature offset, signature ix, key offset, key ix, message offset, message size, message ix]
+    require!(
+        read_u16(2) == ED25519_CURRENT_INSTRUCTION
+            && read_u16(6) == ED25519_CURRENT_INSTRUCTION
+            && read_u16(12) == ED25519_CURRENT_INSTRUCTION,
+        LottoError::IntentSignatureNotFound
+    );
+
+    let key_offset = read_u16(4) as usize;
+    let message_offset = read_u16(8) as usize;
+    let message_size = read_u16(10) as usize;
+
+    require!(
+        data.get(key_offset..key_offset + 32) == Some(signer.as_ref()),
+        LottoError::IntentMismatch
+    );
+    require!(
+        data.get(message_offset..message_offset + message_size) == Some(message),
+        LottoError::IntentMismatch
+    );
+
+    Ok(())
+}
+
+/* -------------------------------------------------
+   TOKEN PAYMENTS
+   - vault is the lottery PDA's associated token account
+   - transfers out of the vault are signed by the lottery PDA
+--------------------------------------------------*/
+pub struct VaultTransfer<'a, 'info> {
+    pub mint: &'a InterfaceAccount<'info, Mint>,
+    pub vault: &'a InterfaceAccount<'info, TokenAccount>,
+    pub destination: &'a InterfaceAccount<'info, TokenAccount>,
+    pub token_program: &'a Interface<'info, TokenInterface>,
+}
+
+/// Validates the optional token accounts of a payout instruction.
+/// Returns `None` for SOL lotteries.
+pub fn vault_transfer<'a, 'info>(
+    lottery: &Lottery,
+    mint: &'a Option<InterfaceAccount<'info, Mint>>,
+    vault: &'a Option<InterfaceAccount<'info, TokenAccount>>,
+    destination: &'a Option<InterfaceAccount<'info, TokenAccount>>,
+    token_program: &'a Option<Interface<'info, TokenInterface>>,
+    destination_owner: &Pubkey,
+) -> Result<Option<VaultTransfer<'a, 'info>>> {
+    if !lottery.is_token_lottery() {
+        return Ok(None);
+    }
+
+    let (Some(mint), Some(vault), Some(destination), Some(token_program)) =
+        (mint, vault, destination, token_program)
+    else {
+        return err!(LottoError::TokenAccountsMissing);
+    };
+
+    require_keys_eq!(
+        mint.key(),
+        lottery.payment_mint,
+        LottoError::TokenAccountMismatch
+    );
+    require_keys_eq!(
+        vault.key(),
+        lottery.payment_vault,
+        LottoError::TokenAccountMismatch
+    );
+    require_keys_eq!(
+        destination.mint,
+        lottery.payment_mint,
+        LottoError::TokenAccountMismatch
+    );
+    require_keys_eq!(
+        destination.owner,
+        *destination_owner,
+        LottoError::TokenAccountMismatch
+    );
+
+    Ok(Some(VaultTransfer {
+        mint,
+        vault,
+        destination,
+        token_program,
+    }))
+}
+
+/// Runs `f` with the seeds of the lottery PDA: `["lottery", lottery_seed]`,
+/// or `["lottery", series, round]` for series rounds.
+fn with_lottery_signer<R>(lottery: &Lottery, f: impl FnOnce(&[&[&[u8]]]) -> R) -> R {
+    let bump = [lottery.bump];
+    let round = lottery.round.to_le_bytes();
+
+    if lottery.is_series_round() {
+        f(&[&[b"lottery", lottery.series.as_ref(), &round, &bump]])
+    } else {
+        f(&[&[b"lottery", lottery.lottery_seed.as_bytes(), &bump]])
+    }
+}
+
+pub fn transfer_from_vault<'info>(
+    lottery: &Account<'info, Lottery>,
+    accounts: &VaultTransfer<'_, 'info>,
+    amount: u64,
+) -> Result<()> {
+    with_lottery_signer(lottery, |signer_seeds| {
+        transfer_checked(
+            CpiContext::new_with_signer(
+                accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: accounts.vault.to_account_info(),
+                    mint: accounts.mint.to_account_info(),
+                    to: accounts.destination.to_account_info(),
+                    authority: lottery.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+            accounts.mint.decimals,
+        )
+    })
+}
+
+pub fn close_vault<'info>(
+    lottery: &Account<'info, Lottery>,
+    accounts: &VaultTransfer<'_, 'info>,
+    rent_destination: &AccountInfo<'info>,
+) -> Result<()> {
+    with_lottery_signer(lottery, |signer_seeds| {
+        close_account(CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: accounts.vault.to_account_info(),
+                destination: rent_destination.clone(),
+                authority: lottery.to_account_info(),
+            },
+            signer_seeds,
+        ))
+    })
+}
+
+/// Pays `amount` out of the lottery escrow: lamports for SOL lotteries,
+/// tokens from the vault otherwise. Every payout is debited from the pot
+/// ledger, so untracked funds and the rent-exempt reserve are never spent.
+pub fn pay_from_lottery<'info>(
+    lottery: &mut Account<'info, Lottery>,
+    recipient: &AccountInfo<'info>,
+    token: Option<&VaultTransfer<'_, 'info>>,
+    amount: u64,
+) -> Result<()> {
+    lottery.pot_lamports = lottery
+        .pot_lamports
+        .checked_sub(amount)
+        .ok_or(LottoError::PotInsufficient)?;
+
+    match token {
+        Some(accounts) => {
+            require!(
+                amount <= withdrawable_balance(lottery, token)?,
+                LottoError::InsufficientLamports
+            );
+
+            transfer_from_vault(lottery, accounts, amount)
+        }
+        // Checks the rent-exempt reserve itself
+        None => transfer_lamports(&lottery.to_account_info(), recipient, amount),
+    }
+}
+
+/// Funds the lottery escrow can actually pay out: lamports above rent exemption
+/// for SOL lotteries, the vault balance otherwise.
+pub fn withdrawable_balance(
+    lottery: &Account<Lottery>,
+    token: Option<&VaultTransfer>,
+) -> Result<u64> {
+    match token {
+        Some(accounts) => Ok(accounts.vault.amount),
+        None => withdrawable_lamports(&lottery.to_account_info()),
+    }
+}
+
+/// Pays `amount` from `payer` (the admin, or a purchase funder) into the lottery
+/// escrow and returns what the escrow actually received. For token lotteries
+/// `token.destination` is the payer's token account, as validated by `vault_transfer`.
+pub fn pay_into_lottery<'info>(
+    lottery: &Account<'info, Lottery>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    token: Option<&VaultTransfer<'_, 'info>>,
+    amount: u64,
+) -> Result<u64> {
+    match token {
+        Some(accounts) => {
+            transfer_checked(
+                CpiContext::new(
+                    accounts.token_program.to_account_info(),
+                    TransferChecked {
+                        from: accounts.destination.to_account_info(),
+                        mint: accounts.mint.to_account_info(),
+                        to: accounts.vault.to_account_info(),
+                        authority: payer.to_account_info(),
+                    },
+                ),
+                amount,
+                accounts.mint.decimals,
+            )?;
+
+            net_of_transfer_fee(accounts.mint, amount)
+        }
+        None => {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: payer.to_account_info(),
+                        to: lottery.to_account_info(),
+                    },
+                ),
+                amount,
+            )?;
+
+            Ok(amount)
+        }
+    }
+}
+
+/// Amount the vault actually receives when `amount` is transferred in,
+/// accounting for a Token-2022 transfer fee on the mint.
+pub fn net_of_transfer_fee(mint: &InterfaceAccount<Mint>, amount: u64) -> Result<u64> {
+    let mint_info = mint.to_account_info();
+
+    if *mint_info.owner != anchor_spl::token_2022::ID {
+        return Ok(amount);
+    }
+
+    let data = mint_info.try_borrow_data()?;
+    let state = StateWithExtensions::<MintState>::unpack(&data)
+        .map_err(|_| LottoError::TransferFeeError)?;
+
+    let Ok(fee_config) = state.get_extension::<TransferFeeConfig>() else {
+        return Ok(amount);
+    };
+
+    let fee = fee_config
+        .calculate_epoch_fee(Clock::get()?.epoch, amount)
+        .ok_or(LottoError::TransferFeeError)?;
+
+    Ok(amount - fee)
+}
+
+/* -------------------------------------------------
+   PLAYER BALANCE
+   - SOL balances are lamports of the player account PDA
+   - token balances sit in the PDA's associated token account;
+     transfers out of it are signed by the player account PDA
+--------------------------------------------------*/
+pub struct BalanceTransfer<'a, 'info> {
+    pub mint: &'a InterfaceAccount<'info, Mint>,
+    pub balance_vault: &'a InterfaceAccount<'info, TokenAccount>,
+    pub destination: &'a InterfaceAccount<'info, TokenAccount>,
+    pub token_program: &'a Interface<'info, TokenInterface>,
+}
+
+/// Validates the optional token accounts of a player balance transfer.
+/// Returns `None` for SOL balances.
+pub fn balance_transfer<'a, 'info>(
+    player_account: &PlayerAccount,
+    mint: &'a Option<InterfaceAccount<'info, Mint>>,
+    balance_vault: &'a Option<InterfaceAccount<'info, TokenAccount>>,
+    destination: &'a Option<InterfaceAccount<'info, TokenAccount>>,
+    token_program: &'a Option<Interface<'info, TokenInterface>>,
+    destination_owner: &Pubkey,
+) -> Result<Option<BalanceTransfer<'a, 'info>>> {
+    if player_account.payment_mint == Pubkey::default() {
+        return Ok(None);
+    }
+
+    let (Some(mint), Some(balance_vault), Some(destination), Some(token_program)) =
+        (mint, balance_vault, destination, token_program)
+    else {
+        return err!(LottoError::TokenAccountsMissing);
+    };
+
+    require_keys_eq!(
+        mint.key(),
+        player_account.payment_mint,
+        LottoError::TokenAccountMismatch
+    );
+    require_keys_eq!(
+        balance_vault.key(),
+        player_account.balance_vault,
+        LottoError::TokenAccountMismatch
+    );
+    require_keys_eq!(
+        destination.mint,
+        player_account.payment_mint,
+        LottoError::TokenAccountMismatch
+    );
+    require_keys_eq!(
+        destination.owner,
+        *destination_owner,
+        LottoError::TokenAccountMismatch
+    );
+
+    Ok(Some(BalanceTransfer {
+        mint,
+        balance_vault,
+        destination,
+        token_program,
+    }))
+}
+
+/// Pays `amount` from the owner into their player balance and returns what the
+/// balance actually received. For token balances `token.destination` is the
+/// owner's token account, as validated by `balance_transfer`.
+pub fn pay_into_balance<'info>(
+    player_account: &mut Account<'info, PlayerAccount>,
+    owner: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    token: Option<&BalanceTransfer<'_, 'info>>,
+    amount: u64,
+) -> Result<u64> {
+    let received = match token {
+        Some(accounts) => {
+            transfer_checked(
+                CpiContext::new(
+                    accounts.token_program.to_account_info(),
+                    TransferChecked {
+                        from: accounts.destination.to_account_info(),
+                        mint: accounts.mint.to_account_info(),
+                        to: accounts.balance_vault.to_account_info(),
+                        authority: owner.to_account_info(),
+                    },
+                ),
+                amount,
+                accounts.mint.decimals,
+            )?;
+
+            net_of_transfer_fee(accounts.mint, amount)?
+        }
+        None => {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: owner.to_account_info(),
+                        to: player_account.to_account_info(),
+                    },
+                ),
+                amount,
+            )?;
+
+            amount
+        }
+    };
+
+    player_account.balance += received;
+
+    Ok(received)
+}
+
+/// Debits `amount` from a player balance and sends it to `recipient` (SOL) or
+/// `token.destination`. Returns what the destination actually received.
+pub fn pay_from_balance<'info>(
+    player_account: &mut Account<'info, PlayerAccount>,
+    recipient: &AccountInfo<'info>,
+    token: Option<&BalanceTransfer<'_, 'info>>,
+    amount: u64,
+) -> Result<u64> {
+    player_account.balance = player_account
+        .balance
+        .checked_sub(amount)
+        .ok_or(LottoError::InsufficientBalance)?;
+
+    match token {
+        Some(accounts) => {
+            let bump = [player_account.bump];
+            let signer_seeds: &[&[&[u8]]] = &[&[
+                b"player_account",
+                player_account.owner.as_ref(),
+                player_account.payment_mint.as_ref(),
+                &bump,
+            ]];
+
+            transfer_checked(
+                CpiContext::new_with_signer(
+                    accounts.token_program.to_account_info(),
+                    TransferChecked {
+                        from: accounts.balance_vault.to_account_info(),
+                        mint: accounts.mint.to_account_info(),
+                        to: accounts.destination.to_account_info(),
+                        authority: player_account.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                amount,
+                accounts.mint.decimals,
+            )?;
+
+            net_of_transfer_fee(accounts.mint, amount)
+        }
+        // Checks the rent-exempt reserve itself
+        None => {
+            transfer_lamports(&player_account.to_account_info(), recipient, amount)?;
+
+            Ok(amount)
+        }
+    }
+}
+
+/// Pays `amount` for tickets of `lottery` out of a player balance, into the lottery
+/// vault for token lotteries. Returns what the lottery actually received.
+pub fn pay_tickets_from_balance<'info>(
+    lottery: &Account<'info, Lottery>,
+    player_account: &mut Account<'info, PlayerAccount>,
+    mint: &Option<InterfaceAccount<'info, Mint>>,
+    balance_vault: &Option<InterfaceAccount<'info, TokenAccount>>,
+    vault: &Option<InterfaceAccount<'info, TokenAccount>>,
+    token_program: &Option<Interface<'info, TokenInterface>>,
+    amount: u64,
+) -> Result<u64> {
+    let token = balance_transfer(
+        player_account,
+        mint,
+        balance_vault,
+        vault,
+        token_program,
+        &lottery.key(),
+    )?;
+
+    if let Some(accounts) = &token {
+        require_keys_eq!(
+            accounts.destination.key(),
+            lottery.payment_vault,
+            LottoError::TokenAccountMismatch
+        );
+    }
+
+    pay_from_balance(
+        player_account,
+        &lottery.to_account_info(),
+        token.as_ref(),
+        amount,
+    )
+}
+
+/// Pays a prize or refund to the bundle owner's wallet, or credits it to their
+/// player balance when `player_account` is given (`token.destination` must then
+/// be its balance vault).
+pub fn pay_to_owner<'info>(
+    lottery: &mut Account<'info, Lottery>,
+    owner: &AccountInfo<'info>,
+    player_account: Option<&mut Account<'info, PlayerAccount>>,
+    token: Option<&VaultTransfer<'_, 'info>>,
+    amount: u64,
+) -> Result<()> {
+    let Some(player_account) = player_account else {
+        return pay_from_lottery(lottery, owner, token, amount);
+    };
+
+    let received = match token {
+        Some(accounts) => {
+            require_keys_eq!(
+                accounts.destination.key(),
+                player_account.balance_vault,
+                LottoError::TokenAccountMismatch
+            );
+
+            net_of_transfer_fee(accounts.mint, amount)?
+        }
+        None => amount,
+    };
+
+    pay_from_lottery(lottery, &player_account.to_account_info(), token, amount)?;
+
+    player_account.balance += received;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// SlotHashes data holding `slots`, newest first, each hashed to `[slot as u8; 32]`.
+    fn slot_hashes_data(slots: &[u64]) -> Vec<u8> {
+        let mut data = (slots.len() as u64).to_le_bytes().to_vec();
+
+        for slot in slots {
+            data.extend_from_slice(&slot.to_le_bytes());
+            data.extend_from_slice(&[*slot as u8; 32]);
+        }
+
+        data
+    }
+
+    fn with_slot_hashes<T>(slots: &[u64], f: impl FnOnce(&AccountInfo) -> T) -> T {
+        let key = solana_sdk_ids::sysvar::slot_hashes::ID;
+        let owner = Pubkey::default();
+        let mut lamports = 0;
+        let mut data = slot_hashes_data(slots);
+        let info = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            0,
+        );
+
+        f(&info)
+    }
+
+    #[test]
+    fn slot_hash_reads_listed_slots_only() {
+        with_slot_hashes(&[105, 104, 102], |info| {
+            assert_eq!(slot_hash(info, 104).unwrap(), Some([104; 32]));
+            assert_eq!(slot_hash(info, 103).unwrap(), None);
+            assert_eq!(slot_hash(info, 90).unwrap(), None);
+        });
+    }
+
+    #[test]
+    fn draw_slot_expires_only_once_out_of_the_window() {
+        with_slot_hashes(&[105, 104, 103], |info| {
+            // Still revealable
+            assert!(!draw_slot_expired(info, 103, 106).unwrap());
+            // Not even available yet
+            assert!(!draw_slot_expired(info, 106, 106).unwrap());
+            // Dropped out of SlotHashes
+            assert!(draw_slot_expired(info, 100, 106).unwrap());
+        });
+    }
+
+    #[test]
+    fn reveal_requires_the_committed_secret() {
+        let secret = [7; 32];
+        let commitment = hash(&secret).to_bytes();
+
+        with_slot_hashes(&[105, 104], |info| {
+            assert_eq!(
+                reveal_with_slot_hash(info, 104, &commitment, &secret).unwrap(),
+                mix_slot_hash(&secret, &[104; 32])
+            );
+            assert!(reveal_with_slot_hash(info, 104, &commitment, &[8; 32]).is_err());
+            assert!(reveal_with_slot_hash(info, 90, &commitment, &secret).is_err());
+        });
+    }
+
+    #[test]
+    fn commit_reveal_codes_stay_in_range_and_distinct() {
+        let random_value = [3; 32];
+        let mut drawn: Vec<[u8; 8]> = Vec::new();
+
+        for draw_index in 0..4 {
+            let code = commit_reveal_winning_code(&random_value, draw_index, (0x10, 0x13), &drawn);
+            let value = u64::from_be_bytes(code);
+
+            assert!((0x10..=0x13).contains(&value));
+            assert!(!drawn.contains(&code));
+
+            drawn.push(code);
+        }
+
+        // Every code taken: draws may repeat rather than fail
+        let code = commit_reveal_winning_code(&random_value, 4, (0x10, 0x13), &drawn);
+        assert!(drawn.contains(&code));
+    }
+
+    /// Open SOL lottery over codes "0".."f", drawn by commit-reveal, nothing sold yet.
+    fn lottery() -> Lottery {
+        Lottery {
+            switchboard_feed_btc_block_decimal: Pubkey::default(),
+            tickets_sold: 0,
+            lamports_per_ticket: 1_000,
+            ticket_code_start_hex: "0".to_string(),
+            ticket_code_end_hex: "f".to_string(),
+            refunds_settled: 0,
+            open: true,
+            platform_fee_percentage: 2,
+            allow_shared_tickets: false,
+            bundle_count: 0,
+            prize_pool: 0,
+            prize_tiers: validate_prize_tiers(vec![]).unwrap(),
+            game_type: GameType::CodeMatch,
+            draws: build_draws(vec![]).unwrap(),
+            lottery_seed: "fixture".to_string(),
+            bump: 255,
+            payment_mint: Pubkey::default(),
+            payment_vault: Pubkey::default(),
+            ticket_revenue: 0,
+            platform_fee_withdrawn: false,
+            max_tickets_total: 16,
+            max_tickets_per_wallet: 0,
+            min_tickets_to_draw: 0,
+            cancelled: false,
+            seeded_prize: 0,
+            pot_lamports: 0,
+            referral_bps: 0,
+            referral_fees_accrued: 0,
+            referral_fees_claimed: 0,
+            series: Pubkey::default(),
+            round: 0,
+            sales_close_ts: 0,
+            claim_window: 0,
+            claim_deadline: 0,
+            prizes_swept: false,
+            rent_payer: Pubkey::default(),
+            ticket_registry: Pubkey::default(),
+            draw_commitment: [1; 32],
+            draw_slot: 0,
+            bundles_closed: 0,
+            prizes_carried_over: false,
+        }
+    }
+
+    fn raffle(tickets_sold: u32) -> Lottery {
+        Lottery {
+            game_type: GameType::Raffle,
+            tickets_sold,
+            ..lottery()
+        }
+    }
+
+    #[test]
+    fn raffle_draws_pick_distinct_tickets_while_there_are_enough() {
+        let lottery = raffle(3);
+        let range = commit_reveal_code_range(&lottery).unwrap();
+        let mut drawn: Vec<[u8; 8]> = Vec::new();
+
+        for draw_index in 0..3 {
+            let code = commit_reveal_winning_code(&[9; 32], draw_index, range, &drawn);
+            let index = raffle_winning_index(&code, lottery.tickets_sold);
+
+            // The drawn value is the ticket index itself
+            assert_eq!(index as u64, u64::from_be_bytes(code));
+            assert!(!drawn.contains(&code));
+
+            drawn.push(code);
+        }
+
+        // More draws than tickets: a ticket wins again
+        let code = commit_reveal_winning_code(&[9; 32], 3, range, &drawn);
+        assert!(drawn.contains(&code));
+    }
+
+    #[test]
+    fn raffle_without_tickets_is_cancelled_at_close() {
+        let mut empty = raffle(0);
+        assert!(commit_reveal_code_range(&empty).is_err());
+
+        close_sales(&mut empty, 10);
+        assert!(empty.cancelled);
+
+        let mut sold = raffle(1);
+        close_sales(&mut sold, 10);
+        assert!(!sold.cancelled);
+    }
+
+    fn tier(match_digits: u8, pool_percentage: u8) -> PrizeTier {
+        PrizeTier {
+            match_digits,
+            pool_percentage,
+        }
+    }
+
+    #[test]
+    fn prize_tiers_are_validated() {
+        let cases = [
+            (vec![tier(16, 70), tier(4, 20), tier(2, 10)], true),
+            (vec![tier(1, 100)], true),
+            // Not totalling 100
+            (vec![tier(16, 60), tier(4, 30)], false),
+            (vec![tier(16, 70), tier(4, 40)], false),
+            // Best tier first, each one strictly easier
+            (vec![tier(4, 30), tier(16, 70)], false),
+            (vec![tier(4, 50), tier(4, 50)], false),
+            // Out of the code digits
+            (vec![tier(16, 70), tier(0, 30)], false),
+            (vec![tier(17, 100)], false),
+            // More than MAX_PRIZE_TIERS
+            (
+                vec![
+                    tier(16, 20),
+                    tier(8, 20),
+                    tier(4, 20),
+                    tier(2, 20),
+                    tier(1, 20),
+                ],
+                false,
+            ),
+        ];
+
+        for (i, (tiers, valid)) in cases.into_iter().enumerate() {
+            match validate_prize_tiers(tiers) {
+                Ok(_) => assert!(valid, "case {i}"),
+                Err(e) => {
+                    assert!(!valid, "case {i}");
+                    assert_eq!(e, LottoError::InvalidPrizeTable.into());
+                }
+            }
+        }
+
+        // No table is a single jackpot
+        let jackpot = validate_prize_tiers(vec![]).unwrap();
+        assert_eq!(jackpot.len(), 1);
+        assert_eq!(jackpot[0].match_digits, TICKET_CODE_DIGITS);
+        assert_eq!(jackpot[0].pool_percentage, 100);
+    }
+
+    #[test]
+    fn tickets_score_the_best_tier_they_reach() {
+        let tiers = validate_prize_tiers(vec![tier(16, 70), tier(4, 20), tier(2, 10)]).unwrap();
+        let winning_code = 0x1234u64.to_be_bytes();
+
+        let cases: [(u64, Option<usize>); 6] = [
+            (0x1234, Some(0)),
+            // Exactly on the 4 digit boundary
+            (0xa1234, Some(1)),
+            // Between tiers: the easier one
+            (0x0234, Some(2)),
+            // Exactly on the 2 digit boundary
+            (0x0034, Some(2)),
+            (0x0004, None),
+            (0x1235, None),
+        ];
+
+        for (ticket, expected) in cases {
+            assert_eq!(
+                score_ticket(&ticket.to_be_bytes(), &winning_code, &tiers),
+                expected,
+                "{ticket:x}"
+            );
+        }
+
+        let mut tickets = [[0; 8]; 100];
+        for (slot, (ticket, _)) in tickets.iter_mut().zip(cases) {
+            *slot = ticket.to_be_bytes();
+        }
+
+        let bundle = TransactionBundle {
+            lottery_pda: Pubkey::default(),
+            owner: Pubkey::default(),
+            tickets,
+            refunded: false,
+            ticket_count: cases.len() as u16,
+            first_ticket_index: 0,
+            draws_tallied: 0,
+            tier_winning_tickets: Default::default(),
+            draws_rewarded: 0,
+            rent_payer: Pubkey::default(),
+            paid_from_balance: false,
+        };
+
+        assert_eq!(tally_bundle(&bundle, &winning_code, &tiers), [1, 1, 2, 0]);
+    }
+
+    #[test]
+    fn draw_tables_are_validated() {
+        let cases: [(Vec<u8>, bool); 7] = [
+            (vec![60, 40], true),
+            (vec![20; MAX_DRAWS], true),
+            // More than MAX_DRAWS
+            (vec![10; MAX_DRAWS + 1], false),
+            (vec![16, 16, 16, 16, 16, 10, 10], false),
+            // Not totalling 100, or paying nothing
+            (vec![60, 30], false),
+            (vec![60, 50], false),
+            (vec![100, 0], false),
+        ];
+
+        for (i, (percentages, valid)) in cases.into_iter().enumerate() {
+            match build_draws(percentages) {
+                Ok(_) => assert!(valid, "case {i}"),
+                Err(e) => {
+                    assert!(!valid, "case {i}");
+                    assert_eq!(e, LottoError::InvalidDrawTable.into());
+                }
+            }
+        }
+
+        // No table is a single draw paying the whole pool
+        let draws = build_draws(vec![]).unwrap();
+        assert_eq!(draws.len(), 1);
+        assert_eq!(draws[0].pool_percentage, 100);
+        assert!(!draws[0].drawn);
+    }
+
+    fn template(referral_bps: u16) -> LotteryTemplate {
+        LotteryTemplate {
+            lamports_per_ticket: 1_000,
+            ticket_code_start_hex: "0".to_string(),
+            ticket_code_end_hex: "f".to_string(),
+            platform_fee_percentage: 10,
+            allow_shared_tickets: true,
+            prize_tiers: vec![],
+            game_type: GameType::CodeMatch,
+            draw_percentages: vec![],
+            max_tickets_total: 0,
+            max_tickets_per_wallet: 0,
+            min_tickets_to_draw: 0,
+            referral_bps,
+            claim_window: 0,
+        }
+    }
+
+    #[test]
+    fn referral_fee_rounds_down_within_the_platform_fee() {
+        // (platform fee %, referral bps, purchase, referral fee)
+        let cases = [
+            (10, 5_000, 1_000, 50),
+            // 1_999 * 10% = 199.9 of fee, half of it 99.95
+            (10, 5_000, 1_999, 99),
+            (2, 2_500, 199, 0),
+            (2, 2_500, 200, 1),
+            (2, 0, 1_000_000, 0),
+            // The whole platform fee, never more
+            (10, 10_000, 1_999, 199),
+            (100, 10_000, u64::MAX, u64::MAX),
+        ];
+
+        for (fee, referral_bps, amount, expected) in cases {
+            let lottery = Lottery {
+                platform_fee_percentage: fee,
+                referral_bps,
+                ..lottery()
+            };
+
+            assert_eq!(
+                lottery.referral_fee(amount),
+                expected,
+                "{amount} at {referral_bps} bps"
+            );
+            assert!(expected <= (amount as u128 * fee as u128 / 100) as u64);
+        }
+
+        // A referral share above the platform fee is refused
+        let mut lottery = lottery();
+        assert!(apply_template(&mut lottery, &template(10_000)).is_ok());
+        assert_eq!(
+            apply_template(&mut lottery, &template(10_001)).unwrap_err(),
+            LottoError::InvalidReferralShare.into()
+        );
+    }
+
+    #[test]
+    fn referrals_accrue_to_a_matching_referrer_only() {
+        let lottery_key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let referrer = Pubkey::new_unique();
+        let mut lottery = Lottery {
+            platform_fee_percentage: 10,
+            referral_bps: 5_000,
+            ..lottery()
+        };
+        let mut referral = ReferralRewards {
+            lottery: Pubkey::default(),
+            referrer: Pubkey::default(),
+            accrued: 0,
+            claimed: 0,
+            bump: 0,
+        };
+
+        let accrued = accrue_referral(
+            lottery_key,
+            &mut lottery,
+            Some(&mut referral),
+            Some(254),
+            Some(referrer),
+            &owner,
+            1_999,
+        )
+        .unwrap();
+
+        assert_eq!(accrued, Some(99));
+        assert_eq!(referral.lottery, lottery_key);
+        assert_eq!(referral.referrer, referrer);
+        assert_eq!(referral.bump, 254);
+        assert_eq!(referral.accrued, 99);
+        assert_eq!(lottery.referral_fees_accrued, 99);
+
+        // No referrer, no referral account
+        assert_eq!(
+            accrue_referral(lottery_key, &mut lottery, None, None, None, &owner, 1_000).unwrap(),
+            None
+        );
+
+        // A referrer without its account, an account without a referrer, or a self-referral
+        let rejected = [
+            accrue_referral(
+                lottery_key,
+                &mut lottery,
+                None,
+                None,
+                Some(referrer),
+                &owner,
+                1_000,
+            ),
+            accrue_referral(
+                lottery_key,
+                &mut lottery,
+                Some(&mut referral),
+                Some(254),
+                None,
+                &owner,
+                1_000,
+            ),
+            accrue_referral(
+                lottery_key,
+                &mut lottery,
+                Some(&mut referral),
+                Some(254),
+                Some(owner),
+                &owner,
+                1_000,
+            ),
+        ];
+
+        for result in rejected {
+            assert_eq!(result.unwrap_err(), LottoError::InvalidReferrer.into());
+        }
+
+        assert_eq!(referral.accrued, 99);
+        assert_eq!(lottery.referral_fees_accrued, 99);
+    }
+
+    #[test]
+    fn quick_picks_are_deterministic_full_width_codes() {
+        let range = ticket_code_range("8000000000000000", "ffffffffffffffff").unwrap();
+        let codes = quick_pick_codes(&[3; 32], 10, range, true, None).unwrap();
+
+        assert_eq!(
+            codes,
+            quick_pick_codes(&[3; 32], 10, range, true, None).unwrap()
+        );
+        assert_ne!(
+            codes,
+            quick_pick_codes(&[4; 32], 10, range, true, None).unwrap()
+        );
+
+        for code in &codes {
+            let hex = ticket_code_to_hex(code);
+
+            assert_eq!(hex.len(), TICKET_CODE_DIGITS as usize);
+            assert_eq!(ticket_code_from_hex(&hex).unwrap(), *code);
+        }
+
+        // The whole u64 range too
+        let codes = quick_pick_codes(&[3; 32], 10, (0, u64::MAX), true, None).unwrap();
+        assert_eq!(codes.len(), 10);
+    }
+
+    #[test]
+    fn unique_quick_picks_skip_sold_and_picked_codes() {
+        let range = ticket_code_range("0", "f").unwrap();
+        let mut registry: TicketRegistry = bytemuck::Zeroable::zeroed();
+
+        assert_eq!(
+            quick_pick_codes(&[5; 32], 4, range, false, None).unwrap_err(),
+            LottoError::TicketRegistryRequired.into()
+        );
+
+        // Every code of the range, once each
+        let codes = quick_pick_codes(&[5; 32], 16, range, false, Some(&registry)).unwrap();
+        assert!(!has_duplicate_codes(&codes));
+        assert!(codes.iter().all(|code| u64::from_be_bytes(*code) <= 0xf));
+
+        for offset in 0..12 {
+            registry.mark_sold(offset);
+        }
+
+        let codes = quick_pick_codes(&[5; 32], 4, range, false, Some(&registry)).unwrap();
+        let mut values: Vec<u64> = codes.iter().map(|code| u64::from_be_bytes(*code)).collect();
+        values.sort();
+        assert_eq!(values, vec![12, 13, 14, 15]);
+
+        assert_eq!(
+            quick_pick_codes(&[5; 32], 5, range, false, Some(&registry)).unwrap_err(),
+            LottoError::LotterySoldOut.into()
+        );
+    }
+}
@@ -3,6 +3,7 @@ import { CtLottoAnchor } from "../target/types/ct_lotto_anchor";
 import {
   LAMPORTS_PER_SOL,
   PublicKey,
+  SYSVAR_SLOT_HASHES_PUBKEY,
   SystemProgram,
   Transaction,
   sendAndConfirmTransaction,
@@ -10,36 +11,41 @@ import {
 
 import { nanoid } from "nanoid";
 import { sha256 } from "@noble/hashes/sha2";
+import { expect } from "chai";
 
+// Ticket codes are stored as the big-endian bytes of their hex value,
+// e.g. "1a2b3c" -> [0, 0, 0, 0, 0, 0x1a, 0x2b, 0x3c] (see ticket_code_from_hex)
 const hexToU8_8 = (hex) => {
   // Remove 0x if present
   hex = hex.replace(/^0x/, "");
 
-  // Ensure even-length hex (required for parsing)
-  if (hex.length % 2 !== 0) {
-    hex = "0" + hex;
+  if (hex.length === 0 || hex.length > 16 || !/^[0-9a-fA-F]+$/.test(hex)) {
+    throw new Error("Invalid hex string");
   }
 
+  // Left-pad to 16 digits = 8 bytes
+  hex = hex.padStart(16, "0");
+
   const bytes = [];
 
   for (let i = 0; i < hex.length; i += 2) {
-    const byte = parseInt(hex.substring(i, i + 2), 16);
-
-    if (isNaN(byte)) {
-      throw new Error("Invalid hex string");
-    }
-
-    bytes.push(byte);
+    bytes.push(parseInt(hex.substring(i, i + 2), 16));
   }
 
-  // Pad to 8 bytes
-  while (bytes.length < 8) {
-    bytes.push(0);
+  return bytes;
+};
+
+// Resolves once the promise fails with the given program error
+const expectError = async (promise: Promise<unknown>, code: string) => {
+  try {
+    await promise;
+  } catch (err) {
+    expect(err.error?.errorCode?.code ?? String(err)).to.contain(code);
+    return;
   }
 
-  // Ensure exactly 8 bytes
-  return bytes.slice(0, 8);
-}
+  expect.fail(`expected ${code}`);
+};
 
 describe("ct-lotto-anchor", () => {
   anchor.setProvider(anchor.AnchorProvider.env());
@@ -52,9 +58,30 @@ describe("ct-lotto-anchor", () => {
     lamportsPerTicket: LAMPORTS_PER_SOL * 0.001,
     ticket_code_start_hex: "0",
     ticket_code_end_hex: "f",
-    lotteryNumbersToPurchase: ["0", "1", "3", "f"],
-    winningNumber: "f",
+    // every code, so the commit-reveal draw always has a winner
+    lotteryNumbersToPurchase: [...Array(16).keys()].map((n) => n.toString(16)),
     platformFeePercentage: 2,
+    allowSharedTickets: false,
+    // empty table = single jackpot tier paying 100% for a full match
+    prizeTiers: [] as { matchDigits: number; poolPercentage: number }[],
+    gameType: { codeMatch: {} },
+    // empty = a single draw paying the whole prize pool
+    drawPercentages: [] as number[],
+    // 0 = capped by the code space ("0".."f" = 16 tickets)
+    maxTicketsTotal: 0,
+    // 0 = no per-wallet cap
+    maxTicketsPerWallet: 0,
+    // closing sales below this many tickets cancels the lottery
+    minTicketsToDraw: 1,
+    // guaranteed prize added to the pool, no fee taken on it
+    seededPrize: LAMPORTS_PER_SOL * 0.001,
+    // share of the platform fee paid to referrers, in basis points
+    referralBps: 2000,
+    // seconds winners have to claim once tallied, 0 = no deadline
+    claimWindow: 0,
+    // commit-reveal draw: hash(secret) committed at creation, secret revealed
+    // after sales close (no Switchboard feed needed)
+    drawSecret: sha256(nanoid()),
   };
 
   // PDAs
@@ -70,13 +97,15 @@ describe("ct-lotto-anchor", () => {
     )[0],
 
     admin: provider.wallet.publicKey,
-
-    sbFeedresult: new PublicKey(
-      "Bj1QJT7JM2jp3AbPB74vEpvUmik1KD6xZbC9rbQEDTft"
-    ),
   };
 
-  let purchaseSignature: string = "4doUjvgFCErsnZZwXDx3MA585DAdvWbynyYvWfzQg2b85ozq3udMPFmKnBppZooaoQo1pmefyPBwggMx8teNnzUo";
+  // sold codes of the lottery, which sells unique codes only through it
+  const ticketRegistry = PublicKey.findProgramAddressSync(
+    [Buffer.from("registry"), accounts.lottery.toBuffer()],
+    program.programId
+  )[0];
+
+  const purchaseReceiptId: string = nanoid();
   let bundlePda: PublicKey;
 
   it("Create configuration PDA", async () => {
@@ -85,6 +114,7 @@ describe("ct-lotto-anchor", () => {
       .accounts({
         configuration: accounts.configuration,
         admin: accounts.admin,
+        payer: accounts.admin,
       })
       .rpc();
 
@@ -98,47 +128,73 @@ describe("ct-lotto-anchor", () => {
         new anchor.BN(constants.lamportsPerTicket),
         constants.ticket_code_start_hex,
         constants.ticket_code_end_hex,
-        new anchor.BN(constants.platformFeePercentage)
+        new anchor.BN(constants.platformFeePercentage),
+        constants.allowSharedTickets,
+        constants.prizeTiers,
+        constants.gameType,
+        Buffer.from(constants.drawPercentages),
+        constants.maxTicketsTotal,
+        constants.maxTicketsPerWallet,
+        constants.minTicketsToDraw,
+        constants.referralBps,
+        new anchor.BN(constants.claimWindow),
+        Array.from(sha256(constants.drawSecret)) // draw commitment
       )
       .accounts({
         configuration: accounts.configuration,
         lottery: accounts.lottery,
         admin: accounts.admin,
-        switchboardFeedBtcBlockDecimal: accounts.sbFeedresult,
+        payer: accounts.admin,
+        // commit-reveal draw: no Switchboard feed
+        switchboardFeedBtcBlockDecimal: null,
+        // SOL lottery: no payment mint / token vault
+        paymentMint: null,
+        vault: null,
       })
       .rpc();
 
     console.log("Lottery created:", tx);
   });
 
-  it("Send SOL to lottery PDA", async () => {
-    const cost =
-      constants.lamportsPerTicket * constants.lotteryNumbersToPurchase.length;
-
-    const tx = new Transaction().add(
-      SystemProgram.transfer({
-        fromPubkey: accounts.admin,
-        toPubkey: accounts.lottery,
-        lamports: cost,
+  it("Create ticket registry (unique codes)", async () => {
+    const tx = await program.methods
+      .createTicketRegistry()
+      .accounts({
+        configuration: accounts.configuration,
+        lottery: accounts.lottery,
+        ticketRegistry,
+        admin: accounts.admin,
+        payer: accounts.admin,
       })
-    );
+      .rpc();
 
-    tx.feePayer = accounts.admin;
-    tx.recentBlockhash = (await provider.connection.getLatestBlockhash())
-      .blockhash;
+    console.log("Ticket registry created:", tx);
+  });
 
-    purchaseSignature = await sendAndConfirmTransaction(
-      provider.connection,
-      tx,
-      [provider.wallet.payer]
-    );
+  it("Seed a guaranteed prize", async () => {
+    const tx = await program.methods
+      .seedPrize(new anchor.BN(constants.seededPrize))
+      .accounts({
+        configuration: accounts.configuration,
+        lottery: accounts.lottery,
+        admin: accounts.admin,
+        // SOL lottery: no token accounts
+        paymentMint: null,
+        vault: null,
+        adminTokenAccount: null,
+        tokenProgram: null,
+      })
+      .rpc();
 
-    console.log("SOL transfer signature:", purchaseSignature);
+    console.log("Prize seeded:", tx);
   });
 
-  it("Create Transaction Bundle PDA (with numbers)", async () => {
-    // create tx_sig_hash (32 bytes)
-    const tx_sig_hash = sha256(purchaseSignature);
+  it("Buy tickets (payment + transaction bundle in one transaction)", async () => {
+    const cost =
+      constants.lamportsPerTicket * constants.lotteryNumbersToPurchase.length;
+
+    // bundle id: the payment signature is not known until the transaction is sent
+    const tx_sig_hash = sha256(purchaseReceiptId);
 
     bundlePda = PublicKey.findProgramAddressSync(
       [
@@ -149,22 +205,47 @@ describe("ct-lotto-anchor", () => {
       program.programId
     )[0];
 
-    const tx = await program.methods
+    const bundleIx = await program.methods
       .createTransactionBundle(
         constants.lotterySeed,
         Array.from(tx_sig_hash), // [u8; 32]
         accounts.admin, // owner
-        constants.lotteryNumbersToPurchase.map(hexToU8_8)
+        constants.lotteryNumbersToPurchase.map(hexToU8_8),
+        null // no referrer
       )
       .accounts({
         configuration: accounts.configuration,
         lottery: accounts.lottery,
         bundle: bundlePda,
+        ticketRegistry,
+        referral: null,
         admin: accounts.admin,
+        payer: accounts.admin,
       })
-      .rpc();
+      .instruction();
+
+    // the program checks the transfer right before the bundle instruction
+    const tx = new Transaction()
+      .add(
+        SystemProgram.transfer({
+          fromPubkey: accounts.admin,
+          toPubkey: accounts.lottery,
+          lamports: cost,
+        })
+      )
+      .add(bundleIx);
+
+    tx.feePayer = accounts.admin;
+    tx.recentBlockhash = (await provider.connection.getLatestBlockhash())
+      .blockhash;
+
+    const signature = await sendAndConfirmTransaction(
+      provider.connection,
+      tx,
+      [provider.wallet.payer]
+    );
 
-    console.log("Transaction Bundle created:", tx);
+    console.log("Transaction Bundle created:", signature);
     console.log("Bundle PDA:", bundlePda.toBase58());
   });
 
@@ -181,56 +262,115 @@ describe("ct-lotto-anchor", () => {
     console.log("Lottery closed:", tx);
   });
 
-  const winnerIndex = constants.lotteryNumbersToPurchase.indexOf(
-    constants.winningNumber
-  );
+  it("Reveal the draw secret", async () => {
+    // the draw mixes in the hash of the slot sales closed in, known once it has passed
+    const closeSlot = await provider.connection.getSlot();
+
+    while ((await provider.connection.getSlot()) <= closeSlot) {
+      await new Promise((resolve) => setTimeout(resolve, 400));
+    }
 
-  if (winnerIndex >= 0) {
-    it("Reward winner using bundle", async () => {
-      const tx = await program.methods
-        .rewardTransactionBundle(hexToU8_8(constants.winningNumber))
+    // only the committed secret reveals the draw
+    await expectError(
+      program.methods
+        .revealDraw(Array.from(sha256("not the secret")))
         .accounts({
           configuration: accounts.configuration,
           lottery: accounts.lottery,
-          bundle: bundlePda,
-          switchboardFeedBtcBlockDecimal: accounts.sbFeedresult,
-          owner: accounts.admin,
-          admin: accounts.admin
+          slotHashes: SYSVAR_SLOT_HASHES_PUBKEY,
+          admin: accounts.admin,
         })
-        .rpc();
-
-      console.log("Rewarded:", tx);
-    });
-  } else {
-    it("Refund all tickets (single group refund)", async () => {
-      const tx = await program.methods
-        .refundTransactionBundle(false)
+        .rpc(),
+      "InvalidSecret"
+    );
+
+    // while the slot hash is still available nobody can cancel the draw
+    await expectError(
+      program.methods
+        .cancelExpiredDraw()
         .accounts({
-          configuration: accounts.configuration,
           lottery: accounts.lottery,
-          bundle: bundlePda,
-          owner: accounts.admin,
-          admin: accounts.admin,
+          slotHashes: SYSVAR_SLOT_HASHES_PUBKEY,
         })
-        .rpc();
+        .rpc(),
+      "DrawSlotNotExpired"
+    );
 
-      console.log("Refunded:", tx);
-    });
-  }
+    const tx = await program.methods
+      .revealDraw(Array.from(constants.drawSecret))
+      .accounts({
+        configuration: accounts.configuration,
+        lottery: accounts.lottery,
+        slotHashes: SYSVAR_SLOT_HASHES_PUBKEY,
+        admin: accounts.admin,
+      })
+      .rpc();
+
+    const lottery = await program.account.lottery.fetch(accounts.lottery);
+
+    console.log("Winner drawn:", tx, lottery.draws[0].winningCode);
+  });
+
+  it("Tally bundle against winning code", async () => {
+    const tx = await program.methods
+      .tallyTransactionBundle()
+      .accounts({
+        configuration: accounts.configuration,
+        lottery: accounts.lottery,
+        bundle: bundlePda,
+        admin: accounts.admin,
+      })
+      .rpc();
+
+    console.log("Tallied:", tx);
+  });
+
+  it("Reward winner using bundle", async () => {
+    const tx = await program.methods
+      .rewardTransactionBundle(0)
+      .accounts({
+        configuration: accounts.configuration,
+        lottery: accounts.lottery,
+        bundle: bundlePda,
+        owner: accounts.admin,
+        admin: accounts.admin
+      })
+      .rpc();
+
+    console.log("Rewarded:", tx);
+  });
 
   it("Close Transaction Bundle PDA", async () => {
     const tx = await program.methods
       .closeTransactionBundle()
       .accounts({
         configuration: accounts.configuration,
+        lottery: accounts.lottery,
         bundle: bundlePda,
         admin: accounts.admin,
+        // the admin paid the bundle rent
+        rentPayer: accounts.admin,
       })
       .rpc();
 
     console.log("Bundle closed:", tx);
   });
 
+  it("Close ticket registry", async () => {
+    const tx = await program.methods
+      .closeTicketRegistry()
+      .accounts({
+        configuration: accounts.configuration,
+        lottery: accounts.lottery,
+        ticketRegistry,
+        rentPayer: accounts.admin,
+        admin: accounts.admin,
+      })
+      .rpc();
+
+    console.log("Ticket registry closed:", tx);
+  });
+
   it("Close Lottery PDA", async () => {
     const tx = await program.methods
       .closeLotteryPda()
@@ -238,6 +378,7 @@ describe("ct-lotto-anchor", () => {
         configuration: accounts.configuration,
         lottery: accounts.lottery,
         admin: accounts.admin,
+        rentPayer: accounts.admin,
       })
       .rpc();
 
@@ -250,6 +391,7 @@ describe("ct-lotto-anchor", () => {
       .accounts({
         configuration: accounts.configuration,
         admin: accounts.admin,
+        rentPayer: accounts.admin,
       })
       .rpc();
 
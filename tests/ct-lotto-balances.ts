@@ -0,0 +1,67 @@
+import { Keypair } from "@solana/web3.js";
+import { expect } from "chai";
+
+import {
+  TICKET_PRICE,
+  buyFromBalance,
+  createLottery,
+  depositBalance,
+  ensureConfiguration,
+  expectError,
+  fetchLottery,
+  fundedKeypair,
+  playerAccountPda,
+  program,
+  provider,
+  withdrawBalance,
+} from "./helpers";
+
+describe("ct-lotto-anchor player balances", () => {
+  before(ensureConfiguration);
+
+  const balanceOf = async (player: Keypair) =>
+    (
+      await program.account.playerAccount.fetch(
+        playerAccountPda(player.publicKey)
+      )
+    ).balance.toNumber();
+
+  it("pays tickets from the deposited balance", async () => {
+    const { seed, lottery } = await createLottery();
+    const player = await fundedKeypair();
+
+    await depositBalance(player, TICKET_PRICE * 3);
+    await buyFromBalance(seed, player, ["1", "2"]);
+
+    expect(await balanceOf(player)).to.equal(TICKET_PRICE);
+
+    const account = await fetchLottery(lottery);
+    expect(account.ticketsSold).to.equal(2);
+    expect(account.ticketRevenue.toNumber()).to.equal(TICKET_PRICE * 2);
+
+    await expectError(
+      buyFromBalance(seed, player, ["3", "4"]),
+      "InsufficientBalance"
+    );
+  });
+
+  it("withdraws what is left of the balance, and no more", async () => {
+    const player = await fundedKeypair();
+
+    await depositBalance(player, TICKET_PRICE * 2);
+
+    await expectError(
+      withdrawBalance(player, TICKET_PRICE * 3),
+      "InsufficientBalance"
+    );
+
+    const walletBefore = await provider.connection.getBalance(player.publicKey);
+
+    await withdrawBalance(player, TICKET_PRICE * 2);
+
+    expect(await balanceOf(player)).to.equal(0);
+    expect(await provider.connection.getBalance(player.publicKey)).to.be.greaterThan(
+      walletBefore
+    );
+  });
+});
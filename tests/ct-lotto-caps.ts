@@ -0,0 +1,41 @@
+import { expect } from "chai";
+
+import {
+  buyTickets,
+  createLottery,
+  ensureConfiguration,
+  expectError,
+  fetchLottery,
+  fundedKeypair,
+} from "./helpers";
+
+describe("ct-lotto-anchor ticket caps", () => {
+  before(ensureConfiguration);
+
+  it("caps the tickets of each wallet across its bundles", async () => {
+    const { seed } = await createLottery({ maxTicketsPerWallet: 2 });
+    const owner = await fundedKeypair();
+
+    await buyTickets(seed, ["1", "2"], { owner });
+
+    await expectError(
+      buyTickets(seed, ["3"], { owner }),
+      "WalletTicketCapExceeded"
+    );
+
+    // other wallets keep their own allowance
+    await buyTickets(seed, ["3"]);
+  });
+
+  it("stops selling once the lottery is sold out", async () => {
+    const { seed, lottery } = await createLottery({ maxTicketsTotal: 3 });
+
+    await buyTickets(seed, ["1", "2"]);
+
+    await expectError(buyTickets(seed, ["3", "4"]), "LotterySoldOut");
+
+    await buyTickets(seed, ["3"]);
+
+    expect((await fetchLottery(lottery)).ticketsSold).to.equal(3);
+  });
+});
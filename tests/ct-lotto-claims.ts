@@ -0,0 +1,130 @@
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+
+import {
+  admin,
+  buyTickets,
+  closeAndReveal,
+  configurationPda,
+  createLottery,
+  ensureConfiguration,
+  expectError,
+  fetchLottery,
+  fundedKeypair,
+  program,
+  provider,
+  sleep,
+  tally,
+} from "./helpers";
+
+const ALL_CODES = [...Array(16).keys()].map((n) => n.toString(16));
+
+describe("ct-lotto-anchor claim window", () => {
+  before(ensureConfiguration);
+
+  const claim = (lottery: PublicKey, bundle: PublicKey, owner: Keypair) =>
+    program.methods
+      .claimPrize(0)
+      .accounts({
+        lottery,
+        bundle,
+        owner: owner.publicKey,
+        playerAccount: null,
+        paymentMint: null,
+        vault: null,
+        ownerTokenAccount: null,
+        tokenProgram: null,
+      })
+      .signers([owner])
+      .rpc();
+
+  const sweep = (lottery: PublicKey) =>
+    program.methods
+      .sweepUnclaimedPrizes()
+      .accounts({
+        configuration: configurationPda,
+        lottery,
+        rollover: null,
+        treasury: admin,
+        paymentMint: null,
+        vault: null,
+        treasuryTokenAccount: null,
+        tokenProgram: null,
+      })
+      .rpc();
+
+  it("opens once, when the last draw is fully tallied", async () => {
+    const claimWindow = 3600;
+    const { seed, lottery, secret } = await createLottery({
+      drawPercentages: [50, 50],
+      claimWindow,
+    });
+
+    const bundle = await buyTickets(seed, ALL_CODES);
+    await closeAndReveal(lottery, secret);
+
+    // winners of the first draw are known, but the second draw is not tallied
+    await tally(lottery, bundle);
+    expect((await fetchLottery(lottery)).claimDeadline.toNumber()).to.equal(0);
+
+    await tally(lottery, bundle);
+
+    const { claimDeadline } = await fetchLottery(lottery);
+    const now = Math.floor(Date.now() / 1000);
+
+    expect(claimDeadline.toNumber()).to.be.within(
+      now + claimWindow - 120,
+      now + claimWindow + 120
+    );
+  });
+
+  it("lets the winner claim their prize within the window", async () => {
+    const { seed, lottery, secret } = await createLottery({
+      claimWindow: 3600,
+    });
+    const owner = await fundedKeypair();
+
+    const bundle = await buyTickets(seed, ALL_CODES, { owner });
+    await closeAndReveal(lottery, secret);
+    await tally(lottery, bundle);
+
+    // nothing is left to sweep before the deadline
+    await expectError(sweep(lottery), "ClaimWindowOpen");
+
+    const { prizePool } = await fetchLottery(lottery);
+    const balanceBefore = await provider.connection.getBalance(owner.publicKey);
+
+    await claim(lottery, bundle, owner);
+
+    expect(await provider.connection.getBalance(owner.publicKey)).to.equal(
+      balanceBefore + prizePool.toNumber()
+    );
+    await expectError(claim(lottery, bundle, owner), "DuplicateRequest");
+  });
+
+  it("sweeps unclaimed prizes to the treasury once the window closes", async () => {
+    const { seed, lottery, secret } = await createLottery({ claimWindow: 1 });
+    const owner = await fundedKeypair();
+
+    const bundle = await buyTickets(seed, ALL_CODES, { owner });
+    await closeAndReveal(lottery, secret);
+    await tally(lottery, bundle);
+
+    await sleep(5000);
+
+    await expectError(claim(lottery, bundle, owner), "ClaimDeadlinePassed");
+
+    const { prizePool } = await fetchLottery(lottery);
+    const treasuryBefore = await provider.connection.getBalance(admin);
+
+    await sweep(lottery);
+
+    expect((await fetchLottery(lottery)).prizesSwept).to.be.true;
+    // the admin pays the fee of the sweep out of the prizes it receives
+    expect(await provider.connection.getBalance(admin)).to.be.greaterThan(
+      treasuryBefore + prizePool.toNumber() - 10_000
+    );
+
+    await expectError(sweep(lottery), "DuplicateRequest");
+  });
+});
@@ -0,0 +1,118 @@
+import { PublicKey, SYSVAR_SLOT_HASHES_PUBKEY } from "@solana/web3.js";
+import { expect } from "chai";
+
+import {
+  admin,
+  buyTickets,
+  closeAndReveal,
+  closeSales,
+  configurationPda,
+  createLottery,
+  ensureConfiguration,
+  expectError,
+  fetchLottery,
+  program,
+  tally,
+  waitForSlotAfter,
+} from "./helpers";
+
+const codes = (count: number) =>
+  [...Array(count).keys()].map((n) => n.toString(16));
+
+describe("ct-lotto-anchor draws", () => {
+  before(ensureConfiguration);
+
+  const reveal = (lottery: PublicKey, secret: Uint8Array) =>
+    program.methods
+      .revealDraw(Array.from(secret))
+      .accounts({
+        configuration: configurationPda,
+        lottery,
+        slotHashes: SYSVAR_SLOT_HASHES_PUBKEY,
+        admin,
+      })
+      .rpc();
+
+  it("closes sales once, so the draw slot cannot be moved", async () => {
+    const { seed, lottery } = await createLottery();
+
+    await buyTickets(seed, ["1"]);
+    await closeSales(lottery);
+
+    const { drawSlot } = await fetchLottery(lottery);
+    await waitForSlotAfter(drawSlot.toNumber());
+
+    await expectError(closeSales(lottery), "LotteryClosed");
+    expect((await fetchLottery(lottery)).drawSlot.toNumber()).to.equal(
+      drawSlot.toNumber()
+    );
+  });
+
+  it("reveals only once sales are closed, and only once", async () => {
+    const { seed, lottery, secret } = await createLottery();
+
+    await buyTickets(seed, ["1"]);
+
+    await expectError(reveal(lottery, secret), "LotteryStillOpen");
+
+    const { draws } = await closeAndReveal(lottery, secret);
+    expect(draws[0].drawn).to.be.true;
+
+    await expectError(reveal(lottery, secret), "AllDrawsCompleted");
+  });
+
+  it("counts each ticket in the strictest prize tier it matches", async () => {
+    const { seed, lottery, secret } = await createLottery({
+      endHex: "ff",
+      prizeTiers: [
+        { matchDigits: 2, poolPercentage: 60 },
+        { matchDigits: 1, poolPercentage: 40 },
+      ],
+    });
+
+    // every code of "0".."ff", at most 100 tickets per bundle
+    const all = codes(256);
+    const bundles = [];
+
+    for (let i = 0; i < all.length; i += 100) {
+      bundles.push(await buyTickets(seed, all.slice(i, i + 100)));
+    }
+
+    await closeAndReveal(lottery, secret);
+
+    for (const bundle of bundles) {
+      await tally(lottery, bundle);
+    }
+
+    const { draws } = await fetchLottery(lottery);
+
+    // the drawn code, and the 15 other codes sharing its last digit
+    expect(draws[0].tierWinningTickets[0]).to.equal(1);
+    expect(draws[0].tierWinningTickets[1]).to.equal(15);
+  });
+
+  it("splits the pool over sequential draws of distinct codes", async () => {
+    const { seed, lottery, secret } = await createLottery({
+      drawPercentages: [60, 40],
+    });
+
+    const bundle = await buyTickets(seed, codes(16));
+    const { draws, prizePool } = await closeAndReveal(lottery, secret);
+
+    expect(draws.map((d) => d.poolPercentage)).to.deep.equal([60, 40]);
+    expect(draws.every((d) => d.drawn)).to.be.true;
+    expect(draws[0].winningCode).to.not.deep.equal(draws[1].winningCode);
+    expect(prizePool.toNumber()).to.be.greaterThan(0);
+
+    // one tally per draw
+    await tally(lottery, bundle);
+    await tally(lottery, bundle);
+
+    const tallied = await fetchLottery(lottery);
+
+    for (const draw of tallied.draws) {
+      expect(draw.bundlesTallied).to.equal(1);
+      expect(draw.tierWinningTickets[0]).to.equal(1);
+    }
+  });
+});
@@ -0,0 +1,74 @@
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+
+import {
+  admin,
+  configurationPda,
+  ensureConfiguration,
+  expectError,
+  program,
+  provider,
+} from "./helpers";
+
+// The admin role moves in two steps, so it cannot be handed to a key nobody controls
+describe("ct-lotto-anchor admin handover", () => {
+  before(ensureConfiguration);
+
+  const propose = (newAdmin: PublicKey, signer?: Keypair) =>
+    program.methods
+      .proposeAdmin(newAdmin)
+      .accounts({
+        configuration: configurationPda,
+        admin: signer?.publicKey ?? admin,
+      })
+      .signers(signer ? [signer] : [])
+      .rpc();
+
+  const accept = (signer?: Keypair) =>
+    program.methods
+      .acceptAdmin()
+      .accounts({
+        configuration: configurationPda,
+        pendingAdmin: signer?.publicKey ?? admin,
+      })
+      .signers(signer ? [signer] : [])
+      .rpc();
+
+  it("hands the admin role to the proposed key only", async () => {
+    const newAdmin = Keypair.generate();
+    const stranger = Keypair.generate();
+
+    await expectError(propose(newAdmin.publicKey, stranger), "AdminOnlyAction");
+
+    await propose(newAdmin.publicKey);
+
+    // proposing alone changes nothing
+    let configuration = await program.account.configuration.fetch(
+      configurationPda
+    );
+    expect(configuration.admin.equals(admin)).to.be.true;
+    expect(configuration.pendingAdmin.equals(newAdmin.publicKey)).to.be.true;
+
+    await expectError(accept(stranger), "PendingAdminOnly");
+
+    await accept(newAdmin);
+
+    configuration = await program.account.configuration.fetch(
+      configurationPda
+    );
+    expect(configuration.admin.equals(newAdmin.publicKey)).to.be.true;
+    expect(configuration.pendingAdmin.equals(PublicKey.default)).to.be.true;
+
+    // the previous admin lost the role
+    await expectError(propose(stranger.publicKey), "AdminOnlyAction");
+
+    // hand it back for the other test files
+    await propose(admin, newAdmin);
+    await accept();
+
+    configuration = await program.account.configuration.fetch(
+      configurationPda
+    );
+    expect(configuration.admin.equals(provider.wallet.publicKey)).to.be.true;
+  });
+});
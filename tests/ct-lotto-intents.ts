@@ -0,0 +1,191 @@
+import * as anchor from "@coral-xyz/anchor";
+import {
+  Ed25519Program,
+  Keypair,
+  PublicKey,
+  Transaction,
+  sendAndConfirmTransaction,
+} from "@solana/web3.js";
+
+import { sha256 } from "@noble/hashes/sha2";
+import { expect } from "chai";
+
+import {
+  TICKET_PRICE,
+  admin,
+  bundlePda,
+  createLottery,
+  depositBalance,
+  ensureConfiguration,
+  expectError,
+  fetchLottery,
+  fundedKeypair,
+  hexToU8_8,
+  playerAccountPda,
+  playerTicketsPda,
+  program,
+  provider,
+  ticketRegistryPda,
+} from "./helpers";
+
+type Intent = {
+  lottery: PublicKey;
+  owner: PublicKey;
+  tickets: number[][];
+  maxPrice: anchor.BN;
+  expiry: anchor.BN;
+  nonce: anchor.BN;
+  referrer: PublicKey | null;
+};
+
+// Borsh encoding of `PurchaseIntent`, the message the player signs
+const intentMessage = (intent: Intent) => {
+  const count = Buffer.alloc(4);
+  count.writeUInt32LE(intent.tickets.length);
+
+  return Buffer.concat([
+    intent.lottery.toBuffer(),
+    intent.owner.toBuffer(),
+    count,
+    Buffer.from(intent.tickets.flat()),
+    intent.maxPrice.toArrayLike(Buffer, "le", 8),
+    intent.expiry.toTwos(64).toArrayLike(Buffer, "le", 8),
+    intent.nonce.toArrayLike(Buffer, "le", 8),
+    intent.referrer
+      ? Buffer.concat([Buffer.from([1]), intent.referrer.toBuffer()])
+      : Buffer.from([0]),
+  ]);
+};
+
+// A player signs an intent off-chain; any relayer submits it, paying the fees
+describe("ct-lotto-anchor purchase intents", () => {
+  before(ensureConfiguration);
+
+  const signIntent = (
+    player: Keypair,
+    lottery: PublicKey,
+    codes: string[],
+    nonce: number
+  ): Intent => ({
+    lottery,
+    owner: player.publicKey,
+    tickets: codes.map(hexToU8_8),
+    maxPrice: new anchor.BN(TICKET_PRICE * codes.length),
+    expiry: new anchor.BN(Math.floor(Date.now() / 1000) + 600),
+    nonce: new anchor.BN(nonce),
+    referrer: null,
+  });
+
+  const relay = async (seed: string, player: Keypair, intent: Intent) => {
+    const message = intentMessage(intent);
+    const txSigHash = sha256(message);
+
+    const purchaseIx = await program.methods
+      .purchaseWithIntent(seed, Array.from(txSigHash), intent)
+      .accounts({
+        lottery: intent.lottery,
+        bundle: bundlePda(seed, txSigHash),
+        ticketRegistry: ticketRegistryPda(intent.lottery),
+        playerTickets: playerTicketsPda(intent.lottery, intent.owner),
+        referral: null,
+        playerAccount: playerAccountPda(intent.owner),
+        payer: admin,
+        paymentMint: null,
+        balanceVault: null,
+        vault: null,
+        tokenProgram: null,
+      })
+      .instruction();
+
+    // the signature check must come right before the purchase
+    return sendAndConfirmTransaction(
+      provider.connection,
+      new Transaction()
+        .add(
+          Ed25519Program.createInstructionWithPrivateKey({
+            privateKey: player.secretKey,
+            message,
+          })
+        )
+        .add(purchaseIx),
+      [provider.wallet.payer]
+    );
+  };
+
+  it("buys the signed tickets from the player's balance", async () => {
+    const { seed, lottery } = await createLottery();
+    const player = await fundedKeypair();
+
+    await depositBalance(player, TICKET_PRICE * 2);
+    await relay(seed, player, signIntent(player, lottery, ["1", "2"], 0));
+
+    expect((await fetchLottery(lottery)).ticketsSold).to.equal(2);
+
+    const { balance, nonce } = await program.account.playerAccount.fetch(
+      playerAccountPda(player.publicKey)
+    );
+    expect(balance.toNumber()).to.equal(0);
+    expect(nonce.toNumber()).to.equal(1);
+  });
+
+  it("accepts each nonce once, so intents cannot be replayed", async () => {
+    const { seed, lottery } = await createLottery();
+    const player = await fundedKeypair();
+
+    await depositBalance(player, TICKET_PRICE * 2);
+    await relay(seed, player, signIntent(player, lottery, ["1"], 0));
+
+    await expectError(
+      relay(seed, player, signIntent(player, lottery, ["2"], 0)),
+      "IntentNonceMismatch"
+    );
+  });
+
+  it("rejects an intent altered after signing", async () => {
+    const { seed, lottery } = await createLottery();
+    const player = await fundedKeypair();
+
+    await depositBalance(player, TICKET_PRICE * 2);
+
+    const intent = signIntent(player, lottery, ["1"], 0);
+    const message = intentMessage(intent);
+    const txSigHash = sha256(message);
+
+    // the signed message buys "1", the instruction asks for "2"
+    const purchaseIx = await program.methods
+      .purchaseWithIntent(seed, Array.from(txSigHash), {
+        ...intent,
+        tickets: [hexToU8_8("2")],
+      })
+      .accounts({
+        lottery,
+        bundle: bundlePda(seed, txSigHash),
+        ticketRegistry: ticketRegistryPda(lottery),
+        playerTickets: playerTicketsPda(lottery, player.publicKey),
+        referral: null,
+        playerAccount: playerAccountPda(player.publicKey),
+        payer: admin,
+        paymentMint: null,
+        balanceVault: null,
+        vault: null,
+        tokenProgram: null,
+      })
+      .instruction();
+
+    await expectError(
+      sendAndConfirmTransaction(
+        provider.connection,
+        new Transaction()
+          .add(
+            Ed25519Program.createInstructionWithPrivateKey({
+              privateKey: player.secretKey,
+              message,
+            })
+          )
+          .add(purchaseIx),
+        [provider.wallet.payer]
+      ),
+      "IntentMismatch"
+    );
+  });
+});
@@ -0,0 +1,122 @@
+import * as anchor from "@coral-xyz/anchor";
+import {
+  SystemProgram,
+  Transaction,
+  sendAndConfirmTransaction,
+} from "@solana/web3.js";
+
+import { nanoid } from "nanoid";
+import { sha256 } from "@noble/hashes/sha2";
+import { expect } from "chai";
+
+import {
+  TICKET_PRICE,
+  admin,
+  bundlePda,
+  configurationPda,
+  createLottery,
+  ensureConfiguration,
+  expectError,
+  fetchLottery,
+  fundedKeypair,
+  hexToU8_8,
+  playerTicketsPda,
+  program,
+  provider,
+  ticketRegistryPda,
+} from "./helpers";
+
+// A multisig admin submits bundles through CPI, where the program cannot see a
+// top-level payment transfer: a `funder` signer then pays in-program instead.
+describe("ct-lotto-anchor purchase payments", () => {
+  before(ensureConfiguration);
+
+  const bundleIx = (
+    seed: string,
+    lottery: anchor.web3.PublicKey,
+    owner: anchor.web3.PublicKey,
+    codes: string[],
+    funder: anchor.web3.PublicKey | null
+  ) => {
+    const txSigHash = sha256(nanoid());
+
+    return program.methods
+      .createTransactionBundle(
+        seed,
+        Array.from(txSigHash),
+        owner,
+        codes.map(hexToU8_8),
+        null
+      )
+      .accounts({
+        configuration: configurationPda,
+        lottery,
+        bundle: bundlePda(seed, txSigHash),
+        ticketRegistry: ticketRegistryPda(lottery),
+        playerTickets: playerTicketsPda(lottery, owner),
+        referral: null,
+        admin,
+        payer: admin,
+        funder,
+      })
+      .instruction();
+  };
+
+  it("a funder pays the tickets in-program, without a preceding transfer", async () => {
+    const { seed, lottery } = await createLottery();
+    const funder = await fundedKeypair();
+    const owner = anchor.web3.Keypair.generate().publicKey;
+
+    const balanceBefore = await provider.connection.getBalance(funder.publicKey);
+
+    await sendAndConfirmTransaction(
+      provider.connection,
+      new Transaction().add(
+        await bundleIx(seed, lottery, owner, ["1", "2"], funder.publicKey)
+      ),
+      [provider.wallet.payer, funder]
+    );
+
+    const account = await fetchLottery(lottery);
+
+    expect(account.ticketsSold).to.equal(2);
+    expect(account.ticketRevenue.toNumber()).to.equal(TICKET_PRICE * 2);
+    expect(await provider.connection.getBalance(funder.publicKey)).to.equal(
+      balanceBefore - TICKET_PRICE * 2
+    );
+  });
+
+  it("rejects an unfunded bundle without a payment transfer", async () => {
+    const { seed, lottery } = await createLottery();
+
+    await expectError(
+      sendAndConfirmTransaction(
+        provider.connection,
+        new Transaction().add(await bundleIx(seed, lottery, admin, ["3"], null)),
+        [provider.wallet.payer]
+      ),
+      "PaymentNotFound"
+    );
+  });
+
+  it("rejects a payment transfer that does not cover the tickets", async () => {
+    const { seed, lottery } = await createLottery();
+
+    await expectError(
+      sendAndConfirmTransaction(
+        provider.connection,
+        new Transaction()
+          .add(
+            SystemProgram.transfer({
+              fromPubkey: admin,
+              toPubkey: lottery,
+              lamports: TICKET_PRICE,
+            })
+          )
+          .add(await bundleIx(seed, lottery, admin, ["4", "5"], null)),
+        [provider.wallet.payer]
+      ),
+      "PaymentMismatch"
+    );
+  });
+});
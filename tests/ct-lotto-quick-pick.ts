@@ -0,0 +1,134 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, SYSVAR_SLOT_HASHES_PUBKEY } from "@solana/web3.js";
+
+import { nanoid } from "nanoid";
+import { sha256 } from "@noble/hashes/sha2";
+import { expect } from "chai";
+
+import {
+  TICKET_PRICE,
+  bundlePda,
+  createLottery,
+  depositBalance,
+  ensureConfiguration,
+  expectError,
+  fetchLottery,
+  fundedKeypair,
+  playerAccountPda,
+  playerTicketsPda,
+  program,
+  quickPickPda,
+  ticketRegistryPda,
+  waitForSlotAfter,
+} from "./helpers";
+
+// Quick picks commit to a player secret, mixed on fulfilment with the hash of
+// the request slot, unknown when the secret was committed
+describe("ct-lotto-anchor quick pick", () => {
+  before(ensureConfiguration);
+
+  const request = (
+    lottery: anchor.web3.PublicKey,
+    player: Keypair,
+    txSigHash: Uint8Array,
+    count: number,
+    secret: Uint8Array
+  ) =>
+    program.methods
+      .requestQuickPick(
+        Array.from(txSigHash),
+        count,
+        { commitReveal: { commitment: Array.from(sha256(secret)) } },
+        null
+      )
+      .accounts({
+        lottery,
+        quickPick: quickPickPda(lottery, txSigHash),
+        playerAccount: playerAccountPda(player.publicKey),
+        randomnessAccount: null,
+        owner: player.publicKey,
+      })
+      .signers([player])
+      .rpc();
+
+  const fulfill = (
+    seed: string,
+    lottery: anchor.web3.PublicKey,
+    player: Keypair,
+    txSigHash: Uint8Array,
+    secret: Uint8Array
+  ) =>
+    program.methods
+      .fulfillQuickPick(seed, Array.from(secret))
+      .accounts({
+        lottery,
+        quickPick: quickPickPda(lottery, txSigHash),
+        bundle: bundlePda(seed, txSigHash),
+        ticketRegistry: ticketRegistryPda(lottery),
+        playerTickets: playerTicketsPda(lottery, player.publicKey),
+        referral: null,
+        playerAccount: playerAccountPda(player.publicKey),
+        randomnessAccount: null,
+        slotHashes: SYSVAR_SLOT_HASHES_PUBKEY,
+        owner: player.publicKey,
+        paymentMint: null,
+        balanceVault: null,
+        vault: null,
+        tokenProgram: null,
+      })
+      .signers([player])
+      .rpc();
+
+  it("picks distinct codes paid from the player's balance", async () => {
+    const { seed, lottery } = await createLottery();
+    const player = await fundedKeypair();
+    const txSigHash = sha256(nanoid());
+    const secret = sha256(nanoid());
+
+    await depositBalance(player, TICKET_PRICE * 3);
+    await request(lottery, player, txSigHash, 3, secret);
+
+    const { requestSlot } = await program.account.quickPickRequest.fetch(
+      quickPickPda(lottery, txSigHash)
+    );
+    await waitForSlotAfter(requestSlot.toNumber());
+
+    await expectError(
+      fulfill(seed, lottery, player, txSigHash, sha256("not the secret")),
+      "InvalidSecret"
+    );
+
+    await fulfill(seed, lottery, player, txSigHash, secret);
+
+    const bundle = await program.account.transactionBundle.fetch(
+      bundlePda(seed, txSigHash)
+    );
+    const codes = bundle.tickets
+      .slice(0, bundle.ticketCount)
+      .map((code) => Buffer.from(code).toString("hex"));
+
+    expect(new Set(codes).size).to.equal(3);
+    expect((await fetchLottery(lottery)).ticketsSold).to.equal(3);
+
+    const { balance } = await program.account.playerAccount.fetch(
+      playerAccountPda(player.publicKey)
+    );
+    expect(balance.toNumber()).to.equal(0);
+  });
+
+  it("refuses picks the balance cannot pay", async () => {
+    const { lottery } = await createLottery();
+    const player = await fundedKeypair();
+
+    await depositBalance(player, TICKET_PRICE);
+
+    await expectError(
+      request(lottery, player, sha256(nanoid()), 2, sha256(nanoid())),
+      "InsufficientBalance"
+    );
+    await expectError(
+      request(lottery, player, sha256(nanoid()), 0, sha256(nanoid())),
+      "InvalidQuickPickCount"
+    );
+  });
+});
@@ -0,0 +1,106 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { nanoid } from "nanoid";
+
+import {
+  TICKET_PRICE,
+  admin,
+  buyTickets,
+  closeAndReveal,
+  closeSales,
+  configurationPda,
+  createLottery,
+  ensureConfiguration,
+  expectError,
+  fetchLottery,
+  lotteryPda,
+  program,
+  tally,
+} from "./helpers";
+
+describe("ct-lotto-anchor raffle", () => {
+  before(ensureConfiguration);
+
+  it("draws exactly one winning ticket among those sold", async () => {
+    const { seed, lottery, secret } = await createLottery({
+      gameType: { raffle: {} },
+    });
+
+    const bundles = [
+      await buyTickets(seed, ["1", "2", "3"]),
+      await buyTickets(seed, ["4", "5"]),
+    ];
+
+    const { draws } = await closeAndReveal(lottery, secret);
+    expect(draws[0].winningTicketIndex).to.be.lessThan(5);
+
+    for (const bundle of bundles) {
+      await tally(lottery, bundle);
+    }
+
+    const account = await fetchLottery(lottery);
+    expect(account.draws[0].tierWinningTickets[0]).to.equal(1);
+  });
+
+  it("draws a distinct ticket per draw while there are enough tickets", async () => {
+    const { seed, lottery, secret } = await createLottery({
+      gameType: { raffle: {} },
+      drawPercentages: [50, 50],
+    });
+
+    await buyTickets(seed, ["1", "2"]);
+
+    const { draws } = await closeAndReveal(lottery, secret);
+    const indices = draws.map((d) => d.winningTicketIndex);
+
+    expect(indices.sort()).to.deep.equal([0, 1]);
+  });
+
+  it("cancels a raffle that sold no ticket", async () => {
+    const { lottery } = await createLottery({
+      gameType: { raffle: {} },
+      minTicketsToDraw: 0,
+    });
+
+    await closeSales(lottery);
+
+    expect((await fetchLottery(lottery)).cancelled).to.be.true;
+  });
+
+  it("refuses to draw a raffle from the feed", async () => {
+    const seed = nanoid(8);
+
+    await expectError(
+      program.methods
+        .createLotteryPda(
+          seed,
+          new anchor.BN(TICKET_PRICE),
+          "0",
+          "f",
+          2,
+          false,
+          [],
+          { raffle: {} },
+          Buffer.from([]),
+          0,
+          0,
+          1,
+          0,
+          new anchor.BN(0),
+          null
+        )
+        .accounts({
+          configuration: configurationPda,
+          lottery: lotteryPda(seed),
+          admin,
+          payer: admin,
+          switchboardFeedBtcBlockDecimal:
+            anchor.web3.Keypair.generate().publicKey,
+          paymentMint: null,
+          vault: null,
+        })
+        .rpc(),
+      "RaffleRequiresCommitReveal"
+    );
+  });
+});
@@ -0,0 +1,105 @@
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+
+import {
+  TICKET_PRICE,
+  buyFromBalance,
+  buyTickets,
+  closeAndReveal,
+  createLottery,
+  depositBalance,
+  ensureConfiguration,
+  expectError,
+  fetchLottery,
+  fundedKeypair,
+  program,
+  provider,
+  referralPda,
+} from "./helpers";
+
+describe("ct-lotto-anchor referrals", () => {
+  before(ensureConfiguration);
+
+  // 10% of the platform fee, which is 2% of the ticket revenue
+  const referralBps = 1000;
+  const referralOf = (amount: number) =>
+    Math.floor((((amount * 2) / 100) * referralBps) / 10_000);
+
+  it("credits the referrer of bundle and balance purchases alike", async () => {
+    const { seed, lottery } = await createLottery({ referralBps });
+    const referrer = anchor.web3.Keypair.generate().publicKey;
+    const player = await fundedKeypair();
+
+    await buyTickets(seed, ["1", "2"], { referrer });
+
+    await depositBalance(player, TICKET_PRICE * 2);
+    await buyFromBalance(seed, player, ["3", "4"], { referrer });
+
+    const referral = await program.account.referralRewards.fetch(
+      referralPda(lottery, referrer)
+    );
+    const expected = referralOf(TICKET_PRICE * 2) * 2;
+
+    expect(referral.referrer.equals(referrer)).to.be.true;
+    expect(referral.accrued.toNumber()).to.equal(expected);
+    expect(
+      (await fetchLottery(lottery)).referralFeesAccrued.toNumber()
+    ).to.equal(expected);
+  });
+
+  it("rejects a player referring their own balance purchase", async () => {
+    const { seed } = await createLottery({ referralBps });
+    const player = await fundedKeypair();
+
+    await depositBalance(player, TICKET_PRICE);
+
+    await expectError(
+      buyFromBalance(seed, player, ["5"], { referrer: player.publicKey }),
+      "InvalidReferrer"
+    );
+  });
+
+  it("pays the referrer out once the lottery is drawn", async () => {
+    const { seed, lottery, secret } = await createLottery({ referralBps });
+    const referrer = await fundedKeypair();
+    const referral = referralPda(lottery, referrer.publicKey);
+
+    await buyTickets(seed, ["1", "2"], { referrer: referrer.publicKey });
+
+    const claim = () =>
+      program.methods
+        .claimReferralRewards()
+        .accounts({
+          lottery,
+          referral,
+          referrer: referrer.publicKey,
+          paymentMint: null,
+          vault: null,
+          referrerTokenAccount: null,
+          tokenProgram: null,
+        })
+        .signers([referrer])
+        .rpc();
+
+    // earnings are only final once the prize pool is fixed by a draw
+    await expectError(claim(), "WinnerNotDrawn");
+
+    await closeAndReveal(lottery, secret);
+
+    const balanceBefore = await provider.connection.getBalance(
+      referrer.publicKey
+    );
+    await claim();
+
+    const expected = referralOf(TICKET_PRICE * 2);
+
+    expect(await provider.connection.getBalance(referrer.publicKey)).to.equal(
+      balanceBefore + expected
+    );
+    expect(
+      (await program.account.referralRewards.fetch(referral)).claimed.toNumber()
+    ).to.equal(expected);
+
+    await expectError(claim(), "NothingToClaim");
+  });
+});
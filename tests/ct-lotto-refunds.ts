@@ -0,0 +1,96 @@
+import { SYSVAR_SLOT_HASHES_PUBKEY } from "@solana/web3.js";
+import { expect } from "chai";
+
+import {
+  TICKET_PRICE,
+  admin,
+  buyFromBalance,
+  buyTickets,
+  closeSales,
+  configurationPda,
+  createLottery,
+  depositBalance,
+  ensureConfiguration,
+  expectError,
+  fetchLottery,
+  fundedKeypair,
+  playerAccountPda,
+  program,
+  provider,
+  refund,
+} from "./helpers";
+
+// Closing sales below `minTicketsToDraw` cancels the lottery: it can no longer
+// draw, and every bundle is refunded in full
+describe("ct-lotto-anchor cancellation and refunds", () => {
+  before(ensureConfiguration);
+
+  it("cancels a lottery below the participation threshold", async () => {
+    const { seed, lottery, secret } = await createLottery({
+      minTicketsToDraw: 3,
+    });
+
+    await buyTickets(seed, ["1", "2"]);
+    await closeSales(lottery);
+
+    expect((await fetchLottery(lottery)).cancelled).to.be.true;
+
+    await expectError(
+      program.methods
+        .revealDraw(Array.from(secret))
+        .accounts({
+          configuration: configurationPda,
+          lottery,
+          slotHashes: SYSVAR_SLOT_HASHES_PUBKEY,
+          admin,
+        })
+        .rpc(),
+      "LotteryCancelled"
+    );
+  });
+
+  it("refunds a cancelled lottery in full, once per bundle", async () => {
+    const { seed, lottery } = await createLottery({ minTicketsToDraw: 3 });
+    const owner = await fundedKeypair();
+
+    const bundle = await buyTickets(seed, ["1", "2"], { owner });
+    await closeSales(lottery);
+
+    const balanceBefore = await provider.connection.getBalance(owner.publicKey);
+
+    // the fee is never deducted from the refunds of a cancelled lottery
+    await refund(lottery, bundle, owner.publicKey, { deductFee: true });
+
+    expect(await provider.connection.getBalance(owner.publicKey)).to.equal(
+      balanceBefore + TICKET_PRICE * 2
+    );
+    expect((await fetchLottery(lottery)).refundsSettled).to.equal(1);
+
+    await expectError(
+      refund(lottery, bundle, owner.publicKey),
+      "DuplicateRequest"
+    );
+  });
+
+  it("refunds a balance purchase to that balance only", async () => {
+    const { seed, lottery } = await createLottery({ minTicketsToDraw: 3 });
+    const player = await fundedKeypair();
+
+    await depositBalance(player, TICKET_PRICE);
+    const bundle = await buyFromBalance(seed, player, ["1"]);
+    await closeSales(lottery);
+
+    await expectError(
+      refund(lottery, bundle, player.publicKey),
+      "PlayerAccountRequired"
+    );
+
+    await refund(lottery, bundle, player.publicKey, { toBalance: true });
+
+    const { balance } = await program.account.playerAccount.fetch(
+      playerAccountPda(player.publicKey)
+    );
+
+    expect(balance.toNumber()).to.equal(TICKET_PRICE);
+  });
+});
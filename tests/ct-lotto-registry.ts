@@ -0,0 +1,66 @@
+import { expect } from "chai";
+
+import {
+  TICKET_PRICE,
+  buyFromBalance,
+  buyTickets,
+  createLottery,
+  depositBalance,
+  ensureConfiguration,
+  expectError,
+  fetchLottery,
+  fundedKeypair,
+} from "./helpers";
+
+// Lotteries with unique codes sell every code once, whichever path buys it
+describe("ct-lotto-anchor ticket registry", () => {
+  before(ensureConfiguration);
+
+  it("rejects a code already sold in another bundle", async () => {
+    const { seed, lottery } = await createLottery();
+
+    await buyTickets(seed, ["1", "2"]);
+    await expectError(buyTickets(seed, ["3", "2"]), "DuplicateTicketCode");
+
+    expect((await fetchLottery(lottery)).ticketsSold).to.equal(2);
+  });
+
+  it("rejects a balance purchase of a code already sold", async () => {
+    const { seed, lottery } = await createLottery();
+    const player = await fundedKeypair();
+
+    await buyTickets(seed, ["a"]);
+    await depositBalance(player, TICKET_PRICE * 2);
+
+    await expectError(
+      buyFromBalance(seed, player, ["a", "b"]),
+      "DuplicateTicketCode"
+    );
+
+    await buyFromBalance(seed, player, ["b"]);
+
+    expect((await fetchLottery(lottery)).ticketsSold).to.equal(2);
+  });
+
+  it("does not sell unique codes before the registry exists", async () => {
+    const { seed } = await createLottery({ ticketRegistry: false });
+
+    await expectError(buyTickets(seed, ["1"]), "TicketRegistryRequired");
+  });
+
+  it("sells shared codes without a registry", async () => {
+    const { seed, lottery } = await createLottery({ allowSharedTickets: true });
+
+    await buyTickets(seed, ["1", "1"]);
+    await buyTickets(seed, ["1"]);
+
+    expect((await fetchLottery(lottery)).ticketsSold).to.equal(3);
+  });
+
+  it("rejects unique code ranges larger than a registry", async () => {
+    await expectError(
+      createLottery({ startHex: "0", endHex: "10000" }),
+      "UniqueCodeRangeTooLarge"
+    );
+  });
+});
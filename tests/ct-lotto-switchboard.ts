@@ -0,0 +1,106 @@
+import { Keypair, PublicKey, SYSVAR_SLOT_HASHES_PUBKEY } from "@solana/web3.js";
+import { expect } from "chai";
+
+import {
+  TICKET_PRICE,
+  admin,
+  buyTickets,
+  closeSales,
+  configurationPda,
+  createLottery,
+  ensureConfiguration,
+  expectError,
+  fetchLottery,
+  fundedKeypair,
+  hexToU8_8,
+  program,
+  provider,
+  refund,
+} from "./helpers";
+
+// BTC block height feed on devnet
+const SB_FEED = new PublicKey("Bj1QJT7JM2jp3AbPB74vEpvUmik1KD6xZbC9rbQEDTft");
+
+describe("ct-lotto-anchor Switchboard draws", () => {
+  before(ensureConfiguration);
+
+  const drawWinner = (lottery: PublicKey, feed: PublicKey, value: string) =>
+    program.methods
+      .drawWinner(hexToU8_8(value))
+      .accounts({
+        configuration: configurationPda,
+        lottery,
+        switchboardFeedBtcBlockDecimal: feed,
+        admin,
+      })
+      .rpc();
+
+  it("draws only from the lottery's own feed", async () => {
+    const feed = Keypair.generate().publicKey;
+    const { seed, lottery, secret } = await createLottery({ feed });
+
+    await buyTickets(seed, ["1"]);
+    await closeSales(lottery);
+
+    await expectError(
+      drawWinner(lottery, Keypair.generate().publicKey, "1"),
+      "SwitchboardFeedMismatch"
+    );
+
+    await expectError(
+      program.methods
+        .revealDraw(Array.from(secret))
+        .accounts({
+          configuration: configurationPda,
+          lottery,
+          slotHashes: SYSVAR_SLOT_HASHES_PUBKEY,
+          admin,
+        })
+        .rpc(),
+      "NotCommitRevealDraw"
+    );
+  });
+
+  it("does not draw a commit-reveal lottery from a feed", async () => {
+    const { seed, lottery } = await createLottery();
+
+    await buyTickets(seed, ["1"]);
+    await closeSales(lottery);
+
+    await expectError(drawWinner(lottery, SB_FEED, "1"), "CommitRevealDraw");
+  });
+
+  it("rejects a value that differs from the feed", async function () {
+    // the feed only exists on devnet
+    if (!(await provider.connection.getAccountInfo(SB_FEED))) {
+      this.skip();
+    }
+
+    const { seed, lottery } = await createLottery({ feed: SB_FEED });
+
+    await buyTickets(seed, ["0"]);
+    await closeSales(lottery);
+
+    await expectError(drawWinner(lottery, SB_FEED, "0"), "FeedValueMismatch");
+  });
+
+  it("refunds the tickets of an undrawn lottery, which then cannot draw", async () => {
+    const feed = Keypair.generate().publicKey;
+    const { seed, lottery } = await createLottery({ feed });
+    const owner = await fundedKeypair();
+
+    const bundle = await buyTickets(seed, ["0", "1", "3", "f"], { owner });
+    await closeSales(lottery);
+
+    const balanceBefore = await provider.connection.getBalance(owner.publicKey);
+
+    await refund(lottery, bundle, owner.publicKey);
+
+    expect(await provider.connection.getBalance(owner.publicKey)).to.equal(
+      balanceBefore + TICKET_PRICE * 4
+    );
+    expect((await fetchLottery(lottery)).refundsSettled).to.equal(1);
+
+    await expectError(drawWinner(lottery, feed, "f"), "DuplicateRequest");
+  });
+});
@@ -0,0 +1,79 @@
+import { expect } from "chai";
+
+import {
+  TICKET_PRICE,
+  buyFromBalance,
+  createLottery,
+  createMint,
+  depositBalance,
+  ensureConfiguration,
+  expectError,
+  fetchLottery,
+  fundedKeypair,
+  mintTo,
+  playerAccountPda,
+  program,
+  tokenAccountAddress,
+  tokenBalance,
+  withdrawBalance,
+} from "./helpers";
+
+// Token lotteries price tickets in base units of their mint and hold the
+// revenue in a vault owned by the lottery
+describe("ct-lotto-anchor token lotteries", () => {
+  before(ensureConfiguration);
+
+  // createLottery prices token lotteries at TICKET_PRICE base units of the mint
+  const price = TICKET_PRICE;
+
+  it("creates the lottery vault and fills it from token balances", async () => {
+    const mint = await createMint();
+    const player = await fundedKeypair();
+    const wallet = await mintTo(mint, player.publicKey, price * 3);
+
+    const { seed, lottery } = await createLottery({ paymentMint: mint });
+    const vault = tokenAccountAddress(lottery, mint);
+
+    const account = await fetchLottery(lottery);
+    expect(account.paymentMint.equals(mint)).to.be.true;
+    expect(await tokenBalance(vault)).to.equal(0);
+
+    await depositBalance(player, price * 3, mint);
+    expect(await tokenBalance(wallet)).to.equal(0);
+
+    await buyFromBalance(seed, player, ["1", "2"], { mint });
+
+    expect(await tokenBalance(vault)).to.equal(price * 2);
+    expect((await fetchLottery(lottery)).ticketRevenue.toNumber()).to.equal(
+      price * 2
+    );
+
+    // the leftover goes back to the player's token account
+    await withdrawBalance(player, price, mint);
+
+    expect(await tokenBalance(wallet)).to.equal(price);
+    expect(
+      (
+        await program.account.playerAccount.fetch(
+          playerAccountPda(player.publicKey, mint)
+        )
+      ).balance.toNumber()
+    ).to.equal(0);
+  });
+
+  it("keeps SOL and token balances apart", async () => {
+    const mint = await createMint();
+    const player = await fundedKeypair();
+
+    await mintTo(mint, player.publicKey, price);
+    await depositBalance(player, price, mint);
+
+    // the SOL balance of the player is still empty
+    const { seed } = await createLottery();
+
+    await expectError(
+      buyFromBalance(seed, player, ["1"]),
+      "AccountNotInitialized"
+    );
+  });
+});
@@ -0,0 +1,519 @@
+import * as anchor from "@coral-xyz/anchor";
+import { CtLottoAnchor } from "../target/types/ct_lotto_anchor";
+import {
+  Keypair,
+  LAMPORTS_PER_SOL,
+  PublicKey,
+  SYSVAR_SLOT_HASHES_PUBKEY,
+  SystemProgram,
+  Transaction,
+  TransactionInstruction,
+  sendAndConfirmTransaction,
+} from "@solana/web3.js";
+
+import { nanoid } from "nanoid";
+import { sha256 } from "@noble/hashes/sha2";
+import { expect } from "chai";
+
+// Shared setup of the scenario test files: each one creates its own lotteries
+// under the global configuration, which `ensureConfiguration` (re)creates.
+
+anchor.setProvider(anchor.AnchorProvider.env());
+
+export const program = anchor.workspace
+  .ctLottoAnchor as anchor.Program<CtLottoAnchor>;
+export const provider = anchor.getProvider() as anchor.AnchorProvider;
+export const admin = provider.wallet.publicKey;
+
+export const TICKET_PRICE = LAMPORTS_PER_SOL * 0.001;
+
+export const TOKEN_PROGRAM_ID = new PublicKey(
+  "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"
+);
+export const ASSOCIATED_TOKEN_PROGRAM_ID = new PublicKey(
+  "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL"
+);
+
+// Ticket codes are stored as the big-endian bytes of their hex value,
+// e.g. "1a2b3c" -> [0, 0, 0, 0, 0, 0x1a, 0x2b, 0x3c] (see ticket_code_from_hex)
+export const hexToU8_8 = (hex: string) => {
+  hex = hex.replace(/^0x/, "").padStart(16, "0");
+
+  const bytes = [];
+
+  for (let i = 0; i < hex.length; i += 2) {
+    bytes.push(parseInt(hex.substring(i, i + 2), 16));
+  }
+
+  return bytes;
+};
+
+// Resolves once the promise fails with the given program error; raw
+// transactions fail with the error code only in their logs
+export const expectError = async (promise: Promise<unknown>, code: string) => {
+  try {
+    await promise;
+  } catch (err) {
+    const logs: string[] = err.logs ?? err.transactionLogs ?? [];
+
+    expect(
+      [err.error?.errorCode?.code, String(err), ...logs].join("\n")
+    ).to.contain(code);
+    return;
+  }
+
+  expect.fail(`expected ${code}`);
+};
+
+export const pda = (...seeds: (Buffer | Uint8Array)[]) =>
+  PublicKey.findProgramAddressSync(seeds, program.programId)[0];
+
+export const configurationPda = pda(Buffer.from("configuration"));
+
+export const lotteryPda = (seed: string) =>
+  pda(Buffer.from("lottery"), Buffer.from(seed));
+
+export const bundlePda = (lotterySeed: string, txSigHash: Uint8Array) =>
+  pda(Buffer.from("bundle"), Buffer.from(lotterySeed), txSigHash);
+
+export const playerTicketsPda = (lottery: PublicKey, owner: PublicKey) =>
+  pda(Buffer.from("player"), lottery.toBuffer(), owner.toBuffer());
+
+export const playerAccountPda = (owner: PublicKey, mint = PublicKey.default) =>
+  pda(Buffer.from("player_account"), owner.toBuffer(), mint.toBuffer());
+
+export const ticketRegistryPda = (lottery: PublicKey) =>
+  pda(Buffer.from("registry"), lottery.toBuffer());
+
+export const quickPickPda = (lottery: PublicKey, txSigHash: Uint8Array) =>
+  pda(Buffer.from("quick_pick"), lottery.toBuffer(), txSigHash);
+
+export const referralPda = (lottery: PublicKey, referrer: PublicKey) =>
+  pda(Buffer.from("referrer"), lottery.toBuffer(), referrer.toBuffer());
+
+// Associated token account of `owner` (a wallet or a PDA)
+export const tokenAccountAddress = (owner: PublicKey, mint: PublicKey) =>
+  PublicKey.findProgramAddressSync(
+    [owner.toBuffer(), TOKEN_PROGRAM_ID.toBuffer(), mint.toBuffer()],
+    ASSOCIATED_TOKEN_PROGRAM_ID
+  )[0];
+
+export const sleep = (ms: number) =>
+  new Promise((resolve) => setTimeout(resolve, ms));
+
+// Waits until `slot` is in the past, so its hash is in SlotHashes
+export const waitForSlotAfter = async (slot: number) => {
+  while ((await provider.connection.getSlot()) <= slot) {
+    await sleep(400);
+  }
+};
+
+export const ensureConfiguration = async () => {
+  if (await provider.connection.getAccountInfo(configurationPda)) {
+    return;
+  }
+
+  await program.methods
+    .createConfigurationPda()
+    .accounts({ configuration: configurationPda, admin, payer: admin })
+    .rpc();
+};
+
+export const fundedKeypair = async (sol = 1) => {
+  const keypair = Keypair.generate();
+
+  await sendAndConfirmTransaction(
+    provider.connection,
+    new Transaction().add(
+      SystemProgram.transfer({
+        fromPubkey: admin,
+        toPubkey: keypair.publicKey,
+        lamports: sol * LAMPORTS_PER_SOL,
+      })
+    ),
+    [provider.wallet.payer]
+  );
+
+  return keypair;
+};
+
+// Creates a mint of the admin; built from raw instructions, as the tests
+// have no SPL token client
+export const createMint = async (decimals = 6) => {
+  const mint = Keypair.generate();
+  const space = 82;
+
+  // InitializeMint2: tag, decimals, mint authority, no freeze authority
+  const data = Buffer.alloc(67);
+  data.writeUInt8(20, 0);
+  data.writeUInt8(decimals, 1);
+  admin.toBuffer().copy(data, 2);
+
+  await sendAndConfirmTransaction(
+    provider.connection,
+    new Transaction()
+      .add(
+        SystemProgram.createAccount({
+          fromPubkey: admin,
+          newAccountPubkey: mint.publicKey,
+          lamports:
+            await provider.connection.getMinimumBalanceForRentExemption(space),
+          space,
+          programId: TOKEN_PROGRAM_ID,
+        })
+      )
+      .add(
+        new TransactionInstruction({
+          programId: TOKEN_PROGRAM_ID,
+          keys: [{ pubkey: mint.publicKey, isSigner: false, isWritable: true }],
+          data,
+        })
+      ),
+    [provider.wallet.payer, mint]
+  );
+
+  return mint.publicKey;
+};
+
+// Mints `amount` base units to the associated token account of `owner`,
+// creating it if needed; returns that account
+export const mintTo = async (
+  mint: PublicKey,
+  owner: PublicKey,
+  amount: number
+) => {
+  const tokenAccount = tokenAccountAddress(owner, mint);
+
+  // MintTo: tag, amount (u64 LE)
+  const data = Buffer.alloc(9);
+  data.writeUInt8(7, 0);
+  data.writeBigUInt64LE(BigInt(amount), 1);
+
+  await sendAndConfirmTransaction(
+    provider.connection,
+    new Transaction()
+      .add(
+        // CreateIdempotent
+        new TransactionInstruction({
+          programId: ASSOCIATED_TOKEN_PROGRAM_ID,
+          keys: [
+            { pubkey: admin, isSigner: true, isWritable: true },
+            { pubkey: tokenAccount, isSigner: false, isWritable: true },
+            { pubkey: owner, isSigner: false, isWritable: false },
+            { pubkey: mint, isSigner: false, isWritable: false },
+            {
+              pubkey: SystemProgram.programId,
+              isSigner: false,
+              isWritable: false,
+            },
+            { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+          ],
+          data: Buffer.from([1]),
+        })
+      )
+      .add(
+        new TransactionInstruction({
+          programId: TOKEN_PROGRAM_ID,
+          keys: [
+            { pubkey: mint, isSigner: false, isWritable: true },
+            { pubkey: tokenAccount, isSigner: false, isWritable: true },
+            { pubkey: admin, isSigner: true, isWritable: false },
+          ],
+          data,
+        })
+      ),
+    [provider.wallet.payer]
+  );
+
+  return tokenAccount;
+};
+
+export const tokenBalance = async (tokenAccount: PublicKey) =>
+  Number(
+    (await provider.connection.getTokenAccountBalance(tokenAccount)).value
+      .amount
+  );
+
+export type LotteryOptions = {
+  startHex?: string;
+  endHex?: string;
+  allowSharedTickets?: boolean;
+  prizeTiers?: { matchDigits: number; poolPercentage: number }[];
+  gameType?: object;
+  drawPercentages?: number[];
+  maxTicketsTotal?: number;
+  maxTicketsPerWallet?: number;
+  minTicketsToDraw?: number;
+  referralBps?: number;
+  claimWindow?: number;
+  // unique codes only: false to leave the lottery without its ticket registry
+  ticketRegistry?: boolean;
+  // draws from this Switchboard feed instead of by commit-reveal
+  feed?: PublicKey;
+  // priced in this SPL token instead of SOL
+  paymentMint?: PublicKey;
+};
+
+// Lottery drawn by commit-reveal, priced in SOL, with a ticket registry unless
+// codes are shared; returns its seed, address and draw secret
+export const createLottery = async (options: LotteryOptions = {}) => {
+  const seed = nanoid(8);
+  const lottery = lotteryPda(seed);
+  const secret = sha256(nanoid());
+
+  await program.methods
+    .createLotteryPda(
+      seed,
+      new anchor.BN(TICKET_PRICE),
+      options.startHex ?? "0",
+      options.endHex ?? "f",
+      2,
+      options.allowSharedTickets ?? false,
+      options.prizeTiers ?? [],
+      options.gameType ?? { codeMatch: {} },
+      Buffer.from(options.drawPercentages ?? []),
+      options.maxTicketsTotal ?? 0,
+      options.maxTicketsPerWallet ?? 0,
+      options.minTicketsToDraw ?? 1,
+      options.referralBps ?? 0,
+      new anchor.BN(options.claimWindow ?? 0),
+      options.feed ? null : Array.from(sha256(secret))
+    )
+    .accounts({
+      configuration: configurationPda,
+      lottery,
+      admin,
+      payer: admin,
+      switchboardFeedBtcBlockDecimal: options.feed ?? null,
+      paymentMint: options.paymentMint ?? null,
+      vault: options.paymentMint
+        ? tokenAccountAddress(lottery, options.paymentMint)
+        : null,
+      tokenProgram: options.paymentMint ? TOKEN_PROGRAM_ID : null,
+      associatedTokenProgram: options.paymentMint
+        ? ASSOCIATED_TOKEN_PROGRAM_ID
+        : null,
+    })
+    .rpc();
+
+  // unique codes sell only once their registry exists
+  if (!options.allowSharedTickets && options.ticketRegistry !== false) {
+    await program.methods
+      .createTicketRegistry()
+      .accounts({
+        configuration: configurationPda,
+        lottery,
+        ticketRegistry: ticketRegistryPda(lottery),
+        admin,
+        payer: admin,
+      })
+      .rpc();
+  }
+
+  return { seed, lottery, secret };
+};
+
+export const fetchLottery = (lottery: PublicKey) =>
+  program.account.lottery.fetch(lottery);
+
+// Pays for and records a bundle of `owner`'s tickets; returns the bundle address
+export const buyTickets = async (
+  seed: string,
+  codes: string[],
+  options: {
+    owner?: Keypair;
+    referrer?: PublicKey;
+  } = {}
+) => {
+  const lottery = lotteryPda(seed);
+  const { ticketRegistry } = await fetchLottery(lottery);
+  const owner = options.owner?.publicKey ?? admin;
+  const txSigHash = sha256(nanoid());
+  const bundle = bundlePda(seed, txSigHash);
+
+  const bundleIx = await program.methods
+    .createTransactionBundle(
+      seed,
+      Array.from(txSigHash),
+      owner,
+      codes.map(hexToU8_8),
+      options.referrer ?? null
+    )
+    .accounts({
+      configuration: configurationPda,
+      lottery,
+      bundle,
+      ticketRegistry: ticketRegistry.equals(PublicKey.default)
+        ? null
+        : ticketRegistry,
+      playerTickets: playerTicketsPda(lottery, owner),
+      referral: options.referrer ? referralPda(lottery, options.referrer) : null,
+      admin,
+      payer: admin,
+      funder: null,
+    })
+    .instruction();
+
+  // the program checks the transfer right before the bundle instruction
+  const tx = new Transaction()
+    .add(
+      SystemProgram.transfer({
+        fromPubkey: owner,
+        toPubkey: lottery,
+        lamports: TICKET_PRICE * codes.length,
+      })
+    )
+    .add(bundleIx);
+
+  const signers = [provider.wallet.payer];
+
+  if (options.owner) {
+    signers.push(options.owner);
+  }
+
+  await sendAndConfirmTransaction(provider.connection, tx, signers);
+
+  return bundle;
+};
+
+// Token accounts of a player balance in `mint`, all null for SOL balances
+const balanceTokenAccounts = (owner: PublicKey, mint?: PublicKey) => ({
+  paymentMint: mint ?? null,
+  balanceVault: mint
+    ? tokenAccountAddress(playerAccountPda(owner, mint), mint)
+    : null,
+  tokenProgram: mint ? TOKEN_PROGRAM_ID : null,
+});
+
+// Deposits SOL, or tokens of `mint`, into `owner`'s player balance
+export const depositBalance = (
+  owner: Keypair,
+  amount: number,
+  mint?: PublicKey
+) =>
+  program.methods
+    .depositBalance(new anchor.BN(amount))
+    .accounts({
+      playerAccount: playerAccountPda(owner.publicKey, mint),
+      owner: owner.publicKey,
+      ...balanceTokenAccounts(owner.publicKey, mint),
+      ownerTokenAccount: mint ? tokenAccountAddress(owner.publicKey, mint) : null,
+      associatedTokenProgram: mint ? ASSOCIATED_TOKEN_PROGRAM_ID : null,
+    })
+    .signers([owner])
+    .rpc();
+
+// Withdraws SOL, or tokens of `mint`, from `owner`'s player balance
+export const withdrawBalance = (
+  owner: Keypair,
+  amount: number,
+  mint?: PublicKey
+) =>
+  program.methods
+    .withdrawBalance(new anchor.BN(amount))
+    .accounts({
+      playerAccount: playerAccountPda(owner.publicKey, mint),
+      owner: owner.publicKey,
+      ...balanceTokenAccounts(owner.publicKey, mint),
+      ownerTokenAccount: mint ? tokenAccountAddress(owner.publicKey, mint) : null,
+    })
+    .signers([owner])
+    .rpc();
+
+// Buys tickets from `owner`'s player balance, in the lottery's `mint` if it
+// is priced in tokens; returns the bundle address
+export const buyFromBalance = async (
+  seed: string,
+  owner: Keypair,
+  codes: string[],
+  options: {
+    referrer?: PublicKey;
+    mint?: PublicKey;
+  } = {}
+) => {
+  const lottery = lotteryPda(seed);
+  const { ticketRegistry } = await fetchLottery(lottery);
+  const txSigHash = sha256(nanoid());
+  const bundle = bundlePda(seed, txSigHash);
+
+  await program.methods
+    .purchaseFromBalance(
+      seed,
+      Array.from(txSigHash),
+      codes.map(hexToU8_8),
+      options.referrer ?? null
+    )
+    .accounts({
+      lottery,
+      bundle,
+      ticketRegistry: ticketRegistry.equals(PublicKey.default)
+        ? null
+        : ticketRegistry,
+      playerTickets: playerTicketsPda(lottery, owner.publicKey),
+      referral: options.referrer
+        ? referralPda(lottery, options.referrer)
+        : null,
+      playerAccount: playerAccountPda(owner.publicKey, options.mint),
+      owner: owner.publicKey,
+      ...balanceTokenAccounts(owner.publicKey, options.mint),
+      vault: options.mint ? tokenAccountAddress(lottery, options.mint) : null,
+    })
+    .signers([owner])
+    .rpc();
+
+  return bundle;
+};
+
+export const closeSales = (lottery: PublicKey) =>
+  program.methods
+    .closeLottery()
+    .accounts({ configuration: configurationPda, lottery, admin })
+    .rpc();
+
+// Closes sales and reveals every draw of a commit-reveal lottery
+export const closeAndReveal = async (lottery: PublicKey, secret: Uint8Array) => {
+  await closeSales(lottery);
+  await waitForSlotAfter(await provider.connection.getSlot());
+
+  await program.methods
+    .revealDraw(Array.from(secret))
+    .accounts({
+      configuration: configurationPda,
+      lottery,
+      slotHashes: SYSVAR_SLOT_HASHES_PUBKEY,
+      admin,
+    })
+    .rpc();
+
+  return fetchLottery(lottery);
+};
+
+// Refunds a bundle of a lottery priced in SOL, to the owner's player balance
+// for bundles bought from it
+export const refund = (
+  lottery: PublicKey,
+  bundle: PublicKey,
+  owner: PublicKey,
+  options: { deductFee?: boolean; toBalance?: boolean } = {}
+) =>
+  program.methods
+    .refundTransactionBundle(options.deductFee ?? false)
+    .accounts({
+      configuration: configurationPda,
+      lottery,
+      bundle,
+      owner,
+      playerAccount: options.toBalance ? playerAccountPda(owner) : null,
+      admin,
+      paymentMint: null,
+      vault: null,
+      ownerTokenAccount: null,
+      tokenProgram: null,
+    })
+    .rpc();
+
+export const tally = (lottery: PublicKey, bundle: PublicKey) =>
+  program.methods
+    .tallyTransactionBundle()
+    .accounts({ configuration: configurationPda, lottery, bundle, admin })
+    .rpc();